@@ -15,6 +15,7 @@ import { invoke } from "@tauri-apps/api/core";
 import IntegrationLogo from "./icons";
 import Patreon from "@/components/icons/patreon";
 import StreamLabs from "@/components/icons/streamlabs";
+import Discord from "@/components/icons/discord";
 
 interface IntegrationStatusProps extends React.ComponentProps<"div"> {}
 const ERROR_LIMIT = 20;
@@ -151,6 +152,19 @@ export default function IntegrationStatusBar({
     });
   }, [integrations]);
 
+  useEffect(() => {
+    for (const api of [Api.StreamLabs, Api.Patreon, Api.Discord]) {
+      if (!integrations.includes(api)) continue;
+      invoke<boolean>("get_config_bool", {
+        key: `auth.${api.toLowerCase()}.auto_connect`,
+      }).then((connect) => {
+        if (connect) {
+          handleConnectToIntegration(api);
+        }
+      });
+    }
+  }, [integrations]);
+
   useEffect(() => {
     for (const api of apis) {
       handleIntegrationStatusCheck(Api[api]);
@@ -199,6 +213,13 @@ export default function IntegrationStatusBar({
         primaryColor="#80F5D2"
         onClick={() => handleOnClick(Api.StreamLabs)}
       />
+      <IntegrationLogo
+        name="Discord"
+        status={statuses.Discord}
+        Logo={Discord}
+        primaryColor="#5865F2"
+        onClick={() => handleOnClick(Api.Discord)}
+      />
     </div>
   );
 }
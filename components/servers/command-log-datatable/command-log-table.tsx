@@ -96,6 +96,11 @@ export const columns: ColumnDef<CommandLog>[] = [
         </Button>
       );
     },
+    cell: ({ row, getValue }) => {
+      const name = getValue<string>();
+      const pick = row.original.pick;
+      return <div>{pick ? `${name} → ${pick}` : name}</div>;
+    },
   },
 
   {
@@ -159,6 +164,11 @@ export const columns: ColumnDef<CommandLog>[] = [
         // @ts-expect-error using custom command
         table.options.meta.resendEvent(command_log);
       };
+      const blockUser = () => {
+        const command_log = row.original;
+        // @ts-expect-error using custom command
+        table.options.meta.blockUser(command_log);
+      };
       return (
         <ButtonGroup className="mx-auto">
           <Button
@@ -175,6 +185,13 @@ export const columns: ColumnDef<CommandLog>[] = [
           >
             Event
           </Button>
+          <Button
+            variant="secondary"
+            className="bg-destructive/50 hover:bg-destructive active:bg-destructive/50"
+            onClick={() => blockUser()}
+          >
+            Block User
+          </Button>
         </ButtonGroup>
       );
     },
@@ -274,6 +291,11 @@ export default function CommandLogTable({ selectedServer }: DataTableProps) {
           .then(console.log)
           .catch(console.warn);
       },
+      blockUser: function f(commandLog: CommandLog) {
+        invoke("block_command_log_user", { commandLog })
+          .then(console.log)
+          .catch(console.warn);
+      },
     },
   });
   // send_command_to_server
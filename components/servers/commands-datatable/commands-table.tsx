@@ -123,6 +123,10 @@ export const columns: ColumnDef<Command>[] = [
           return value.command.relative_path;
         case "Inline":
           return value.command;
+        case "MysteryBox":
+          return value.command.picks
+            .map((pick) => `${pick.command} (${pick.weight})`)
+            .join(", ");
         default:
           return "Error";
       }
@@ -1,17 +1,19 @@
 use std::{
     io::{self, Write},
+    path::Path,
     sync::Arc,
 };
 use tauri::App;
 use tauri_plugin_cli::Matches;
 use tracing::error;
 
-use crate::integration::TwitchApiConnection;
+use crate::integration::{streamlabs::profile, TwitchApiConnection, YouTubeApiConnection};
 
 pub async fn handle_cli_matches(
     matches: Matches,
     app: &App,
     twitch_integration: Arc<futures::lock::Mutex<TwitchApiConnection>>,
+    youtube_integration: Arc<futures::lock::Mutex<YouTubeApiConnection>>,
 ) {
     let mut exit: bool = false;
     for (name, arg_data) in matches.args.into_iter() {
@@ -36,6 +38,10 @@ pub async fn handle_cli_matches(
     }
 
     if let Some(subcommands) = matches.subcommand {
+        if subcommands.name == "import" {
+            import_profiles(subcommands.matches);
+            std::process::exit(0);
+        }
         let matches = subcommands.matches;
         let mut token_buf = Vec::new();
         let _ = writeln!(token_buf, "Auth Tokens:\n");
@@ -68,7 +74,14 @@ pub async fn handle_cli_matches(
                 "youtube" => {
                     if arg_data.value.as_bool().expect("Tried to parse as boolean") {
                         exit = true;
-                        let _ = writeln!(token_buf, "YouTube: To be Implemented");
+                        match youtube_integration.lock().await.authenticate(false).await {
+                            Ok(token) => {
+                                let _ = writeln!(token_buf, "YouTube: {}", token.access_token);
+                            }
+                            Err(e) => {
+                                error!("Failed to get YouTube Token: {e:?}")
+                            }
+                        }
                     }
                 }
                 _ => todo!("Implement"),
@@ -82,3 +95,28 @@ pub async fn handle_cli_matches(
         std::process::exit(0)
     }
 }
+
+/// `import <profiles>... --server <name>`, prints a report for each profile.
+fn import_profiles(matches: Matches) {
+    let arg = |name: &str| matches.args.get(name).map(|a| a.value.clone());
+    if let Some(help) = arg("help") {
+        println!("{}", help.as_str().unwrap_or_default());
+        return;
+    }
+    let profiles = match arg("profiles") {
+        Some(serde_json::Value::Array(profiles)) => profiles,
+        Some(serde_json::Value::String(profile)) => vec![profile.into()],
+        _ => Vec::new(),
+    };
+    let server = arg("server")
+        .and_then(|s| s.as_str().map(str::to_string))
+        .unwrap_or_default();
+    let enabled = arg("enable").and_then(|e| e.as_bool()).unwrap_or(false);
+    let dry_run = arg("dry-run").and_then(|d| d.as_bool()).unwrap_or(false);
+    for path in profiles.iter().filter_map(|p| p.as_str()) {
+        match profile::import_profile(Path::new(path), &server, enabled, dry_run) {
+            Ok(report) => println!("{report}"),
+            Err(e) => error!("Failed to import {path}: {e:?}"),
+        }
+    }
+}
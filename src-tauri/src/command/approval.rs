@@ -0,0 +1,311 @@
+//! Commands from triggers with [`requires_approval`](GameServerTrigger::requires_approval)
+//! wait here until a moderator approves or rejects them.
+use std::{
+    sync::{Arc, LazyLock},
+    time::SystemTime,
+};
+
+use anyhow::{bail, Result};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use tracing::{info, warn};
+use twitch_api::helix::points::CustomRewardRedemptionStatus;
+use uuid::Uuid;
+
+use super::{
+    command_logs::{CommandLog, CommandStatus, COMMAND_LOGS},
+    Command, GameServerTrigger, Outcome,
+};
+use crate::{
+    integration::{feedback, IntegrationEvent},
+    AsyncMutex, TwitchApiConnection,
+};
+
+pub static APPROVAL_QUEUE: LazyLock<Arc<AsyncMutex<ApprovalQueue>>> =
+    LazyLock::new(|| Arc::new(AsyncMutex::new(ApprovalQueue::default())));
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingApproval {
+    pub id: Uuid,
+    pub received: SystemTime,
+    pub command: Command,
+    pub trigger: GameServerTrigger,
+    pub event: IntegrationEvent,
+    /// Values sent in place of the variables, editable before approving.
+    pub variables: IndexMap<String, String>,
+    /// The event's channel point redemption was already fulfilled or refunded
+    /// for another of its triggers, so it can no longer be refunded.
+    pub reported: bool,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ApprovalQueue {
+    pending: Vec<PendingApproval>,
+}
+
+impl ApprovalQueue {
+    /// Adds the command to the end of the queue, returning its id.
+    pub fn push(
+        &mut self,
+        command: Command,
+        trigger: GameServerTrigger,
+        event: IntegrationEvent,
+        variables: IndexMap<String, String>,
+    ) -> Uuid {
+        let id = Uuid::new_v4();
+        self.pending.push(PendingApproval {
+            id,
+            received: SystemTime::now(),
+            command,
+            trigger,
+            event,
+            variables,
+            reported: false,
+        });
+        id
+    }
+
+    /// Oldest first.
+    pub fn pending(&self) -> &[PendingApproval] {
+        &self.pending
+    }
+
+    /// Replaces the values of the given variables, ignoring names the command
+    /// doesn't have.
+    pub fn set_variables(&mut self, id: Uuid, variables: IndexMap<String, String>) -> Result<()> {
+        let Some(pending) = self.pending.iter_mut().find(|p| p.id == id) else {
+            bail!("Pending approval not found.");
+        };
+        for (name, value) in variables {
+            match pending.variables.get_mut(&name) {
+                Some(v) => *v = value,
+                None => warn!("{} has no variable named {}", pending.command.name, name),
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the command from the queue.
+    pub fn take(&mut self, id: Uuid) -> Option<PendingApproval> {
+        let position = self.pending.iter().position(|p| p.id == id)?;
+        Some(self.pending.remove(position))
+    }
+
+    /// Marks the pending commands of the event as [`reported`](PendingApproval::reported).
+    pub fn reported(&mut self, event: &IntegrationEvent) {
+        for pending in self.pending.iter_mut().filter(|p| &p.event == event) {
+            pending.reported = true;
+        }
+    }
+}
+
+/// Sends the pending command to its server, with `variables` replacing the queued values.
+pub async fn approve(id: Uuid, variables: Option<IndexMap<String, String>>) -> Result<()> {
+    let mut pending = {
+        let mut queue = APPROVAL_QUEUE.lock().await;
+        if let Some(variables) = variables {
+            queue.set_variables(id, variables)?;
+        }
+        match queue.take(id) {
+            Some(p) => p,
+            None => bail!("Pending approval not found."),
+        }
+    };
+    COMMAND_LOGS.lock().await.add_log(
+        CommandLog::new(
+            pending.command.clone(),
+            pending.trigger.clone(),
+            pending.event.clone(),
+            pending.event.username(),
+            pending.event.message().map(|s| s.to_string()),
+        )
+        .with_status(CommandStatus::Approved)
+        .with_variables(pending.variables.clone()),
+    );
+    info!("Approved \"{}\"", pending.command.name);
+    let sent = pending
+        .command
+        .send(pending.trigger.server(), &pending.event, &pending.variables)
+        .await;
+    if !pending.reported {
+        Outcome::from_sent(sent.is_ok()).report(&pending.event);
+        APPROVAL_QUEUE.lock().await.reported(&pending.event);
+    }
+    sent.map(|_| ())
+}
+
+/// Drops the pending command. With `refund`, a channel point redemption is
+/// cancelled, returning the viewer's points, otherwise it is fulfilled.
+pub async fn reject(id: Uuid, refund: bool, twitch: &mut TwitchApiConnection) -> Result<()> {
+    let pending = {
+        let mut queue = APPROVAL_QUEUE.lock().await;
+        match queue.pending().iter().find(|p| p.id == id) {
+            None => bail!("Pending approval not found."),
+            Some(PendingApproval {
+                event: IntegrationEvent::ChannelPoint(_),
+                reported: true,
+                ..
+            }) if refund => bail!("The redemption was already fulfilled, it can't be refunded."),
+            Some(_) => {}
+        }
+        queue.take(id).expect("Pending approval is queued.")
+    };
+    let refunded = match (&pending.event, refund) {
+        (IntegrationEvent::ChannelPoint(redemption), true) => match twitch
+            .update_redemption_status(redemption, CustomRewardRedemptionStatus::Canceled)
+            .await
+        {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Failed to refund {}: {:?}", redemption.event_id, e);
+                false
+            }
+        },
+        _ => false,
+    };
+    if !refund && !pending.reported {
+        feedback::redemption_outcome(&pending.event, true);
+    }
+    if refunded || !refund {
+        APPROVAL_QUEUE.lock().await.reported(&pending.event);
+    }
+    COMMAND_LOGS.lock().await.add_log(
+        CommandLog::new(
+            pending.command.clone(),
+            pending.trigger,
+            pending.event.clone(),
+            pending.event.username(),
+            pending.event.message().map(|s| s.to_string()),
+        )
+        .with_status(CommandStatus::Rejected { refunded })
+        .with_variables(pending.variables),
+    );
+    info!("Rejected \"{}\"", pending.command.name);
+    feedback::interaction_notice(
+        &pending.event,
+        format!("A moderator rejected {}.", pending.command.name),
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn pending_approvals() -> Result<Vec<PendingApproval>, String> {
+    Ok(APPROVAL_QUEUE.lock().await.pending().to_vec())
+}
+
+#[tauri::command]
+pub async fn update_approval_variables(
+    id: Uuid,
+    variables: IndexMap<String, String>,
+) -> Result<(), String> {
+    APPROVAL_QUEUE
+        .lock()
+        .await
+        .set_variables(id, variables)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn approve_command(
+    id: Uuid,
+    variables: Option<IndexMap<String, String>>,
+) -> Result<(), String> {
+    approve(id, variables).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn reject_command(
+    id: Uuid,
+    refund: bool,
+    twitch_mutex: State<'_, Arc<futures::lock::Mutex<TwitchApiConnection>>>,
+) -> Result<(), String> {
+    let mut twitch = twitch_mutex.lock().await;
+    reject(id, refund, &mut twitch)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        command::{Prefix, RconCommand, RconCommandLua, Trigger, Variable},
+        servers::GameServer,
+    };
+    use rstest::{fixture, rstest};
+
+    #[fixture]
+    fn queue() -> ApprovalQueue {
+        ApprovalQueue::default()
+    }
+
+    fn push(queue: &mut ApprovalQueue, msg: &str) -> Uuid {
+        let command = RconCommand {
+            prefix: Prefix::SC,
+            lua_command: RconCommandLua::Inline("boom(AMOUNT);".to_string()),
+            variables: Variable::from_config("AMOUNT:int=1").unwrap(),
+        };
+        let trigger = Trigger::Chat {
+            pattern: "boom".to_string(),
+            case_sensitive: false,
+        };
+        queue.push(
+            Command::new("boom", command),
+            GameServerTrigger::new(GameServer::default(), trigger),
+            IntegrationEvent::Chat {
+                msg: msg.into(),
+                author: "Legend".into(),
+                chatter: Default::default(),
+            },
+            IndexMap::from([("AMOUNT".to_string(), "5".to_string())]),
+        )
+    }
+
+    #[rstest]
+    fn take_pending(mut queue: ApprovalQueue) {
+        let first = push(&mut queue, "boom 5");
+        let second = push(&mut queue, "boom 5");
+        assert_eq!(queue.pending().len(), 2);
+        assert!(queue.take(Uuid::new_v4()).is_none());
+        assert_eq!(queue.take(second).unwrap().id, second);
+        assert_eq!(queue.pending()[0].id, first);
+    }
+
+    #[rstest]
+    fn edit_variables(mut queue: ApprovalQueue) {
+        let id = push(&mut queue, "boom 5");
+        queue
+            .set_variables(
+                id,
+                IndexMap::from([
+                    ("AMOUNT".to_string(), "2".to_string()),
+                    ("MISSING".to_string(), "1".to_string()),
+                ]),
+            )
+            .unwrap();
+        assert_eq!(
+            queue.pending()[0].variables,
+            IndexMap::from([("AMOUNT".to_string(), "2".to_string())])
+        );
+        assert!(queue
+            .set_variables(Uuid::new_v4(), IndexMap::new())
+            .is_err());
+    }
+
+    #[rstest]
+    fn reported_event(mut queue: ApprovalQueue) {
+        push(&mut queue, "boom 5");
+        push(&mut queue, "boom 5");
+        push(&mut queue, "boom 6");
+        let event = queue.pending()[0].event.clone();
+        assert!(queue.pending().iter().all(|p| !p.reported));
+        queue.reported(&event);
+        let reported = queue
+            .pending()
+            .iter()
+            .map(|p| p.reported)
+            .collect::<Vec<_>>();
+        assert_eq!(reported, vec![true, true, false]);
+    }
+}
@@ -1,5 +1,6 @@
 use std::time::SystemTime;
 
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -16,6 +17,33 @@ pub struct CommandLog {
     pub(super) event: IntegrationEvent,
     pub(super) username: String,
     pub(super) message: Option<String>,
+    #[serde(default)]
+    pub(super) status: CommandStatus,
+    /// Values that replaced the variables from the message.
+    #[serde(default)]
+    pub(super) variables: IndexMap<String, String>,
+    /// Command a [`MysteryBox`](crate::command::MysteryBox) picked, `command`
+    /// then holds the picked Lua so a resend sends the same.
+    #[serde(default)]
+    pub(super) pick: Option<String>,
+}
+
+/// What happened to the command.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CommandStatus {
+    #[default]
+    Sent,
+    /// Waiting in the approval queue.
+    AwaitingApproval,
+    /// Approved by a moderator and sent.
+    Approved,
+    /// Rejected by a moderator.
+    Rejected { refunded: bool },
+    /// Not sent as the viewer is blocked or not on the allowlist, see
+    /// [`user_policy`](crate::command::user_policy).
+    Blocked { reason: String },
+    /// Not sent as the viewer went over the command's [`quota`](crate::command::quota).
+    OverQuota { reason: String },
 }
 
 impl Ord for CommandLog {
@@ -49,13 +77,36 @@ impl CommandLog {
             username,
             message,
             event,
+            status: CommandStatus::default(),
+            variables: IndexMap::new(),
+            pick: None,
         }
     }
 
-    /// Clone of the log with the time set to [`SystemTime::now()`]
+    pub fn with_status(mut self, status: CommandStatus) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn with_variables(mut self, variables: IndexMap<String, String>) -> Self {
+        self.variables = variables;
+        self
+    }
+
+    /// Replaces the command with the one opened from a mystery box, see
+    /// [`Command::open`].
+    pub fn with_pick(mut self, command: Command, pick: String) -> Self {
+        self.command = command;
+        self.pick = Some(pick);
+        self
+    }
+
+    /// Clone of the log with the time set to [`SystemTime::now()`] and the status
+    /// to [`CommandStatus::Sent`], as it is sent again.
     pub fn repeat_log(&self) -> Self {
         let mut log = self.clone();
         log.time = SystemTime::now();
+        log.status = CommandStatus::Sent;
         log
     }
 
@@ -83,6 +134,18 @@ impl CommandLog {
         &self.event
     }
 
+    pub fn status(&self) -> &CommandStatus {
+        &self.status
+    }
+
+    pub fn variables(&self) -> &IndexMap<String, String> {
+        &self.variables
+    }
+
+    pub fn pick(&self) -> Option<&str> {
+        self.pick.as_deref()
+    }
+
     pub fn from_server(command: &Command, server: &GameServer) -> Self {
         let trigger = GameServerTrigger::new(server.clone(), Trigger::Server);
         let event = IntegrationEvent::Server;
@@ -7,6 +7,7 @@ use std::{
 use serde::{Deserialize, Serialize};
 
 use crate::command::command_logs::CommandLog;
+use crate::localhost::overlay::OVERLAY;
 use crate::{Arc, AsyncMutex};
 
 pub static COMMAND_LOGS: LazyLock<Arc<AsyncMutex<CommandLogs>>> =
@@ -21,7 +22,10 @@ impl CommandLogs {
     }
 
     /// Adds the [`CommandLog`] to end of [`Vec`], if not added in order then [`CommandLogs::sort()`] is run.
+    ///
+    /// The log is also shown on the overlays.
     pub fn add_log(&mut self, command_log: CommandLog) {
+        OVERLAY.record_trigger(&command_log);
         self.0.push(command_log);
         if !self.is_sorted() {
             self.sort()
@@ -95,7 +99,8 @@ mod tests {
 
     use crate::{
         command::{
-            Command, GameServerTrigger, Prefix, RconCommand, RconCommandLua, Trigger, Variable,
+            command_logs::CommandStatus, Command, GameServerTrigger, Prefix, RconCommand,
+            RconCommandLua, Trigger, Variable,
         },
         integration::IntegrationEvent,
         servers::{Game, GameServer},
@@ -153,6 +158,7 @@ mod tests {
         IntegrationEvent::Chat {
             msg: "Hello".into(),
             author: "Legend".into(),
+            chatter: Default::default(),
         }
     }
 
@@ -176,6 +182,9 @@ mod tests {
                 username: "Legend".into(),
                 message: None,
                 event: chat_event.clone(),
+                status: Default::default(),
+                variables: Default::default(),
+                pick: None,
             };
             command_logs.push(command_log);
         }
@@ -196,4 +205,19 @@ mod tests {
 
         assert_eq!(logs_since.len(), half);
     }
+
+    #[rstest]
+    fn repeat_is_sent(test_command_logs: CommandLogs) {
+        let log =
+            test_command_logs
+                .first()
+                .unwrap()
+                .clone()
+                .with_status(CommandStatus::OverQuota {
+                    reason: "Legend is over 1 per minute".to_string(),
+                });
+        let repeat = log.repeat_log();
+        assert_eq!(repeat.status, CommandStatus::Sent);
+        assert!(repeat.time() > log.time());
+    }
 }
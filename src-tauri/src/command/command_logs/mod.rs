@@ -1,7 +1,7 @@
 pub mod command_log;
 use std::sync::Arc;
 
-pub use command_log::CommandLog;
+pub use command_log::{CommandLog, CommandStatus};
 mod command_logs;
 pub use command_logs::*;
 use tauri::State;
@@ -45,7 +45,11 @@ pub async fn resend_command(command_log: CommandLog) -> Result<(), String> {
             None => return Err("Server not connected to.".to_string()),
         };
     match connection
-        .send_command(command.tx_string(command_log.message(), &command_log.username()))
+        .send_command(command.rcon_lua.command_with_values(
+            command_log.message(),
+            command_log.username(),
+            command_log.variables(),
+        ))
         .await
     {
         Ok(_r) => {
@@ -4,13 +4,15 @@ use anyhow::{bail, Result};
 use config::{Map, Value, ValueKind};
 use serde::{Deserialize, Serialize};
 
-use super::settings::ScriptSettings;
+use super::{settings::ScriptSettings, MysteryBox};
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(tag = "commandType", content = "command")]
 pub enum RconCommandLua {
     File(LuaFile),
     Inline(String),
+    /// Sends one of several other commands instead, see [`Command::open`](super::Command::open).
+    MysteryBox(MysteryBox),
     Other,
 }
 
@@ -20,6 +22,7 @@ impl RconCommandLua {
         match self {
             File(_) => stringify!(File).to_string(),
             Inline(_) => stringify!(Inline).to_string(),
+            MysteryBox(_) => stringify!(MysteryBox).to_string(),
             Other => stringify!(Other).to_string(),
         }
     }
@@ -30,6 +33,11 @@ impl From<RconCommandLua> for ValueKind {
         match lua {
             RconCommandLua::File(lua_file) => Self::from(lua_file.relative_path.to_str()),
             RconCommandLua::Inline(lua) => Self::from(lua),
+            RconCommandLua::MysteryBox(mystery_box) => {
+                let mut map = Map::new();
+                mystery_box.insert_into(&mut map);
+                Self::from(map)
+            }
             RconCommandLua::Other => todo!("impl From<RconLuaCommand> for ValueKind::Other"),
         }
     }
@@ -65,6 +73,7 @@ impl TryFrom<Map<String, Value>> for RconCommandLua {
                 Some(lua) => Ok(Inline(lua.to_string())),
                 None => bail!("No 'inline' property specified."),
             },
+            "mysterybox" => Ok(MysteryBox(super::MysteryBox::try_from(&value)?)),
             _ => {
                 bail!("Invalid command type.")
             }
@@ -114,6 +123,7 @@ impl RconCommandLua {
         match self {
             File(lua_file) => lua_file.contents(),
             Inline(command) => Ok(command.clone()),
+            MysteryBox(_) => bail!("A mystery box has no Lua of its own, open it first."),
             _ => todo!("Rcon command not implemented"),
         }
     }
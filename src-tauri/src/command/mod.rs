@@ -1,18 +1,24 @@
+pub mod approval;
 pub mod command_logs;
 use anyhow::{bail, Result};
 use config::{Map, Value, ValueKind};
+use indexmap::IndexMap;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use settings::ScriptSettings;
 use std::{
     collections::HashMap,
     sync::{Arc, LazyLock, Mutex},
+    time::SystemTime,
 };
 use tracing::{debug, error, info, instrument, trace, warn};
 
+pub mod pause;
+pub mod quota;
 mod runner;
 pub mod settings;
 pub mod trigger;
+pub mod user_policy;
 pub use runner::Runner;
 pub use trigger::{GameServerTrigger, Trigger};
 mod command_type;
@@ -23,12 +29,28 @@ mod command_lua;
 pub use command_lua::{LuaFile, RconCommandLua};
 mod rcon;
 pub use rcon::RconCommand;
+mod outcome;
+pub use outcome::Outcome;
+mod reply;
+pub use reply::Reply;
+mod mystery_box;
+pub use mystery_box::{MysteryBox, Pick};
+mod reward;
+pub use reward::Reward;
 mod variable;
-pub use variable::Variable;
+pub use variable::{escape_lua, Variable, VariableType};
 
 use crate::{
-    command::command_logs::{CommandLog, COMMAND_LOGS},
-    integration::IntegrationEvent,
+    command::{
+        approval::APPROVAL_QUEUE,
+        command_logs::{CommandLog, CommandStatus, COMMAND_LOGS},
+        quota::{OverQuota, Quota, QuotaExceeded, QuotaUsage, QUOTA_USAGE},
+        user_policy::USER_POLICY,
+    },
+    integration::{
+        feedback::{self, Feedback},
+        IntegrationEvent,
+    },
     servers::{GameServer, CONNECTIONS},
 };
 
@@ -40,6 +62,18 @@ pub struct Command {
     pub name: String,
     pub rcon_lua: RconCommand,
     pub server_triggers: Vec<GameServerTrigger>,
+    /// Channel point reward synced to Twitch.
+    #[serde(default)]
+    pub reward: Option<Reward>,
+    /// Chat message sent after the command runs.
+    #[serde(default)]
+    pub reply: Reply,
+    /// Ids or names of the only viewers who may trigger it, anyone when empty.
+    #[serde(default)]
+    pub allowed_users: Vec<String>,
+    /// Limits on how much each viewer can trigger it.
+    #[serde(default)]
+    pub quota: Option<Quota>,
 }
 #[allow(dead_code)]
 impl Command {
@@ -48,6 +82,10 @@ impl Command {
             name: name.into(),
             rcon_lua,
             server_triggers: Vec::new(),
+            reward: None,
+            reply: Reply::default(),
+            allowed_users: Vec::new(),
+            quota: None,
         }
     }
 
@@ -68,6 +106,50 @@ impl Command {
             name: name.into(),
             rcon_lua: rcon_lua.into(),
             server_triggers: server_triggers.into(),
+            reward: None,
+            reply: Reply::default(),
+            allowed_users: Vec::new(),
+            quota: None,
+        }
+    }
+
+    pub fn with_reward(self, reward: Option<Reward>) -> Self {
+        Self { reward, ..self }
+    }
+
+    pub fn with_reply(self, reply: Reply) -> Self {
+        Self { reply, ..self }
+    }
+
+    pub fn with_allowed_users(self, allowed_users: Vec<String>) -> Self {
+        Self {
+            allowed_users,
+            ..self
+        }
+    }
+
+    pub fn with_quota(self, quota: Option<Quota>) -> Self {
+        Self { quota, ..self }
+    }
+
+    /// Stores the Twitch id of the reward and fills it in on the channel point
+    /// triggers matching the reward's title.
+    pub fn set_reward_id(&mut self, id: &str) {
+        let Some(reward) = self.reward.as_mut() else {
+            return;
+        };
+        reward.id = Some(id.to_string());
+        for server_trigger in self.server_triggers.iter_mut() {
+            if let Trigger::ChannelPointRewardRedeemed {
+                title,
+                id: trigger_id,
+                ..
+            } = &mut server_trigger.trigger
+            {
+                if *title == reward.title {
+                    *trigger_id = id.to_string();
+                }
+            }
         }
     }
 
@@ -107,27 +189,270 @@ impl Command {
         self.rcon_lua.command(message, username)
     }
 
-    pub async fn handle_event(&mut self, event: &IntegrationEvent) {
-        for trigger in self.server_triggers.clone() {
-            if let Some(server) = trigger.event_triggered(event) {
-                COMMAND_LOGS.lock().await.add_log(CommandLog::new(
+    /// Sends the command for every trigger the event matches, the [`Outcome`] is
+    /// reported by the [`Runner`] once all commands handled the event. The viewer
+    /// is checked against the [`USER_POLICY`], the command's [`Quota`] and
+    /// `viewer_quota` once, however many triggers match.
+    pub async fn handle_event(
+        &mut self,
+        event: &IntegrationEvent,
+        viewer_quota: Option<&Quota>,
+    ) -> Outcome {
+        let triggered = self
+            .server_triggers
+            .iter()
+            .filter_map(|trigger| Some((trigger.clone(), trigger.event_triggered(event)?)))
+            .collect_vec();
+        if triggered.is_empty() {
+            return Outcome::Ignored;
+        }
+        let logs = triggered
+            .iter()
+            .map(|(trigger, _)| {
+                CommandLog::new(
                     self.clone(),
                     trigger.clone(),
                     event.clone(),
                     event.username(),
                     event.message().map(|s| s.to_string()),
-                ));
-                info!("Server {} was triggered by {:?}", server.name, event);
-                let mut connection_lock = CONNECTIONS.lock().await;
-                if let Some(connection) = connection_lock.get_mut(&server) {
-                    let _ = connection
-                        .send_command(self.tx_string(event.message(), &event.username()))
-                        .await;
-
-                    info!("Sent \"{}\" to \"{}\" server.", self.name, &server.name);
+                )
+            })
+            .collect_vec();
+        if let Err(denial) = USER_POLICY.lock().await.check(self, event) {
+            info!("\"{}\" was not sent: {}", self.name, denial);
+            feedback::interaction_notice(event, format!("{} was not sent: {denial}", self.name));
+            let mut command_logs = COMMAND_LOGS.lock().await;
+            for log in logs {
+                command_logs.add_log(log.with_status(CommandStatus::Blocked {
+                    reason: denial.to_string(),
+                }));
+            }
+            return Outcome::Failed;
+        }
+        let over_quota = match self.use_quota(event, viewer_quota).await {
+            Ok(over_quota) => over_quota,
+            Err(exceeded) => {
+                info!("\"{}\" was not sent: {}", self.name, exceeded);
+                feedback::interaction_notice(
+                    event,
+                    format!("{} was not sent, you are {exceeded}.", self.name),
+                );
+                let mut command_logs = COMMAND_LOGS.lock().await;
+                for log in logs {
+                    command_logs.add_log(log.with_status(CommandStatus::OverQuota {
+                        reason: format!("{} is {exceeded}", event.username()),
+                    }));
+                }
+                return Outcome::Failed;
+            }
+        };
+        let mut outcome = Outcome::default();
+        for ((trigger, server), log) in triggered.iter().zip(logs) {
+            let (mut command, log) = match self.open() {
+                Ok(Some((command, pick))) => {
+                    info!("\"{}\" picked \"{}\"", self.name, pick);
+                    (command.clone(), log.with_pick(command, pick))
+                }
+                Ok(None) => (self.clone(), log),
+                Err(e) => {
+                    error!("Failed to open \"{}\": {e}", self.name);
+                    feedback::interaction_notice(event, format!("{} failed: {e}", self.name));
+                    outcome = outcome.and(Outcome::Failed);
+                    continue;
+                }
+            };
+            let values = event.values();
+            let variables = command.logged_variables(event.message(), &values);
+            if trigger.requires_approval() || over_quota {
+                APPROVAL_QUEUE.lock().await.push(
+                    command,
+                    trigger.clone(),
+                    event.clone(),
+                    variables.clone(),
+                );
+                COMMAND_LOGS.lock().await.add_log(
+                    log.with_status(CommandStatus::AwaitingApproval)
+                        .with_variables(variables),
+                );
+                info!(
+                    "\"{}\" for \"{}\" server is awaiting approval.",
+                    self.name, &server.name
+                );
+                feedback::interaction_notice(
+                    event,
+                    format!("{} is waiting for a moderator to approve it.", self.name),
+                );
+                outcome = outcome.and(Outcome::AwaitingApproval);
+                continue;
+            }
+            COMMAND_LOGS
+                .lock()
+                .await
+                .add_log(log.with_variables(variables));
+            info!("Server {} was triggered by {:?}", server.name, event);
+            let sent = command.send(server, event, &values).await;
+            outcome = outcome.and(Outcome::from_sent(sent.is_ok()));
+        }
+        if self.quota.is_some()
+            && !over_quota
+            && matches!(outcome, Outcome::Sent | Outcome::AwaitingApproval)
+        {
+            quota::record(event, &self.name).await;
+        }
+        outcome
+    }
+
+    /// For a [`MysteryBox`], this command with the Lua and variables of the command
+    /// it picked, and the pick's name. [`None`] for any other command.
+    pub fn open(&self) -> Result<Option<(Command, String)>> {
+        let RconCommandLua::MysteryBox(mystery_box) = &self.rcon_lua.lua_command else {
+            return Ok(None);
+        };
+        let Some(pick) = mystery_box.open(&self.name) else {
+            bail!("\"{}\" has nothing to pick.", self.name);
+        };
+        let Some(picked) = Command::get(&pick.command) else {
+            bail!(
+                "\"{}\" picked \"{}\", which isn't a command.",
+                self.name,
+                pick.command
+            );
+        };
+        if let RconCommandLua::MysteryBox(_) = picked.rcon_lua.lua_command {
+            bail!(
+                "\"{}\" picked \"{}\", which is another mystery box.",
+                self.name,
+                pick.command
+            );
+        }
+        let opened = Self {
+            rcon_lua: picked.rcon_lua,
+            ..self.clone()
+        };
+        Ok(Some((opened, pick.command)))
+    }
+
+    /// Checks the viewer's [`Quota`] for this command and `viewer_quota`. Over
+    /// either it is [`Err`] to drop it, or `Ok(true)` when it waits for approval
+    /// instead and isn't counted. The event is counted once it is handled, by
+    /// [`handle_event`](Self::handle_event) and the [`Runner`].
+    async fn use_quota(
+        &self,
+        event: &IntegrationEvent,
+        viewer_quota: Option<&Quota>,
+    ) -> Result<bool, QuotaExceeded> {
+        let Some(key) = QuotaUsage::key(event) else {
+            return Ok(false);
+        };
+        let value_cents = quota::event_value_cents(event);
+        let now = SystemTime::now();
+        let usage = QUOTA_USAGE.lock().await;
+        let mut over_quota = false;
+        for (quota, command) in [
+            (self.quota.as_ref(), self.name.as_str()),
+            (viewer_quota, quota::ALL_COMMANDS),
+        ] {
+            let Some(quota) = quota else {
+                continue;
+            };
+            match usage.check(&key, command, quota, value_cents, now) {
+                Ok(()) => {}
+                Err(exceeded) if quota.over_quota == OverQuota::Approval => {
+                    info!("{} is {exceeded}, waiting for approval", event.username());
+                    over_quota = true;
+                }
+                Err(exceeded) => return Err(exceeded),
+            }
+        }
+        Ok(over_quota)
+    }
+
+    /// The variables read from the message with `values` replacing them, which
+    /// are logged so [`resend_command`](command_logs::resend_command) sends the same.
+    fn logged_variables(
+        &self,
+        message: Option<&str>,
+        values: &IndexMap<String, String>,
+    ) -> IndexMap<String, String> {
+        let mut variables = self.rcon_lua.variable_values(message);
+        for (name, value) in values {
+            if let Some(variable) = variables.get_mut(name) {
+                *variable = value.clone();
+            }
+        }
+        variables
+    }
+
+    /// Sends the command to the server, with `values` replacing the variables
+    /// from the message, then the [`Reply`] is sent.
+    pub async fn send(
+        &mut self,
+        server: &GameServer,
+        event: &IntegrationEvent,
+        values: &IndexMap<String, String>,
+    ) -> Result<String> {
+        let result = {
+            let mut connection_lock = CONNECTIONS.lock().await;
+            match connection_lock.get_mut(server) {
+                Some(connection) => {
+                    connection
+                        .send_command(self.rcon_lua.command_with_values(
+                            event.message(),
+                            &event.username(),
+                            values,
+                        ))
+                        .await
                 }
+                None => Err(anyhow::anyhow!(
+                    "\"{}\" server is not connected.",
+                    server.name
+                )),
+            }
+        };
+        match &result {
+            Ok(_) => info!("Sent \"{}\" to \"{}\" server.", self.name, &server.name),
+            Err(e) => error!(
+                "Failed to send \"{}\" to \"{}\" server: {:?}",
+                self.name, &server.name, e
+            ),
+        }
+        self.send_reply(event, values, &result);
+        result
+    }
+
+    /// Sends the reply for the outcome to chat, if there is a template for it.
+    /// Slash commands always get one, see [`Reply::interaction`].
+    fn send_reply(
+        &self,
+        event: &IntegrationEvent,
+        values: &IndexMap<String, String>,
+        result: &Result<String>,
+    ) {
+        let reply = match event {
+            IntegrationEvent::SlashCommand { .. } => self.reply.clone().or(Reply::interaction()),
+            _ => self.reply.clone(),
+        };
+        if reply.is_empty() {
+            return;
+        }
+        let mut reply_values = self.rcon_lua.variable_values(event.message());
+        reply_values.extend(values.clone());
+        reply_values.insert("USERNAME".to_string(), event.username());
+        reply_values.insert("COMMAND".to_string(), self.name.clone());
+        match result {
+            Ok(response) => {
+                reply_values.insert("RESPONSE".to_string(), response.trim().to_string());
+            }
+            Err(e) => {
+                reply_values.insert("ERROR".to_string(), e.to_string());
             }
         }
+        if let Some(message) = reply.message(result.is_ok(), &reply_values) {
+            feedback::send(Feedback::Reply {
+                event: event.clone(),
+                message,
+            });
+        }
     }
 
     pub fn contains_server_trigger(&self, server: &GameServer, trigger: &Trigger) -> bool {
@@ -208,8 +533,37 @@ impl From<Command> for Value {
                     ValueKind::from(command.rcon_lua.lua_command),
                 );
             }
+            stringify!(MysteryBox) => {
+                if let RconCommandLua::MysteryBox(mystery_box) = command.rcon_lua.lua_command {
+                    mystery_box.insert_into(&mut map);
+                }
+            }
             _ => {}
         }
+        if let Some(variables) = command.rcon_lua.variables.filter(|v| !v.is_empty()) {
+            map.insert(
+                "variables".to_string(),
+                ValueKind::from(variables.iter().join(",")),
+            );
+        }
+        if let Some(reward) = command.reward {
+            map.insert("reward".to_string(), Value::from(reward).kind);
+        }
+        if let Some(reply) = command.reply.success {
+            map.insert("reply".to_string(), ValueKind::from(reply));
+        }
+        if let Some(error_reply) = command.reply.error {
+            map.insert("error_reply".to_string(), ValueKind::from(error_reply));
+        }
+        if let Some(quota) = command.quota {
+            map.insert("quota".to_string(), Value::from(quota).kind);
+        }
+        if !command.allowed_users.is_empty() {
+            map.insert(
+                "allowed_users".to_string(),
+                ValueKind::from(command.allowed_users),
+            );
+        }
         if !command.server_triggers.is_empty() {
             map.insert(
                 "server_triggers".to_string(),
@@ -232,21 +586,23 @@ impl TryFrom<Map<String, Value>> for Command {
 
     fn try_from(command_config_map: Map<String, Value>) -> std::result::Result<Self, Self::Error> {
         let mut errors: Vec<anyhow::Error> = vec![];
-        let prefix = match Prefix::try_from(command_config_map.clone()) {
-            Ok(p) => p,
+        let lua_command = match RconCommandLua::try_from(command_config_map.clone()) {
+            Ok(lua) => lua,
             Err(e) => {
                 error!("{}", &e);
                 errors.push(e);
-                Prefix::default()
+                RconCommandLua::default()
             }
         };
 
-        let lua_command = match RconCommandLua::try_from(command_config_map.clone()) {
-            Ok(lua) => lua,
+        let prefix = match Prefix::try_from(command_config_map.clone()) {
+            Ok(p) => p,
+            // A mystery box sends with the prefix of the command it picks.
+            Err(_) if matches!(lua_command, RconCommandLua::MysteryBox(_)) => Prefix::default(),
             Err(e) => {
                 error!("{}", &e);
                 errors.push(e);
-                RconCommandLua::default()
+                Prefix::default()
             }
         };
 
@@ -271,6 +627,48 @@ impl TryFrom<Map<String, Value>> for Command {
                 None => vec![],
             };
 
+        let reward = match command_config_map.get("reward") {
+            Some(r) => match Reward::try_from(r.clone()) {
+                Ok(r) => Some(r),
+                Err(e) => {
+                    error!("Invalid reward: {}", &e);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let reply = Reply {
+            success: command_config_map
+                .get("reply")
+                .and_then(|r| r.clone().into_string().ok()),
+            error: command_config_map
+                .get("error_reply")
+                .and_then(|r| r.clone().into_string().ok()),
+        };
+
+        let quota = match command_config_map.get("quota") {
+            Some(q) => match Quota::try_from(q.clone()) {
+                Ok(q) => Some(q),
+                Err(e) => {
+                    error!("Invalid quota: {}", &e);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let allowed_users = match command_config_map.get("allowed_users") {
+            Some(a) => match a.clone().into_array() {
+                Ok(users) => users
+                    .into_iter()
+                    .filter_map(|u| u.into_string().ok())
+                    .collect(),
+                Err(e) => bail!(e),
+            },
+            None => vec![],
+        };
+
         if !errors.is_empty() {
             error!(
                 "{} error/s occued in conversion from config file: {:?}",
@@ -279,7 +677,11 @@ impl TryFrom<Map<String, Value>> for Command {
             );
             Err(errors.remove(0))
         } else {
-            Ok(Command::from_config("", rconcommand, server_triggers))
+            Ok(Command::from_config("", rconcommand, server_triggers)
+                .with_reward(reward)
+                .with_reply(reply)
+                .with_allowed_users(allowed_users)
+                .with_quota(quota))
         }
     }
 }
@@ -387,3 +789,40 @@ pub async fn server_trigger_commands(
 pub async fn commands() -> Result<Vec<Command>, String> {
     Ok(ScriptSettings::get_commands())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn config_keeps_variables() {
+        let rcon_lua = RconCommand {
+            prefix: Prefix::SC,
+            lua_command: RconCommandLua::Inline("game.print(MESSAGE .. COUNT)".to_string()),
+            variables: Variable::from_config("MESSAGE,COUNT:int=5").unwrap(),
+        };
+        let command = Command::from_config("config_keeps_variables", rcon_lua, vec![]);
+        let read = Command::try_from(Value::from(command.clone())).unwrap();
+        assert_eq!(read.rcon_lua.variables, command.rcon_lua.variables);
+    }
+
+    #[rstest]
+    fn logged_variables_use_values() {
+        let rcon_lua = RconCommand {
+            prefix: Prefix::SC,
+            lua_command: RconCommandLua::Inline("game.print(COUNT)".to_string()),
+            variables: Variable::from_config("COUNT:int=5").unwrap(),
+        };
+        let command = Command::from_config("logged_variables_use_values", rcon_lua, vec![]);
+        let values = IndexMap::from([
+            ("COUNT".to_string(), "7".to_string()),
+            ("BITS".to_string(), "100".to_string()),
+        ]);
+        let variables = command.logged_variables(None, &values);
+        assert_eq!(
+            variables,
+            IndexMap::from([("COUNT".to_string(), "7".to_string())])
+        );
+    }
+}
@@ -0,0 +1,238 @@
+//! A command that opens to one of several other commands, picked at random by
+//! weight, see `command_type = "MysteryBox"`.
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+};
+
+use anyhow::{bail, Result};
+use config::{Map, Value, ValueKind};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// The latest picks of each mystery box, by the box's command name.
+pub static MYSTERY_BOX_HISTORY: LazyLock<Mutex<HashMap<String, Vec<String>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// One of the commands in a [`MysteryBox`].
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Pick {
+    /// Name of the command.
+    pub command: String,
+    /// Chance relative to the other picks, 0 never picks it.
+    pub weight: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MysteryBox {
+    pub picks: Vec<Pick>,
+    /// How many of the latest picks can't be picked again, so the same command
+    /// doesn't come up twice in a row.
+    #[serde(default)]
+    pub exclude_last: usize,
+}
+
+impl MysteryBox {
+    /// Picks by weight, skipping the `recent` picks unless that leaves nothing.
+    /// `roll` gets the total weight and returns a number below it.
+    pub fn pick_with(&self, recent: &[String], roll: impl FnOnce(u64) -> u64) -> Option<&Pick> {
+        let weighted = || self.picks.iter().filter(|pick| pick.weight > 0);
+        let mut candidates: Vec<&Pick> = weighted()
+            .filter(|pick| !recent.contains(&pick.command))
+            .collect();
+        if candidates.is_empty() {
+            candidates = weighted().collect();
+        }
+        let total: u64 = candidates.iter().map(|pick| pick.weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = roll(total);
+        candidates
+            .into_iter()
+            .find(|pick| match roll.checked_sub(pick.weight) {
+                Some(rest) => {
+                    roll = rest;
+                    false
+                }
+                None => true,
+            })
+    }
+
+    pub fn pick(&self, recent: &[String]) -> Option<&Pick> {
+        self.pick_with(recent, |total| rand::rng().random_range(0..total))
+    }
+
+    /// Picks for the box named `name` and remembers the pick, see
+    /// [`exclude_last`](Self::exclude_last).
+    pub fn open(&self, name: &str) -> Option<Pick> {
+        let mut history = MYSTERY_BOX_HISTORY.lock().unwrap();
+        let recent = history.entry(name.to_string()).or_default();
+        let skip = recent.len().saturating_sub(self.exclude_last);
+        let pick = self.pick(&recent[skip..])?.clone();
+        recent.push(pick.command.clone());
+        let skip = recent.len().saturating_sub(self.exclude_last);
+        recent.drain(..skip);
+        Some(pick)
+    }
+
+    /// Writes `picks` and `exclude_last` into the command's table.
+    pub fn insert_into(self, map: &mut Map<String, ValueKind>) {
+        let picks: Vec<Value> = self
+            .picks
+            .into_iter()
+            .map(|pick| {
+                let mut table = Map::new();
+                table.insert("command".to_string(), ValueKind::from(pick.command));
+                table.insert("weight".to_string(), ValueKind::from(pick.weight));
+                Value::new(None, ValueKind::from(table))
+            })
+            .collect();
+        map.insert("picks".to_string(), ValueKind::from(picks));
+        if self.exclude_last > 0 {
+            map.insert(
+                "exclude_last".to_string(),
+                ValueKind::from(self.exclude_last as u64),
+            );
+        }
+    }
+}
+
+impl TryFrom<&Map<String, Value>> for MysteryBox {
+    type Error = anyhow::Error;
+
+    /// Reads `picks` and `exclude_last` from the command's table.
+    fn try_from(table: &Map<String, Value>) -> Result<Self, Self::Error> {
+        let picks = match table.get("picks").map(|p| p.clone().into_array()) {
+            Some(Ok(picks)) => picks,
+            Some(Err(e)) => bail!(e),
+            None => bail!("No 'picks' property specified."),
+        };
+        let picks = picks
+            .into_iter()
+            .map(|pick| {
+                let pick = pick.into_table()?;
+                let command = match pick.get("command") {
+                    Some(c) => c.to_string(),
+                    None => bail!("A pick needs the properties: {:?}", vec!["command"]),
+                };
+                let weight = match pick.get("weight").map(|w| w.clone().into_uint()) {
+                    Some(Ok(w)) => w,
+                    Some(Err(e)) => bail!(e),
+                    None => 1,
+                };
+                Ok(Pick { command, weight })
+            })
+            .collect::<Result<Vec<Pick>>>()?;
+        if picks.iter().all(|pick| pick.weight == 0) {
+            bail!("A mystery box needs a pick with a weight above 0.");
+        }
+        let exclude_last = match table.get("exclude_last").map(|e| e.clone().into_uint()) {
+            Some(Ok(e)) => e as usize,
+            Some(Err(e)) => bail!(e),
+            None => 0,
+        };
+        Ok(Self {
+            picks,
+            exclude_last,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn mystery_box(exclude_last: usize) -> MysteryBox {
+        MysteryBox {
+            picks: vec![
+                Pick {
+                    command: "biters".into(),
+                    weight: 5,
+                },
+                Pick {
+                    command: "never".into(),
+                    weight: 0,
+                },
+                Pick {
+                    command: "nuke".into(),
+                    weight: 1,
+                },
+                Pick {
+                    command: "fish".into(),
+                    weight: 4,
+                },
+            ],
+            exclude_last,
+        }
+    }
+
+    #[rstest]
+    #[case(&[], 0, 10, "biters")]
+    #[case(&[], 4, 10, "biters")]
+    #[case(&[], 5, 10, "nuke")]
+    #[case(&[], 6, 10, "fish")]
+    #[case(&[], 9, 10, "fish")]
+    #[case(&["biters"], 0, 5, "nuke")]
+    #[case(&["biters", "nuke"], 3, 4, "fish")]
+    // Excluding every pick falls back to all of them.
+    #[case(&["biters", "nuke", "fish"], 5, 10, "nuke")]
+    fn pick_with(
+        #[case] recent: &[&str],
+        #[case] roll: u64,
+        #[case] total: u64,
+        #[case] expected: &str,
+    ) {
+        let recent: Vec<String> = recent.iter().map(|r| r.to_string()).collect();
+        let mystery_box = mystery_box(0);
+        let pick = mystery_box.pick_with(&recent, |t| {
+            assert_eq!(t, total);
+            roll
+        });
+        assert_eq!(pick.unwrap().command, expected);
+    }
+
+    #[rstest]
+    fn open_excludes_the_last_picks() {
+        let mystery_box = mystery_box(2);
+        let mut opened: Vec<String> = Vec::new();
+        for _ in 0..30 {
+            let pick = mystery_box.open("open_excludes_the_last_picks").unwrap();
+            assert!(!opened.iter().rev().take(2).any(|p| *p == pick.command));
+            assert_ne!(pick.command, "never");
+            opened.push(pick.command);
+        }
+        let history = MYSTERY_BOX_HISTORY.lock().unwrap();
+        assert_eq!(history["open_excludes_the_last_picks"], opened[28..]);
+    }
+
+    #[rstest]
+    fn config_round_trip() {
+        let mut map = Map::new();
+        mystery_box(2).insert_into(&mut map);
+        let table: Map<String, Value> = map
+            .into_iter()
+            .map(|(k, v)| (k, Value::new(None, v)))
+            .collect();
+        assert_eq!(MysteryBox::try_from(&table).unwrap(), mystery_box(2));
+    }
+
+    #[rstest]
+    fn no_weight() {
+        let mut map = Map::new();
+        MysteryBox {
+            picks: vec![Pick {
+                command: "never".into(),
+                weight: 0,
+            }],
+            exclude_last: 0,
+        }
+        .insert_into(&mut map);
+        let table: Map<String, Value> = map
+            .into_iter()
+            .map(|(k, v)| (k, Value::new(None, v)))
+            .collect();
+        assert!(MysteryBox::try_from(&table).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+//! What became of an event once every command has handled it.
+use crate::integration::{feedback, IntegrationEvent};
+
+/// Outcome of an event across all the triggers it matched, so a channel point
+/// redemption is fulfilled or refunded once however many servers it went to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Outcome {
+    /// No trigger matched.
+    #[default]
+    Ignored,
+    /// Dropped or failed to send.
+    Failed,
+    /// Waiting in the approval queue, reported once it is decided.
+    AwaitingApproval,
+    /// Sent to at least one server.
+    Sent,
+}
+
+impl Outcome {
+    /// Combines outcomes of the same event, a send to any server wins.
+    pub fn and(self, other: Self) -> Self {
+        self.max(other)
+    }
+
+    pub fn from_sent(succeeded: bool) -> Self {
+        if succeeded {
+            Self::Sent
+        } else {
+            Self::Failed
+        }
+    }
+
+    /// Fulfils or refunds a channel point redemption.
+    pub fn report(self, event: &IntegrationEvent) {
+        match self {
+            Self::Sent => feedback::redemption_outcome(event, true),
+            Self::Failed => feedback::redemption_outcome(event, false),
+            Self::Ignored | Self::AwaitingApproval => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(&[], Outcome::Ignored)]
+    #[case(&[Outcome::Failed, Outcome::Ignored], Outcome::Failed)]
+    #[case(&[Outcome::Failed, Outcome::Sent, Outcome::Failed], Outcome::Sent)]
+    #[case(&[Outcome::AwaitingApproval, Outcome::Failed], Outcome::AwaitingApproval)]
+    fn combined(#[case] outcomes: &[Outcome], #[case] expected: Outcome) {
+        let outcome = outcomes
+            .iter()
+            .fold(Outcome::default(), |outcome, other| outcome.and(*other));
+        assert_eq!(outcome, expected);
+    }
+}
@@ -0,0 +1,299 @@
+//! What the [`Runner`](super::Runner) does with events received while paused.
+use std::{
+    sync::{Arc, LazyLock},
+    time::{Duration, SystemTime},
+};
+
+use config::Config;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    integration::{feedback, IntegrationEvent},
+    localhost::overlay::OVERLAY,
+    AsyncMutex, TwitchApiConnection,
+};
+
+pub static PAUSE_STATE: LazyLock<Arc<AsyncMutex<PauseState>>> =
+    LazyLock::new(|| Arc::new(AsyncMutex::new(PauseState::default())));
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PausePolicy {
+    /// Events received while paused are discarded.
+    Drop,
+    /// Events are buffered and replayed in order on [`IntegrationEvent::Continue`].
+    #[default]
+    Replay,
+    /// Events are buffered until each is approved or discarded from the UI.
+    Approve,
+}
+
+impl From<config::Value> for PausePolicy {
+    /// If an invalid policy is used, it defaults to [PausePolicy::Replay].
+    fn from(value: config::Value) -> Self {
+        match value.to_string().to_lowercase().as_str() {
+            "drop" => Self::Drop,
+            "replay" => Self::Replay,
+            "approve" => Self::Approve,
+            p => {
+                warn!(
+                    "Recieved an invalid pause policy: {}. Defaulting to Replay",
+                    p
+                );
+                Self::Replay
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PauseSettings {
+    pub policy: PausePolicy,
+    /// Time between replayed events.
+    pub replay_spacing: Duration,
+}
+
+impl PauseSettings {
+    /// Reads `runner.pause_policy` and `runner.replay_spacing_ms`.
+    pub fn from_config(config: &Config) -> Self {
+        let policy = config
+            .get::<config::Value>("runner.pause_policy")
+            .map(PausePolicy::from)
+            .unwrap_or_default();
+        let replay_spacing = Duration::from_millis(
+            config
+                .get_int("runner.replay_spacing_ms")
+                .unwrap_or(0)
+                .max(0) as u64,
+        );
+        Self {
+            policy,
+            replay_spacing,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BufferedEvent {
+    pub id: Uuid,
+    pub received: SystemTime,
+    pub event: IntegrationEvent,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PauseState {
+    paused_since: Option<SystemTime>,
+    policy: PausePolicy,
+    buffer: Vec<BufferedEvent>,
+}
+
+impl PauseState {
+    pub fn is_paused(&self) -> bool {
+        self.paused_since.is_some()
+    }
+
+    pub fn pause(&mut self, policy: PausePolicy) {
+        if self.paused_since.is_none() {
+            self.paused_since = Some(SystemTime::now());
+        }
+        self.policy = policy;
+        OVERLAY.set_paused(true);
+    }
+
+    /// Ends the pause, returning the events to replay.
+    pub fn resume(&mut self) -> Vec<IntegrationEvent> {
+        if let Some(duration) = self.pause_duration() {
+            info!("Paused for {:?}", duration);
+        }
+        self.paused_since = None;
+        OVERLAY.set_paused(false);
+        match self.policy {
+            PausePolicy::Replay => self.buffer.drain(..).map(|b| b.event).collect(),
+            PausePolicy::Drop | PausePolicy::Approve => Vec::new(),
+        }
+    }
+
+    /// Handles an event received while paused according to the policy.
+    pub fn receive(&mut self, event: IntegrationEvent) {
+        match self.policy {
+            PausePolicy::Drop => {
+                info!("Paused, dropping event: {:?}", event);
+                feedback::redemption_outcome(&event, false);
+                feedback::interaction_notice(
+                    &event,
+                    "Commands are paused, this one was dropped.".to_string(),
+                );
+            }
+            PausePolicy::Replay | PausePolicy::Approve => {
+                info!("Paused, buffering event: {:?}", event);
+                let message = if self.policy == PausePolicy::Replay {
+                    "Commands are paused, this one runs when they continue."
+                } else {
+                    "Commands are paused, this one waits for a moderator to approve it."
+                };
+                feedback::interaction_notice(&event, message.to_string());
+                self.buffer.push(BufferedEvent {
+                    id: Uuid::new_v4(),
+                    received: SystemTime::now(),
+                    event,
+                })
+            }
+        }
+    }
+
+    pub fn pause_duration(&self) -> Option<Duration> {
+        self.paused_since
+            .map(|since| since.elapsed().unwrap_or_default())
+    }
+
+    pub fn buffer(&self) -> &[BufferedEvent] {
+        &self.buffer
+    }
+
+    /// Removes the event from the buffer.
+    pub fn take(&mut self, id: Uuid) -> Option<BufferedEvent> {
+        let position = self.buffer.iter().position(|b| b.id == id)?;
+        Some(self.buffer.remove(position))
+    }
+
+    pub fn status(&self) -> PauseStatus {
+        PauseStatus {
+            paused: self.is_paused(),
+            policy: self.policy,
+            paused_for_secs: self.pause_duration().map(|d| d.as_secs()),
+            buffered: self.buffer.len(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PauseStatus {
+    pub paused: bool,
+    pub policy: PausePolicy,
+    pub paused_for_secs: Option<u64>,
+    /// Number of events waiting to be replayed or approved.
+    pub buffered: usize,
+}
+
+#[tauri::command]
+pub async fn pause_status() -> Result<PauseStatus, String> {
+    Ok(PAUSE_STATE.lock().await.status())
+}
+
+#[tauri::command]
+pub async fn buffered_events() -> Result<Vec<BufferedEvent>, String> {
+    Ok(PAUSE_STATE.lock().await.buffer().to_vec())
+}
+
+#[tauri::command]
+pub async fn pause_runner(
+    twitch_mutex: State<'_, Arc<futures::lock::Mutex<TwitchApiConnection>>>,
+) -> Result<(), String> {
+    let mut twitch = twitch_mutex.lock().await;
+    twitch
+        .send_event_to_runner(IntegrationEvent::Pause)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn continue_runner(
+    twitch_mutex: State<'_, Arc<futures::lock::Mutex<TwitchApiConnection>>>,
+) -> Result<(), String> {
+    let mut twitch = twitch_mutex.lock().await;
+    twitch
+        .send_event_to_runner(IntegrationEvent::Continue)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Sends a buffered event to the runner, only allowed while not paused.
+#[tauri::command]
+pub async fn approve_buffered_event(
+    id: Uuid,
+    twitch_mutex: State<'_, Arc<futures::lock::Mutex<TwitchApiConnection>>>,
+) -> Result<(), String> {
+    let buffered = {
+        let mut pause_state = PAUSE_STATE.lock().await;
+        if pause_state.is_paused() {
+            return Err("Continue the runner before approving events.".to_string());
+        }
+        match pause_state.take(id) {
+            Some(b) => b,
+            None => return Err("Buffered event not found.".to_string()),
+        }
+    };
+    let mut twitch = twitch_mutex.lock().await;
+    twitch
+        .send_event_to_runner(buffered.event)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn discard_buffered_event(id: Uuid) -> Result<(), String> {
+    match PAUSE_STATE.lock().await.take(id) {
+        Some(b) => {
+            info!("Discarded buffered event: {:?}", b.event);
+            feedback::redemption_outcome(&b.event, false);
+            feedback::interaction_notice(&b.event, "A moderator discarded it.".to_string());
+            Ok(())
+        }
+        None => Err("Buffered event not found.".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn chat_event() -> IntegrationEvent {
+        IntegrationEvent::Chat {
+            msg: "Hello".into(),
+            author: "Legend".into(),
+            chatter: Default::default(),
+        }
+    }
+
+    #[rstest]
+    #[case(PausePolicy::Drop, 0, 0)]
+    #[case(PausePolicy::Replay, 2, 0)]
+    #[case(PausePolicy::Approve, 0, 2)]
+    fn resume_with_policy(
+        #[case] policy: PausePolicy,
+        #[case] replayed: usize,
+        #[case] remaining: usize,
+    ) {
+        let mut state = PauseState::default();
+        state.pause(policy);
+        assert!(state.is_paused());
+        state.receive(chat_event());
+        state.receive(chat_event());
+        assert_eq!(state.resume().len(), replayed);
+        assert!(!state.is_paused());
+        assert_eq!(state.status().buffered, remaining);
+    }
+
+    #[rstest]
+    fn take_buffered_event() {
+        let mut state = PauseState::default();
+        state.pause(PausePolicy::Approve);
+        state.receive(chat_event());
+        let id = state.buffer()[0].id;
+        assert!(state.take(Uuid::new_v4()).is_none());
+        assert_eq!(state.take(id).unwrap().event, chat_event());
+        assert!(state.buffer().is_empty());
+    }
+
+    #[rstest]
+    #[case("drop", PausePolicy::Drop)]
+    #[case("Replay", PausePolicy::Replay)]
+    #[case("APPROVE", PausePolicy::Approve)]
+    #[case("nonsense", PausePolicy::Replay)]
+    fn policy_from_config(#[case] value: &str, #[case] policy: PausePolicy) {
+        assert_eq!(PausePolicy::from(config::Value::from(value)), policy);
+    }
+}
@@ -0,0 +1,523 @@
+//! Per-viewer limits on a [`Command`](super::Command), see the `[<command>.quota]`
+//! table, and across all commands, see [`VIEWER_QUOTA_KEY`]. What each viewer
+//! triggered is kept for a day in `quota_usage.json` next to the main config
+//! file, so a restart doesn't reset the limits.
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{bail, Result};
+use config::{Config, Map, Value, ValueKind};
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+
+use super::user_policy::Viewer;
+use crate::{
+    integration::{currency, IntegrationEvent},
+    settings::Settings,
+    AsyncMutex,
+};
+
+/// The longest window, usage older than this is forgotten.
+pub const MAX_WINDOW_MINUTES: u64 = 24 * 60;
+const DEFAULT_WINDOW_MINUTES: u64 = 60;
+
+/// Config key of the quota across all commands in the main config file.
+pub const VIEWER_QUOTA_KEY: &str = "moderation.quota";
+
+/// Stands in for the command in [`Usage`] counted against the quota across all
+/// commands, once per event. Not a valid command name.
+pub const ALL_COMMANDS: &str = "*";
+
+pub static QUOTA_USAGE: LazyLock<Arc<AsyncMutex<QuotaUsage>>> =
+    LazyLock::new(|| Arc::new(AsyncMutex::new(QuotaUsage::load(&QuotaUsage::filepath()))));
+
+/// What happens when a viewer goes over the quota.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum OverQuota {
+    /// Not sent, a channel point redemption is refunded.
+    #[default]
+    Drop,
+    /// Waits in the [approval queue](super::approval) for a moderator.
+    Approval,
+}
+
+impl Display for OverQuota {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OverQuota::Drop => write!(f, "Drop"),
+            OverQuota::Approval => write!(f, "Approval"),
+        }
+    }
+}
+
+impl TryFrom<String> for OverQuota {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "drop" => Ok(Self::Drop),
+            "approval" => Ok(Self::Approval),
+            _ => bail!("Invalid input for over_quota: {}", value),
+        }
+    }
+}
+
+/// Limits on how much one viewer can trigger a command within a rolling window,
+/// or since the stream started.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Quota {
+    /// Times a viewer can trigger it, [`None`] for no limit.
+    #[serde(default)]
+    pub max_triggers: Option<u64>,
+    /// Total worth in US cents of a viewer's events that triggered it, including
+    /// the new one, [`None`] for no limit. Events without a value count as 0.
+    #[serde(default)]
+    pub max_value_cents: Option<u64>,
+    pub window_minutes: u64,
+    /// Counts from when the commands were started instead of `window_minutes`.
+    #[serde(default)]
+    pub per_stream: bool,
+    #[serde(default)]
+    pub over_quota: OverQuota,
+}
+
+impl Default for Quota {
+    fn default() -> Self {
+        Self {
+            max_triggers: None,
+            max_value_cents: None,
+            window_minutes: DEFAULT_WINDOW_MINUTES,
+            per_stream: false,
+            over_quota: OverQuota::default(),
+        }
+    }
+}
+
+impl Quota {
+    pub fn window(&self) -> Duration {
+        Duration::from_secs(self.window_minutes * 60)
+    }
+
+    /// The quota across all commands from [`VIEWER_QUOTA_KEY`], [`None`] if it
+    /// isn't set or is invalid.
+    pub fn viewer(config: &Config) -> Option<Self> {
+        let value = config.get::<Value>(VIEWER_QUOTA_KEY).ok()?;
+        Self::try_from(value)
+            .inspect_err(|e| error!("Invalid {VIEWER_QUOTA_KEY}: {e}"))
+            .ok()
+    }
+}
+
+impl TryFrom<Value> for Quota {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let table = match value.into_table() {
+            Ok(t) => t,
+            Err(e) => bail!(e),
+        };
+        let max_triggers = match table.get("max_triggers").map(|m| m.clone().into_uint()) {
+            Some(Ok(m)) => Some(m),
+            Some(Err(e)) => bail!(e),
+            None => None,
+        };
+        let max_value_cents = match table.get("max_value") {
+            Some(m) => match currency::parse_cents(&m.to_string()) {
+                Some(c) => Some(c),
+                None => bail!("max_value is an invalid amount in USD, recieved '{m}'"),
+            },
+            None => None,
+        };
+        if max_triggers.is_none() && max_value_cents.is_none() {
+            bail!(
+                "A quota needs at least one of the properties: {:?}",
+                vec!["max_triggers", "max_value"]
+            );
+        }
+        let window_minutes = match table.get("window_minutes").map(|w| w.clone().into_uint()) {
+            Some(Ok(w)) if w > MAX_WINDOW_MINUTES => {
+                warn!("A quota window can be at most {MAX_WINDOW_MINUTES} minutes, not {w}");
+                MAX_WINDOW_MINUTES
+            }
+            Some(Ok(0)) => bail!("window_minutes needs to be at least 1"),
+            Some(Ok(w)) => w,
+            Some(Err(e)) => bail!(e),
+            None => DEFAULT_WINDOW_MINUTES,
+        };
+        let per_stream = match table.get("per_stream").map(|p| p.clone().into_bool()) {
+            Some(Ok(p)) => p,
+            Some(Err(e)) => bail!(e),
+            None => false,
+        };
+        let over_quota = match table.get("over_quota") {
+            Some(o) => OverQuota::try_from(o.to_string())?,
+            None => OverQuota::default(),
+        };
+        Ok(Self {
+            max_triggers,
+            max_value_cents,
+            window_minutes,
+            per_stream,
+            over_quota,
+        })
+    }
+}
+
+impl From<Quota> for Value {
+    fn from(quota: Quota) -> Self {
+        let mut map = Map::new();
+        if let Some(max_triggers) = quota.max_triggers {
+            map.insert("max_triggers".to_string(), ValueKind::from(max_triggers));
+        }
+        if let Some(max_value_cents) = quota.max_value_cents {
+            map.insert(
+                "max_value".to_string(),
+                ValueKind::from(currency::format_cents(max_value_cents)),
+            );
+        }
+        map.insert(
+            "window_minutes".to_string(),
+            ValueKind::from(quota.window_minutes),
+        );
+        if quota.per_stream {
+            map.insert("per_stream".to_string(), ValueKind::from(true));
+        }
+        if quota.over_quota != OverQuota::default() {
+            map.insert(
+                "over_quota".to_string(),
+                ValueKind::from(quota.over_quota.to_string()),
+            );
+        }
+        Self::new(None, ValueKind::from(map))
+    }
+}
+
+/// Which limit the viewer went over.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuotaExceeded {
+    Triggers { max: u64 },
+    Value { max_cents: u64 },
+}
+
+impl Display for QuotaExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuotaExceeded::Triggers { max } => write!(f, "over the quota of {max} triggers"),
+            QuotaExceeded::Value { max_cents } => {
+                write!(
+                    f,
+                    "over the quota of ${} in value",
+                    currency::format_cents(*max_cents)
+                )
+            }
+        }
+    }
+}
+
+/// A command triggered by a viewer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    pub time: SystemTime,
+    pub command: String,
+    pub value_cents: u64,
+}
+
+/// What each viewer triggered, by user id or lowercase name without one.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaUsage {
+    #[serde(flatten)]
+    usage: HashMap<String, Vec<Usage>>,
+    /// When the commands were started, for [`Quota::per_stream`].
+    #[serde(skip)]
+    stream_start: Option<SystemTime>,
+}
+
+impl QuotaUsage {
+    pub fn filepath() -> PathBuf {
+        Settings::default().config_folder.join("quota_usage.json")
+    }
+
+    /// Empty if the file doesn't exist or can't be read.
+    pub fn load(path: &Path) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                error!("Invalid quota usage in {}: {e}", path.display());
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Identifies the viewer, [`None`] for events not triggered by one.
+    pub fn key(event: &IntegrationEvent) -> Option<String> {
+        let viewer = Viewer::from_event(event);
+        match (viewer.user_id.as_str(), viewer.user_name.as_str()) {
+            ("", "" | "<server>") => None,
+            ("", user_name) => Some(user_name.to_lowercase()),
+            (user_id, _) => Some(user_id.to_string()),
+        }
+    }
+
+    /// Whether the viewer can trigger the command once more, worth `value_cents`.
+    pub fn check(
+        &self,
+        key: &str,
+        command: &str,
+        quota: &Quota,
+        value_cents: u64,
+        now: SystemTime,
+    ) -> Result<(), QuotaExceeded> {
+        let since = match (quota.per_stream, self.stream_start) {
+            (true, Some(stream_start)) => stream_start,
+            // Not started, only what is remembered.
+            (true, None) => SystemTime::UNIX_EPOCH,
+            (false, _) => now
+                .checked_sub(quota.window())
+                .unwrap_or(SystemTime::UNIX_EPOCH),
+        };
+        let used: Vec<&Usage> = self
+            .usage
+            .get(key)
+            .into_iter()
+            .flatten()
+            .filter(|usage| usage.command == command && usage.time > since)
+            .collect();
+        if let Some(max) = quota.max_triggers {
+            if used.len() as u64 >= max {
+                return Err(QuotaExceeded::Triggers { max });
+            }
+        }
+        if let Some(max_cents) = quota.max_value_cents {
+            let total: u64 = used.iter().map(|usage| usage.value_cents).sum();
+            if total + value_cents > max_cents {
+                return Err(QuotaExceeded::Value { max_cents });
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the trigger and forgets any usage older than [`MAX_WINDOW_MINUTES`].
+    pub fn record(&mut self, key: &str, command: &str, value_cents: u64, now: SystemTime) {
+        self.usage.entry(key.to_string()).or_default().push(Usage {
+            time: now,
+            command: command.to_string(),
+            value_cents,
+        });
+        let since = now
+            .checked_sub(Duration::from_secs(MAX_WINDOW_MINUTES * 60))
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        self.usage.retain(|_, usage| {
+            usage.retain(|u| u.time > since);
+            !usage.is_empty()
+        });
+    }
+
+    /// Forgets what the viewer triggered, returning `false` if there was nothing.
+    pub fn reset(&mut self, key: &str) -> bool {
+        self.usage.remove(key).is_some()
+    }
+
+    pub fn usage(&self) -> &HashMap<String, Vec<Usage>> {
+        &self.usage
+    }
+
+    /// Starts the window of [`Quota::per_stream`] quotas.
+    pub fn start_stream(&mut self, now: SystemTime) {
+        self.stream_start = Some(now);
+    }
+}
+
+/// Counts an event that sent or queued a command against the viewer's quota
+/// across all commands, once however many commands it triggered.
+pub async fn record_viewer(event: &IntegrationEvent) {
+    record(event, ALL_COMMANDS).await;
+}
+
+/// Counts the event against the viewer's quotas for `command`.
+pub async fn record(event: &IntegrationEvent, command: &str) {
+    let Some(key) = QuotaUsage::key(event) else {
+        return;
+    };
+    let mut usage = QUOTA_USAGE.lock().await;
+    usage.record(&key, command, event_value_cents(event), SystemTime::now());
+    if let Err(e) = usage.save(&QuotaUsage::filepath()) {
+        error!("Failed to save the quota usage: {e}");
+    }
+}
+
+/// The event's worth for [`Quota::max_value_cents`].
+pub fn event_value_cents(event: &IntegrationEvent) -> u64 {
+    event
+        .value_cents(&currency::value_rates())
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub async fn quota_usage() -> Result<HashMap<String, Vec<Usage>>, String> {
+    Ok(QUOTA_USAGE.lock().await.usage().clone())
+}
+
+/// Lets the viewer, by user id or name, trigger commands again before the window ends.
+#[tauri::command]
+pub async fn reset_quota_usage(user: String) -> Result<bool, String> {
+    let mut usage = QUOTA_USAGE.lock().await;
+    let reset = usage.reset(&user) || usage.reset(&user.to_lowercase());
+    if reset {
+        usage
+            .save(&QuotaUsage::filepath())
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(reset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn quota(max_triggers: Option<u64>, max_value_cents: Option<u64>) -> Quota {
+        Quota {
+            max_triggers,
+            max_value_cents,
+            ..Default::default()
+        }
+    }
+
+    #[rstest]
+    #[case(quota(Some(3), None))]
+    #[case(Quota { max_triggers: None, max_value_cents: Some(2050), window_minutes: 720, per_stream: true, over_quota: OverQuota::Approval })]
+    fn config_round_trip(#[case] quota: Quota) {
+        assert_eq!(Quota::try_from(Value::from(quota.clone())).unwrap(), quota);
+    }
+
+    #[rstest]
+    #[case(&[("window_minutes", ValueKind::from(30))])]
+    #[case(&[("max_triggers", ValueKind::from(1)), ("window_minutes", ValueKind::from(0))])]
+    #[case(&[("max_triggers", ValueKind::from(1)), ("over_quota", ValueKind::from("refund"))])]
+    fn invalid_config(#[case] entries: &[(&str, ValueKind)]) {
+        let map: Map<String, ValueKind> = entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect();
+        assert!(Quota::try_from(Value::new(None, ValueKind::from(map))).is_err());
+    }
+
+    #[rstest]
+    #[case(quota(Some(2), None), &[0, 0], 0, Err(QuotaExceeded::Triggers { max: 2 }))]
+    #[case(quota(Some(2), None), &[0], 0, Ok(()))]
+    #[case(quota(None, Some(1000)), &[500, 400], 100, Ok(()))]
+    #[case(quota(None, Some(1000)), &[500, 400], 101, Err(QuotaExceeded::Value { max_cents: 1000 }))]
+    #[case(quota(None, Some(1000)), &[], 5000, Err(QuotaExceeded::Value { max_cents: 1000 }))]
+    fn check(
+        #[case] quota: Quota,
+        #[case] used: &[u64],
+        #[case] value_cents: u64,
+        #[case] expected: Result<(), QuotaExceeded>,
+    ) {
+        let now = SystemTime::now();
+        let mut usage = QuotaUsage::default();
+        for value_cents in used {
+            usage.record("1", "nuke", *value_cents, now - MINUTE);
+        }
+        // Other viewers and commands don't count.
+        usage.record("2", "nuke", 5000, now - MINUTE);
+        usage.record("1", "biters", 5000, now - MINUTE);
+        assert_eq!(usage.check("1", "nuke", &quota, value_cents, now), expected);
+    }
+
+    #[rstest]
+    fn window_rolls() {
+        let now = SystemTime::now();
+        let mut usage = QuotaUsage::default();
+        usage.record("1", "nuke", 0, now - 61 * MINUTE);
+        usage.record("1", "nuke", 0, now - 59 * MINUTE);
+        let quota = quota(Some(1), None);
+        assert!(usage.check("1", "nuke", &quota, 0, now).is_err());
+        assert!(usage
+            .check("1", "nuke", &quota, 0, now + 2 * MINUTE)
+            .is_ok());
+    }
+
+    #[rstest]
+    fn window_per_stream() {
+        let now = SystemTime::now();
+        let mut usage = QuotaUsage::default();
+        usage.record("1", "nuke", 0, now - 120 * MINUTE);
+        usage.start_stream(now - 90 * MINUTE);
+        usage.record("1", "nuke", 0, now - 80 * MINUTE);
+        let quota = Quota {
+            per_stream: true,
+            ..quota(Some(2), None)
+        };
+        assert!(usage.check("1", "nuke", &quota, 0, now).is_ok());
+        usage.record("1", "nuke", 0, now - MINUTE);
+        assert!(usage.check("1", "nuke", &quota, 0, now).is_err());
+        usage.start_stream(now);
+        assert!(usage.check("1", "nuke", &quota, 0, now).is_ok());
+    }
+
+    #[rstest]
+    fn viewer_quota_counts_events_once() {
+        let now = SystemTime::now();
+        let mut usage = QuotaUsage::default();
+        // One event worth $6 that triggered two commands.
+        usage.record("1", "nuke", 600, now - MINUTE);
+        usage.record("1", "biters", 600, now - MINUTE);
+        usage.record("1", ALL_COMMANDS, 600, now - MINUTE);
+        let quota = quota(None, Some(1000));
+        assert!(usage.check("1", ALL_COMMANDS, &quota, 400, now).is_ok());
+        assert_eq!(
+            usage.check("1", ALL_COMMANDS, &quota, 401, now),
+            Err(QuotaExceeded::Value { max_cents: 1000 })
+        );
+    }
+
+    #[rstest]
+    fn viewer_quota_from_config() {
+        let config = Config::builder()
+            .add_source(config::File::from_str(
+                "[moderation.quota]\nmax_value = 100\nper_stream = true",
+                config::FileFormat::Toml,
+            ))
+            .build()
+            .unwrap();
+        let quota = Quota::viewer(&config).unwrap();
+        assert_eq!(quota.max_value_cents, Some(10000));
+        assert!(quota.per_stream);
+        assert_eq!(Quota::viewer(&Config::default()), None);
+    }
+
+    #[rstest]
+    fn usage_file_round_trip() {
+        let mut usage = QuotaUsage::default();
+        usage.record("1", "nuke", 100, SystemTime::now());
+        let json = serde_json::to_string(&usage).unwrap();
+        assert!(json.starts_with(r#"{"1":"#));
+        assert_eq!(serde_json::from_str::<QuotaUsage>(&json).unwrap(), usage);
+    }
+
+    #[rstest]
+    fn record_forgets_after_a_day() {
+        let now = SystemTime::now();
+        let mut usage = QuotaUsage::default();
+        usage.record("1", "nuke", 0, now - 25 * 60 * MINUTE);
+        usage.record("2", "nuke", 0, now);
+        assert_eq!(usage.usage().keys().collect::<Vec<_>>(), vec!["2"]);
+        assert!(usage.reset("2"));
+        assert!(!usage.reset("2"));
+    }
+}
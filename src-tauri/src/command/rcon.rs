@@ -1,4 +1,5 @@
 use crate::command::Variable;
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use std::fmt::Write;
 use tracing::error;
@@ -15,14 +16,24 @@ pub struct RconCommand {
 impl RconCommand {
     /// The complete command to transmit to the server.
     pub fn command(&mut self, message: Option<&str>, username: &str) -> String {
+        self.command_with_values(message, username, &IndexMap::new())
+    }
+
+    /// The complete command to transmit to the server, `values` take priority over the
+    /// values in the message.
+    pub fn command_with_values(
+        &mut self,
+        message: Option<&str>,
+        username: &str,
+        values: &IndexMap<String, String>,
+    ) -> String {
         match self.lua_command.command() {
             Ok(command) => {
                 let mut commmand_string = self.prefix.to_string();
 
                 if let Some(variables) = &self.variables {
                     for variable in variables {
-                        let v = variable
-                            .from_message(message)
+                        let v = Self::variable_value(variable, message, values)
                             .map(|v| v.variable_type().clone());
                         if let Err(e) = writeln!(
                             commmand_string,
@@ -49,6 +60,34 @@ impl RconCommand {
             }
         }
     }
+
+    fn variable_value(
+        variable: &Variable,
+        message: Option<&str>,
+        values: &IndexMap<String, String>,
+    ) -> Option<Variable> {
+        if let Some(value) = values.get(variable.name()) {
+            match variable.with_value(value) {
+                Ok(v) => return Some(v),
+                Err(e) => error!("Invalid value for {}: {e}", variable.name()),
+            }
+        }
+        variable.from_message(message)
+    }
+
+    /// The value of each variable that would be used for this message, either from the
+    /// message or the default.
+    pub fn variable_values(&self, message: Option<&str>) -> IndexMap<String, String> {
+        self.variables
+            .iter()
+            .flatten()
+            .filter(|v| v.name() != "USERNAME")
+            .map(|v| {
+                let value = v.from_message(message).unwrap_or(v.clone());
+                (v.name().to_string(), value.variable_type().to_string())
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -83,4 +122,33 @@ mod tests {
             expected
         );
     }
+
+    #[rstest]
+    fn inline_command_print_with_values() {
+        let variables = Variable::from_config("x:int=5,y=hello").unwrap();
+        let mut command = RconCommand {
+            prefix: Prefix::SC,
+            lua_command: RconCommandLua::Inline("game.print(x);".to_string()),
+            variables,
+        };
+        let message = Some("you suck x=20");
+        assert_eq!(
+            command.variable_values(message),
+            IndexMap::from([
+                ("x".to_string(), "20".to_string()),
+                ("y".to_string(), "hello".to_string())
+            ])
+        );
+        let values = IndexMap::from([
+            ("x".to_string(), "2".to_string()),
+            ("z".to_string(), "1".to_string()),
+        ]);
+        let expected = "/silent-command local x = 2;\nlocal y = \"hello\";\ngame.print(x);";
+        assert_eq!(
+            command
+                .command_with_values(message, "test", &values)
+                .as_str(),
+            expected
+        );
+    }
 }
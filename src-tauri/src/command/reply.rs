@@ -0,0 +1,137 @@
+//! Chat message sent back to the platform after a command runs.
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+/// Templates with `{NAME}` placeholders for the command's variables, `USERNAME`,
+/// `COMMAND`, and `RESPONSE` or `ERROR` from the server. Unknown placeholders
+/// are left as they are.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Reply {
+    /// Sent when the server accepted the command, config key `reply`.
+    #[serde(default)]
+    pub success: Option<String>,
+    /// Sent when the server is offline or the command failed, config key `error_reply`.
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl Reply {
+    pub fn is_empty(&self) -> bool {
+        self.success.is_none() && self.error.is_none()
+    }
+
+    /// Slash commands are always answered, these fill in for missing templates.
+    pub fn interaction() -> Self {
+        Self {
+            success: Some("Sent {COMMAND}. {RESPONSE}".to_string()),
+            error: Some("{COMMAND} failed: {ERROR}".to_string()),
+        }
+    }
+
+    /// Each template, or the one from `other` if missing.
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            success: self.success.or(other.success),
+            error: self.error.or(other.error),
+        }
+    }
+
+    /// The message for the outcome, [`None`] if there is no template for it.
+    pub fn message(&self, succeeded: bool, values: &IndexMap<String, String>) -> Option<String> {
+        let template = if succeeded {
+            self.success.as_ref()
+        } else {
+            self.error.as_ref()
+        }?;
+        Some(render(template, values))
+    }
+}
+
+/// Replaces each `{NAME}` with its value in a single pass, so placeholders
+/// inside the values, e.g. a viewer's message, are left as they are.
+pub fn render(template: &str, values: &IndexMap<String, String>) -> String {
+    let mut message = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        message.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after
+            .find('}')
+            .and_then(|end| Some((end, values.get(&after[..end])?)));
+        match value {
+            Some((end, value)) => {
+                message.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                message.push('{');
+                rest = after;
+            }
+        }
+    }
+    message.push_str(rest);
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(true, Some("@Legend your nuke lands in 30s"))]
+    #[case(false, Some("Sorry @Legend, server offline: {RESPONSE}"))]
+    fn reply_message(#[case] succeeded: bool, #[case] expected: Option<&str>) {
+        let reply = Reply {
+            success: Some("@{USERNAME} your nuke lands in {DELAY}s".into()),
+            error: Some("Sorry @{USERNAME}, {ERROR}: {RESPONSE}".into()),
+        };
+        let values = IndexMap::from([
+            ("USERNAME".to_string(), "Legend".to_string()),
+            ("DELAY".to_string(), "30".to_string()),
+            ("ERROR".to_string(), "server offline".to_string()),
+        ]);
+        assert_eq!(reply.message(succeeded, &values).as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case("{MESSAGE} from {USERNAME}", "{ERROR} from {RESPONSE}")]
+    #[case("{{MESSAGE}} {UNKNOWN} {", "{{ERROR}} {UNKNOWN} {")]
+    #[case("{MESSAGE", "{MESSAGE")]
+    fn render_once(#[case] template: &str, #[case] expected: &str) {
+        let values = IndexMap::from([
+            ("MESSAGE".to_string(), "{ERROR}".to_string()),
+            ("USERNAME".to_string(), "{RESPONSE}".to_string()),
+            ("ERROR".to_string(), "server offline".to_string()),
+            ("RESPONSE".to_string(), "ok".to_string()),
+        ]);
+        assert_eq!(render(template, &values), expected);
+    }
+
+    #[rstest]
+    fn no_template() {
+        let reply = Reply {
+            success: Some("Done".into()),
+            error: None,
+        };
+        assert_eq!(reply.message(false, &IndexMap::new()), None);
+    }
+
+    #[rstest]
+    fn interaction_fills_in() {
+        let reply = Reply {
+            success: Some("Done".into()),
+            error: None,
+        }
+        .or(Reply::interaction());
+        let values = IndexMap::from([
+            ("COMMAND".to_string(), "nuke".to_string()),
+            ("ERROR".to_string(), "server offline".to_string()),
+        ]);
+        assert_eq!(reply.message(true, &values).as_deref(), Some("Done"));
+        assert_eq!(
+            reply.message(false, &values).as_deref(),
+            Some("nuke failed: server offline")
+        );
+    }
+}
@@ -0,0 +1,164 @@
+//! Twitch channel point reward owned by a [`Command`](super::Command).
+use anyhow::bail;
+use config::{Map, Value, ValueKind};
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+
+/// Reward definition synced to Twitch, see the `[<command>.reward]` table.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Reward {
+    /// Twitch id, [`None`] until the reward is created.
+    #[serde(default)]
+    pub id: Option<String>,
+    pub title: String,
+    pub cost: usize,
+    #[serde(default)]
+    pub prompt: Option<String>,
+    #[serde(default)]
+    pub is_user_input_required: bool,
+    /// Seconds between redemptions by anyone, [`None`] for no cooldown.
+    #[serde(default)]
+    pub global_cooldown: Option<usize>,
+}
+
+impl Reward {
+    pub fn new<T: Into<String>>(title: T, cost: usize) -> Self {
+        Self {
+            title: title.into(),
+            cost,
+            ..Default::default()
+        }
+    }
+
+    /// Keeps the Twitch id of the reward it replaces, so the next sync updates
+    /// it instead of creating another.
+    pub fn replacing(mut self, previous: Option<&Reward>) -> Self {
+        if self.id.is_none() {
+            self.id = previous.and_then(|p| p.id.clone());
+        }
+        self
+    }
+}
+
+impl TryFrom<Value> for Reward {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let table = match value.into_table() {
+            Ok(t) => t,
+            Err(e) => bail!(e),
+        };
+        let title = match table.get("title").map(|t| t.clone().into_string()) {
+            Some(Ok(t)) => t,
+            Some(Err(e)) => bail!(e),
+            None => bail!("A reward needs the properties: {:?}", vec!["title", "cost"]),
+        };
+        let cost = match table.get("cost").map(|c| c.clone().into_uint()) {
+            Some(Ok(c)) => c as usize,
+            Some(Err(e)) => bail!(e),
+            None => bail!("A reward needs the properties: {:?}", vec!["title", "cost"]),
+        };
+        let id = table.get("id").and_then(|i| i.clone().into_string().ok());
+        let prompt = table
+            .get("prompt")
+            .and_then(|p| p.clone().into_string().ok());
+        let is_user_input_required = match table.get("input_required") {
+            Some(i) => i.clone().into_bool().unwrap_or_else(|e| {
+                error!(
+                    "Invalid input_required property for {} reward: {:?}",
+                    title, e
+                );
+                warn!("Setting value to false");
+                false
+            }),
+            None => false,
+        };
+        let global_cooldown = match table.get("cooldown").map(|c| c.clone().into_uint()) {
+            Some(Ok(c)) => Some(c as usize),
+            Some(Err(e)) => {
+                error!("Invalid cooldown property for {} reward: {:?}", title, e);
+                None
+            }
+            None => None,
+        };
+        Ok(Self {
+            id,
+            title,
+            cost,
+            prompt,
+            is_user_input_required,
+            global_cooldown,
+        })
+    }
+}
+
+impl From<Reward> for Value {
+    fn from(reward: Reward) -> Self {
+        let mut map = Map::new();
+        if let Some(id) = reward.id {
+            map.insert("id".to_string(), ValueKind::from(id));
+        }
+        map.insert("title".to_string(), ValueKind::from(reward.title));
+        map.insert("cost".to_string(), ValueKind::from(reward.cost as u64));
+        if let Some(prompt) = reward.prompt {
+            map.insert("prompt".to_string(), ValueKind::from(prompt));
+        }
+        if reward.is_user_input_required {
+            map.insert("input_required".to_string(), ValueKind::from(true));
+        }
+        if let Some(cooldown) = reward.global_cooldown {
+            map.insert("cooldown".to_string(), ValueKind::from(cooldown as u64));
+        }
+        Self::new(None, ValueKind::from(map))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(Reward::new("Player Goes Boom", 500))]
+    #[case(Reward {
+        id: Some("12345678-1234-1234-1234-123456789012".into()),
+        title: "Spawn Biters".into(),
+        cost: 1000,
+        prompt: Some("How many?".into()),
+        is_user_input_required: true,
+        global_cooldown: Some(60),
+    })]
+    fn config_round_trip(#[case] reward: Reward) {
+        assert_eq!(
+            Reward::try_from(Value::from(reward.clone())).unwrap(),
+            reward
+        );
+    }
+
+    #[rstest]
+    #[case(None, None, None)]
+    #[case(None, Some("old"), Some("old"))]
+    #[case(Some("new"), Some("old"), Some("new"))]
+    fn replacing(
+        #[case] id: Option<&str>,
+        #[case] previous_id: Option<&str>,
+        #[case] expected: Option<&str>,
+    ) {
+        let reward = Reward {
+            id: id.map(String::from),
+            ..Reward::new("Player Goes Boom", 500)
+        };
+        let previous = Reward {
+            id: previous_id.map(String::from),
+            ..Reward::new("Boom", 100)
+        };
+        assert_eq!(reward.replacing(Some(&previous)).id.as_deref(), expected);
+    }
+
+    #[rstest]
+    fn missing_cost() {
+        let mut map = Map::new();
+        map.insert("title".to_string(), ValueKind::from("Boom"));
+        assert!(Reward::try_from(Value::new(None, ValueKind::from(map))).is_err());
+    }
+}
@@ -1,13 +1,26 @@
-use crate::integration::IntegrationEvent;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, RwLock},
+    time::SystemTime,
+};
+
+use crate::{integration::IntegrationEvent, settings::Settings};
 use anyhow::Result;
 use tokio::{
-    spawn,
+    select, spawn,
     sync::mpsc::{channel, error::SendError, Receiver, Sender},
     task::JoinHandle,
+    time::{sleep_until, Instant},
 };
 use tracing::{debug, error, info};
 
-use super::{settings::ScriptSettings, Command};
+use super::{
+    approval::APPROVAL_QUEUE,
+    pause::{PauseSettings, PAUSE_STATE},
+    quota::{self, Quota, QUOTA_USAGE},
+    settings::ScriptSettings,
+    Command, Outcome,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RunnerError {
@@ -28,6 +41,10 @@ pub struct Runner {
     /// [Sender] for the runner to pass to subscribers.
     tx: Sender<IntegrationEvent>,
     commands: Vec<Command>,
+    /// Limits each viewer across all commands.
+    viewer_quota: Option<Quota>,
+    /// Shared with the running task so changes apply right away.
+    pause_settings: Arc<RwLock<PauseSettings>>,
     joinhandle: Option<JoinHandle<Result<(), RunnerError>>>,
 }
 
@@ -41,6 +58,10 @@ impl Runner {
             rx: Some(rx),
             tx,
             commands: ScriptSettings::get_commands(),
+            viewer_quota: Settings::new()
+                .ok()
+                .and_then(|settings| Quota::viewer(&settings.config())),
+            pause_settings: Arc::new(RwLock::new(PauseSettings::default())),
             joinhandle: None,
         }
     }
@@ -50,6 +71,14 @@ impl Runner {
         self.tx.clone()
     }
 
+    /// Also applies to the running runner, from the next pause or replayed event.
+    pub fn set_pause_settings(&mut self, pause_settings: PauseSettings) {
+        *self
+            .pause_settings
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = pause_settings;
+    }
+
     pub async fn transmit(
         &mut self,
         event: IntegrationEvent,
@@ -68,7 +97,13 @@ impl Runner {
     }
 
     /// TODO: re-write this section.
+    ///
+    /// Does nothing if the runner is already running, so every existing
+    /// [`Runner::tx()`] stays connected.
     pub fn run(&mut self) -> Result<(), RunnerError> {
+        if self.is_running() {
+            return Ok(());
+        }
         let mut rx = match self.rx.take() {
             Some(rx) => rx,
             None => {
@@ -78,10 +113,30 @@ impl Runner {
             }
         };
         let mut commands = self.commands.clone();
+        let viewer_quota = self.viewer_quota.clone();
+        let pause_settings = self.pause_settings.clone();
+        let pause_settings = move || *pause_settings.read().unwrap_or_else(|e| e.into_inner());
         use IntegrationEvent::*;
         let jh: JoinHandle<std::result::Result<(), RunnerError>> = spawn(async move {
+            QUOTA_USAGE.lock().await.start_stream(SystemTime::now());
+            let mut paused = false;
+            // Events buffered during the last pause, replayed one per `replay_spacing`
+            // between handling newly received events.
+            let mut replay: VecDeque<IntegrationEvent> = VecDeque::new();
+            let mut next_replay = Instant::now();
             loop {
-                match rx.recv().await {
+                let received = select! {
+                    biased;
+                    received = rx.recv() => received,
+                    _ = sleep_until(next_replay), if !paused && !replay.is_empty() => {
+                        if let Some(event) = replay.pop_front() {
+                            Self::handle_event(&mut commands, viewer_quota.as_ref(), &event).await;
+                        }
+                        next_replay = Instant::now() + pause_settings().replay_spacing;
+                        continue;
+                    }
+                };
+                match received {
                     Some(Connected) => {
                         info!("runner connected");
                     }
@@ -89,23 +144,26 @@ impl Runner {
                         info!("runner Disconnected");
                     }
                     Some(Stop) => return Ok(()),
-                    Some(Pause) => loop {
-                        match rx.recv().await {
-                            Some(Continue) => break,
-                            Some(Stop) => return Ok(()),
-                            None => return Ok(()),
-                            _ => continue,
+                    Some(Pause) => {
+                        paused = true;
+                        PAUSE_STATE.lock().await.pause(pause_settings().policy);
+                    }
+                    Some(Continue) if paused => {
+                        paused = false;
+                        let buffered = PAUSE_STATE.lock().await.resume();
+                        if !buffered.is_empty() {
+                            info!("Replaying {} events", buffered.len());
                         }
-                    },
+                        replay.extend(buffered);
+                        next_replay = Instant::now();
+                    }
                     Some(Unknown) => {
                         error!("An unknown event occurred")
                     }
+                    Some(Update) if paused => {}
+                    Some(event) if paused => PAUSE_STATE.lock().await.receive(event),
                     Some(event) => {
-                        debug!("{:?}", &event);
-                        for command in commands.iter_mut() {
-                            debug!("{:?}", &command);
-                            command.handle_event(&event).await;
-                        }
+                        Self::handle_event(&mut commands, viewer_quota.as_ref(), &event).await
                     }
 
                     None => return Ok(()),
@@ -116,6 +174,28 @@ impl Runner {
         Ok(())
     }
 
+    async fn handle_event(
+        commands: &mut [Command],
+        viewer_quota: Option<&Quota>,
+        event: &IntegrationEvent,
+    ) {
+        debug!("{:?}", event);
+        // Duplicates are dropped where they are received, a resent event runs again.
+        let mut outcome = Outcome::default();
+        for command in commands.iter_mut() {
+            debug!("{:?}", &command);
+            outcome = outcome.and(command.handle_event(event, viewer_quota).await);
+        }
+        if viewer_quota.is_some() && matches!(outcome, Outcome::Sent | Outcome::AwaitingApproval) {
+            quota::record_viewer(event).await;
+        }
+        if outcome == Outcome::Sent {
+            // A redemption is fulfilled now, pending approvals of it can't refund it.
+            APPROVAL_QUEUE.lock().await.reported(event);
+        }
+        outcome.report(event);
+    }
+
     /// Returns [true] if the runner is actively running.
     pub fn is_running(&self) -> bool {
         if let Some(jh) = &self.joinhandle {
@@ -159,3 +239,47 @@ impl Runner {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        command::{command_logs::COMMAND_LOGS, Prefix, RconCommand, RconCommandLua, Trigger},
+        integration::{CustomRewardEvent, CustomRewardVariant},
+        servers::GameServer,
+    };
+    use rstest::rstest;
+
+    #[rstest]
+    #[tokio::test]
+    async fn resent_event_reaches_commands() {
+        let rcon_command = RconCommand {
+            prefix: Prefix::default(),
+            lua_command: RconCommandLua::Inline(String::new()),
+            variables: None,
+        };
+        let mut command = Command::new("resent_event_reaches_commands", rcon_command);
+        let trigger = Trigger::ChannelPointRewardRedeemed {
+            title: "Nuke".into(),
+            id: "resent-reward".into(),
+            variant: CustomRewardVariant::New,
+        };
+        command.add_server_trigger(GameServer::default(), trigger, true);
+        let event = IntegrationEvent::ChannelPoint(CustomRewardEvent {
+            variant: CustomRewardVariant::New,
+            event_id: "resent-redemption".into(),
+            id: "resent-reward".into(),
+            ..Default::default()
+        });
+        let mut commands = vec![command];
+        Runner::handle_event(&mut commands, None, &event).await;
+        Runner::handle_event(&mut commands, None, &event).await;
+        let logs = COMMAND_LOGS.lock().await;
+        let handled = logs
+            .all_logs()
+            .iter()
+            .filter(|log| log.command().name == "resent_event_reaches_commands")
+            .count();
+        assert_eq!(handled, 2);
+    }
+}
@@ -199,16 +199,18 @@ impl ScriptSettings {
         old_command
     }
 
-    pub fn set_commands(&mut self, commands: &Vec<Command>) {
-        let commands = commands.clone();
+    /// Sets the commands in config and writes it, the config is left unchanged
+    /// if a command name is not a valid key.
+    pub fn set_commands(&mut self, commands: &Vec<Command>) -> Result<()> {
         let mut builder = self.config_builder.clone();
         for command in commands {
             builder = builder
                 .set_override(command.name.clone(), command.clone())
-                .expect("Setting override");
+                .with_context(|| format!("Setting command \"{}\"", command.name))?;
         }
         self.config_builder = builder;
-        let _ = self.write();
+        self.write()
+            .with_context(|| format!("Writing {}", self.config_filepath().display()))
     }
 
     pub fn write(&self) -> std::io::Result<()> {
@@ -34,7 +34,11 @@ impl ComparisonOperator {
     where
         O: Ord,
     {
-        let result: Ordering = left.cmp(right);
+        self.matches(left.cmp(right))
+    }
+
+    /// Whether the ordering of the left side compared to the right side satisfies the operator.
+    pub fn matches(&self, result: Ordering) -> bool {
         use ComparisonOperator::*;
         match self {
             Lt => result.is_lt(),
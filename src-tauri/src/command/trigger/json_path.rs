@@ -0,0 +1,162 @@
+//! The subset of JSONPath used by [`Trigger::JsonPath`](super::Trigger::JsonPath),
+//! `$` followed by `.field`, `['field']` and `[index]` steps.
+use anyhow::bail;
+use serde_json::Value;
+
+use super::ComparisonOperator;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Field(String),
+    Index(usize),
+}
+
+fn parse(path: &str) -> anyhow::Result<Vec<Step>> {
+    let Some(mut rest) = path.trim().strip_prefix('$') else {
+        bail!("JSONPath must start with '$': {path}");
+    };
+    let mut steps = Vec::new();
+    while !rest.is_empty() {
+        if let Some(r) = rest.strip_prefix('.') {
+            let end = r.find(['.', '[']).unwrap_or(r.len());
+            if end == 0 {
+                bail!("Empty field in JSONPath: {path}");
+            }
+            steps.push(Step::Field(r[..end].to_string()));
+            rest = &r[end..];
+        } else if let Some(r) = rest.strip_prefix('[') {
+            let Some(end) = r.find(']') else {
+                bail!("Unclosed '[' in JSONPath: {path}");
+            };
+            let inner = r[..end].trim();
+            let step = match inner.parse::<usize>() {
+                Ok(index) => Step::Index(index),
+                Err(_) => Step::Field(inner.trim_matches(['\'', '"']).to_string()),
+            };
+            steps.push(step);
+            rest = &r[end + 1..];
+        } else {
+            bail!("Invalid JSONPath: {path}");
+        }
+    }
+    Ok(steps)
+}
+
+/// The value at the path, [`None`] if the path is invalid or not in the value.
+pub fn select<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let steps = parse(path).ok()?;
+    steps.iter().try_fold(value, |value, step| match step {
+        Step::Field(field) => value.get(field),
+        Step::Index(index) => value.get(index),
+    })
+}
+
+/// Whether the path is valid.
+pub fn validate(path: &str) -> anyhow::Result<()> {
+    parse(path).map(|_| ())
+}
+
+/// Compares numbers numerically, including numbers sent as strings, and anything
+/// else as text, `expected` may be quoted. [`ComparisonOperator::Any`] only needs
+/// the field to exist.
+pub fn compare(found: &Value, comparison_operator: &ComparisonOperator, expected: &str) -> bool {
+    let expected = expected.trim();
+    let found_number = match found {
+        Value::String(s) => s.trim().parse::<f64>().ok(),
+        other => other.as_f64(),
+    };
+    let ordering = match (found_number, expected.parse::<f64>()) {
+        (Some(found), Ok(expected)) => found.partial_cmp(&expected),
+        _ => {
+            let found = match found {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            Some(found.as_str().cmp(expected.trim_matches(['\'', '"'])))
+        }
+    };
+    match ordering {
+        Some(ordering) => comparison_operator.matches(ordering),
+        None => comparison_operator == &ComparisonOperator::Any,
+    }
+}
+
+/// Splits an expression such as `$.reward.cost >= 1000` into the path, operator
+/// and value at the first operator, so the value may contain operators too.
+/// Without an operator the field only needs to exist.
+pub fn parse_expression(expression: &str) -> (String, ComparisonOperator, String) {
+    const OPERATORS: [&str; 7] = [">=", "<=", "==", "!=", ">", "<", "="];
+    let first = OPERATORS
+        .into_iter()
+        .filter_map(|operator| Some((expression.find(operator)?, operator)))
+        // The longest operator at the same position, `>=` rather than `>`.
+        .min_by_key(|(position, operator)| (*position, std::cmp::Reverse(operator.len())));
+    let Some((position, operator)) = first else {
+        return (
+            expression.trim().to_string(),
+            ComparisonOperator::Any,
+            String::new(),
+        );
+    };
+    let comparison_operator = match operator {
+        ">=" => ComparisonOperator::Ge,
+        "<=" => ComparisonOperator::Le,
+        "==" | "=" => ComparisonOperator::Eq,
+        "!=" => ComparisonOperator::Ne,
+        ">" => ComparisonOperator::Gt,
+        _ => ComparisonOperator::Lt,
+    };
+    (
+        expression[..position].trim().to_string(),
+        comparison_operator,
+        expression[position + operator.len()..].trim().to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde_json::json;
+
+    fn payload() -> Value {
+        json!({
+            "broadcaster_user_name": "Ozy_Viking",
+            "reward": { "title": "Nuke", "cost": 1500 },
+            "choices": [{ "title": "Biters" }, { "title": "Meteor" }],
+            "is_automatic": false,
+            "duration_seconds": "60",
+            "title": "a >= b"
+        })
+    }
+
+    #[rstest]
+    #[case("$.reward.cost", Some(json!(1500)))]
+    #[case("$['reward']['title']", Some(json!("Nuke")))]
+    #[case("$.choices[1].title", Some(json!("Meteor")))]
+    #[case("$.choices[2].title", None)]
+    #[case("$.missing", None)]
+    #[case("reward.cost", None)]
+    fn selected(#[case] path: &str, #[case] expected: Option<Value>) {
+        assert_eq!(select(&payload(), path).cloned(), expected);
+    }
+
+    #[rstest]
+    #[case("$.reward.cost >= 1000", true)]
+    #[case("$.reward.cost < 1000", false)]
+    #[case("$.reward.title == 'Nuke'", true)]
+    #[case("$.reward.title != Nuke", false)]
+    #[case("$.is_automatic == false", true)]
+    #[case("$.broadcaster_user_name", true)]
+    #[case("$.duration_seconds > 100", false)]
+    #[case("$.duration_seconds >= 60", true)]
+    #[case("$.duration_seconds == 60.0", true)]
+    #[case("$.title == 'a >= b'", true)]
+    #[case("$.title = a = b", false)]
+    fn expression_matched(#[case] expression: &str, #[case] expected: bool) {
+        let (path, comparison_operator, value) = parse_expression(expression);
+        let payload = payload();
+        let found = select(&payload, &path).unwrap();
+        assert_eq!(compare(found, &comparison_operator, &value), expected);
+    }
+}
@@ -3,7 +3,10 @@ use config::{Map, Value, ValueKind};
 use serde::{Deserialize, Serialize};
 use tracing::{error, warn};
 
-use crate::integration::{CustomRewardEvent, CustomRewardVariant, IntegrationEvent};
+use crate::integration::{
+    currency, discord, CustomRewardEvent, CustomRewardVariant, HypeTrainState, IntegrationEvent,
+    PledgeAction,
+};
 
 mod server_trigger;
 pub use server_trigger::GameServerTrigger;
@@ -12,6 +15,7 @@ mod subscription;
 pub use subscription::SubscriptionTier;
 mod comparison_operator;
 pub use comparison_operator::ComparisonOperator;
+pub mod json_path;
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Hash, Eq, PartialOrd, Ord)]
 #[serde(tag = "trigger", content = "data")]
@@ -47,6 +51,85 @@ pub enum Trigger {
         bits: u64,
         comparison_operator: ComparisonOperator,
     },
+    Follow,
+    Raid {
+        viewers: u64,
+        comparison_operator: ComparisonOperator,
+    },
+    HypeTrain {
+        /// [`None`] matches every state.
+        state: Option<HypeTrainState>,
+        level: i64,
+        comparison_operator: ComparisonOperator,
+        /// Only match the first event at each new level.
+        level_increased: bool,
+    },
+    /// Matches the winning choice of a poll or prediction, case insensitive.
+    PollResult {
+        /// Title of the poll, empty matches any poll.
+        title: String,
+        choice: String,
+    },
+    /// Matches a field of a generic EventSub event, e.g. `$.reward.cost >= 1000`,
+    /// or the body of a webhook.
+    JsonPath {
+        /// Subscription type or `webhook.<source>`, empty matches any generic
+        /// event.
+        event_type: String,
+        path: String,
+        comparison_operator: ComparisonOperator,
+        /// Compared as a number if both sides are numbers, otherwise as text.
+        value: String,
+    },
+    /// Matches a Streamlabs donation by amount.
+    Donation {
+        /// In cents of the currency.
+        amount: u64,
+        /// ISO 4217 code, empty compares the amount of any currency.
+        currency: String,
+        comparison_operator: ComparisonOperator,
+    },
+    /// Matches a YouTube Super Chat or Super Sticker by its value in US cents.
+    SuperChat {
+        usd_cents: u64,
+        comparison_operator: ComparisonOperator,
+    },
+    /// Matches a YouTube membership by level name, case insensitive.
+    Membership {
+        /// Empty matches any level.
+        level: String,
+    },
+    /// Matches a generic webhook by amount, in the currency paid.
+    Webhook {
+        /// Name of the route, empty matches any webhook.
+        source: String,
+        /// In cents of the currency.
+        amount: u64,
+        comparison_operator: ComparisonOperator,
+    },
+    /// Matches a Discord slash command, see [`discord`].
+    SlashCommand {
+        /// Name of the command as registered, see [`discord::command_name`].
+        name: String,
+        /// Ids of the roles allowed to use it, empty allows anyone.
+        roles: Vec<String>,
+    },
+    /// Matches a Patreon pledge by tier and amount.
+    Pledge {
+        /// Tier title, case insensitive. Empty matches any tier.
+        tier: String,
+        /// In cents of the campaign's currency.
+        amount: u64,
+        comparison_operator: ComparisonOperator,
+        /// [`None`] matches any action.
+        action: Option<PledgeAction>,
+    },
+    /// Matches any bits, subscription, donation, Super Chat, membership or pledge
+    /// by its value in US cents, see [`currency::ValueRates`].
+    Value {
+        amount: u64,
+        comparison_operator: ComparisonOperator,
+    },
     Server,
 }
 
@@ -126,6 +209,196 @@ impl PartialEq<IntegrationEvent> for Trigger {
                     false
                 }
             }
+            Trigger::Follow => matches!(event, IntegrationEvent::Follow { .. }),
+            Trigger::Raid {
+                viewers: trigger_viewers,
+                comparison_operator,
+            } => {
+                if let IntegrationEvent::Raid {
+                    viewers: event_viewers,
+                    ..
+                } = event
+                {
+                    comparison_operator.compare(event_viewers, trigger_viewers)
+                } else {
+                    false
+                }
+            }
+            Trigger::HypeTrain {
+                state: trigger_state,
+                level: trigger_level,
+                comparison_operator,
+                level_increased: trigger_level_increased,
+            } => {
+                if let IntegrationEvent::HypeTrain {
+                    state: event_state,
+                    level: event_level,
+                    level_increased: event_level_increased,
+                    ..
+                } = event
+                {
+                    let state = trigger_state.as_ref().is_none_or(|s| s == event_state);
+                    let level = comparison_operator.compare(event_level, trigger_level);
+                    let increased = !trigger_level_increased || *event_level_increased;
+                    state & level & increased
+                } else {
+                    false
+                }
+            }
+            Trigger::PollResult {
+                title: trigger_title,
+                choice,
+            } => {
+                if let IntegrationEvent::PollEnd {
+                    title: event_title,
+                    winning_choice,
+                    ..
+                } = event
+                {
+                    let title =
+                        trigger_title.is_empty() || trigger_title.eq_ignore_ascii_case(event_title);
+                    title && choice.eq_ignore_ascii_case(winning_choice)
+                } else {
+                    false
+                }
+            }
+            Trigger::JsonPath {
+                event_type: trigger_event_type,
+                path,
+                comparison_operator,
+                value,
+            } => {
+                let (event_event_type, payload) = match event {
+                    IntegrationEvent::Generic {
+                        event_type,
+                        payload,
+                    } => (event_type.clone(), payload),
+                    IntegrationEvent::Webhook {
+                        source, payload, ..
+                    } => (format!("webhook.{source}"), payload),
+                    _ => return false,
+                };
+                if !trigger_event_type.is_empty() && trigger_event_type != &event_event_type {
+                    return false;
+                }
+                match json_path::select(payload, path) {
+                    Some(found) => json_path::compare(found, comparison_operator, value),
+                    None => false,
+                }
+            }
+            Trigger::Donation {
+                amount: trigger_amount,
+                currency: trigger_currency,
+                comparison_operator,
+            } => {
+                if let IntegrationEvent::Donation {
+                    amount: event_amount,
+                    currency: event_currency,
+                    ..
+                } = event
+                {
+                    let currency = trigger_currency.is_empty()
+                        || trigger_currency.eq_ignore_ascii_case(event_currency);
+                    currency && comparison_operator.compare(event_amount, trigger_amount)
+                } else {
+                    false
+                }
+            }
+            Trigger::SuperChat {
+                usd_cents: trigger_cents,
+                comparison_operator,
+            } => {
+                if let IntegrationEvent::SuperChat {
+                    usd_cents: event_cents,
+                    ..
+                } = event
+                {
+                    match event_cents {
+                        Some(event_cents) => {
+                            comparison_operator.compare(event_cents, trigger_cents)
+                        }
+                        // Without a rate the amount is unknown.
+                        None => comparison_operator == &ComparisonOperator::Any,
+                    }
+                } else {
+                    false
+                }
+            }
+            Trigger::Membership {
+                level: trigger_level,
+            } => {
+                if let IntegrationEvent::Membership {
+                    level: event_level, ..
+                } = event
+                {
+                    trigger_level.is_empty() || trigger_level.eq_ignore_ascii_case(event_level)
+                } else {
+                    false
+                }
+            }
+            Trigger::Webhook {
+                source: trigger_source,
+                amount: trigger_amount,
+                comparison_operator,
+            } => {
+                if let IntegrationEvent::Webhook {
+                    source: event_source,
+                    amount: event_amount,
+                    ..
+                } = event
+                {
+                    let source = trigger_source.is_empty()
+                        || trigger_source.eq_ignore_ascii_case(event_source);
+                    source && comparison_operator.compare(event_amount, trigger_amount)
+                } else {
+                    false
+                }
+            }
+            Trigger::SlashCommand {
+                name: trigger_name,
+                roles: trigger_roles,
+            } => {
+                if let IntegrationEvent::SlashCommand {
+                    name: event_name,
+                    roles: event_roles,
+                    ..
+                } = event
+                {
+                    let roles = trigger_roles.is_empty()
+                        || trigger_roles.iter().any(|r| event_roles.contains(r));
+                    trigger_name == event_name && roles
+                } else {
+                    false
+                }
+            }
+            Trigger::Pledge {
+                tier: trigger_tier,
+                amount: trigger_amount,
+                comparison_operator,
+                action: trigger_action,
+            } => {
+                if let IntegrationEvent::Pledge {
+                    action: event_action,
+                    tier: event_tier,
+                    amount_cents: event_amount,
+                    ..
+                } = event
+                {
+                    let action = trigger_action.as_ref().is_none_or(|a| a == event_action);
+                    let tier =
+                        trigger_tier.is_empty() || trigger_tier.eq_ignore_ascii_case(event_tier);
+                    action && tier && comparison_operator.compare(event_amount, trigger_amount)
+                } else {
+                    false
+                }
+            }
+            Trigger::Value {
+                amount,
+                comparison_operator,
+            } => match event.value_cents(&currency::value_rates()) {
+                Some(value) => comparison_operator.compare(&value, amount),
+                None => false,
+            },
         }
     }
 }
@@ -165,20 +438,99 @@ impl Trigger {
                 count: Default::default(),
                 count_comparison_operator: Default::default(),
             },
+            Follow => Follow,
+            Raid { .. } => Raid {
+                viewers: Default::default(),
+                comparison_operator: Default::default(),
+            },
+            HypeTrain { .. } => HypeTrain {
+                state: Default::default(),
+                level: Default::default(),
+                comparison_operator: Default::default(),
+                level_increased: Default::default(),
+            },
+            PollResult { .. } => PollResult {
+                title: Default::default(),
+                choice: Default::default(),
+            },
+            JsonPath { .. } => JsonPath {
+                event_type: Default::default(),
+                path: Default::default(),
+                comparison_operator: Default::default(),
+                value: Default::default(),
+            },
+            Donation { .. } => Donation {
+                amount: Default::default(),
+                currency: Default::default(),
+                comparison_operator: Default::default(),
+            },
+            SuperChat { .. } => SuperChat {
+                usd_cents: Default::default(),
+                comparison_operator: Default::default(),
+            },
+            Membership { .. } => Membership {
+                level: Default::default(),
+            },
+            Webhook { .. } => Webhook {
+                source: Default::default(),
+                amount: Default::default(),
+                comparison_operator: Default::default(),
+            },
+            SlashCommand { .. } => SlashCommand {
+                name: Default::default(),
+                roles: Default::default(),
+            },
+            Pledge { .. } => Pledge {
+                tier: Default::default(),
+                amount: Default::default(),
+                comparison_operator: Default::default(),
+                action: Default::default(),
+            },
+            Value { .. } => Value {
+                amount: Default::default(),
+                comparison_operator: Default::default(),
+            },
             Server => Server,
         }
     }
 
+    /// Name of the variant as used for `trigger_type` in the config.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Trigger::Chat { .. } => stringify!(Chat),
+            Trigger::ChatRegex { .. } => stringify!(ChatRegex),
+            Trigger::ChannelPointRewardRedeemed { .. } => stringify!(ChannelPointRewardRedeemed),
+            Trigger::Subscription { .. } => stringify!(Subscription),
+            Trigger::GiftSub { .. } => stringify!(GiftSub),
+            Trigger::Bits { .. } => stringify!(Bits),
+            Trigger::Follow => stringify!(Follow),
+            Trigger::Raid { .. } => stringify!(Raid),
+            Trigger::HypeTrain { .. } => stringify!(HypeTrain),
+            Trigger::PollResult { .. } => stringify!(PollResult),
+            Trigger::JsonPath { .. } => stringify!(JsonPath),
+            Trigger::Donation { .. } => stringify!(Donation),
+            Trigger::SuperChat { .. } => stringify!(SuperChat),
+            Trigger::Membership { .. } => stringify!(Membership),
+            Trigger::Webhook { .. } => stringify!(Webhook),
+            Trigger::SlashCommand { .. } => stringify!(SlashCommand),
+            Trigger::Pledge { .. } => stringify!(Pledge),
+            Trigger::Value { .. } => stringify!(Value),
+            Trigger::Server => stringify!(Server),
+        }
+    }
+
     /// Linking [Trigger] with [IntegrationEvent].
     pub fn event_type(&self) -> IntegrationEvent {
         match self {
             Trigger::Chat { .. } => IntegrationEvent::Chat {
                 msg: Default::default(),
                 author: Default::default(),
+                chatter: Default::default(),
             },
             Trigger::ChatRegex { .. } => IntegrationEvent::Chat {
                 msg: Default::default(),
                 author: Default::default(),
+                chatter: Default::default(),
             },
             Trigger::ChannelPointRewardRedeemed { .. } => {
                 IntegrationEvent::ChannelPoint(CustomRewardEvent::default())
@@ -197,6 +549,81 @@ impl Trigger {
                 user_name: Default::default(),
                 bits: Default::default(),
             },
+            Trigger::Follow => IntegrationEvent::Follow {
+                user_name: Default::default(),
+            },
+            Trigger::Raid { .. } => IntegrationEvent::Raid {
+                from: Default::default(),
+                viewers: Default::default(),
+            },
+            Trigger::HypeTrain { .. } => IntegrationEvent::HypeTrain {
+                state: HypeTrainState::Begin,
+                id: Default::default(),
+                level: Default::default(),
+                progress: Default::default(),
+                goal: Default::default(),
+                total: Default::default(),
+                expires_at: twitch_types::Timestamp::new("1970-01-01T00:00:00+00:00".to_string())
+                    .unwrap(),
+                level_increased: Default::default(),
+            },
+            Trigger::PollResult { .. } => IntegrationEvent::PollEnd {
+                title: Default::default(),
+                winning_choice: Default::default(),
+                votes: Default::default(),
+            },
+            Trigger::JsonPath { event_type, .. } => IntegrationEvent::Generic {
+                event_type: event_type.clone(),
+                payload: Default::default(),
+            },
+            Trigger::Donation { .. } => IntegrationEvent::Donation {
+                user_name: Default::default(),
+                amount: Default::default(),
+                currency: Default::default(),
+                usd_cents: Default::default(),
+                message: Default::default(),
+            },
+            Trigger::SuperChat { .. } => IntegrationEvent::SuperChat {
+                user_name: Default::default(),
+                amount_micros: Default::default(),
+                currency: Default::default(),
+                usd_cents: Default::default(),
+                message: Default::default(),
+                sticker: Default::default(),
+            },
+            Trigger::Membership { .. } => IntegrationEvent::Membership {
+                user_name: Default::default(),
+                level: Default::default(),
+                months: Default::default(),
+                gifts: Default::default(),
+            },
+            Trigger::Webhook { source, .. } => IntegrationEvent::Webhook {
+                source: source.clone(),
+                amount: Default::default(),
+                currency: Default::default(),
+                usd_cents: Default::default(),
+                user: Default::default(),
+                message: Default::default(),
+                payload: Default::default(),
+            },
+            Trigger::SlashCommand { name, .. } => IntegrationEvent::SlashCommand {
+                name: name.clone(),
+                user_name: Default::default(),
+                user_id: Default::default(),
+                roles: Default::default(),
+                options: Default::default(),
+                application_id: Default::default(),
+                interaction_id: Default::default(),
+                token: Default::default(),
+            },
+            Trigger::Pledge { .. } => IntegrationEvent::Pledge {
+                action: Default::default(),
+                tier: Default::default(),
+                amount_cents: Default::default(),
+                user: Default::default(),
+            },
+            // Matches several kinds of event.
+            Trigger::Value { .. } => IntegrationEvent::Unknown,
         }
     }
 
@@ -456,33 +883,421 @@ impl TryFrom<Value> for Trigger {
                                 u64::default()
                             }
                         }
-                    }
+                    }
+                    None => {
+                        warn!(
+                        "A trigger_type of '{}', missing 'bits', needs the properties: {:?}. Defaulting to \"{:?}\"",
+                        trigger_type,
+                        vec!["bits", "comparison_operator"],
+                        u64::default()
+                    );
+
+                        u64::default()
+                    }
+                };
+                let comparison_operator = match trigger_table.get("comparison_operator") {
+                    Some(t) => t.clone().into(),
+                    None => {
+                        warn!(
+                        "A trigger_type of '{}', missing 'comparison_operator', needs the properties: {:?} Defaulting to \"{:?}\"",
+                        trigger_type,
+                        vec!["tier", "comparison_operator"],
+                        ComparisonOperator::default()
+
+                    );
+                        ComparisonOperator::default()
+                    }
+                };
+                Ok(Self::Bits {
+                    bits,
+                    comparison_operator,
+                })
+            }
+            "follow" => Ok(Self::Follow),
+            "raid" => {
+                let required_keys = ["viewers", "comparison_operator"];
+                let viewers = match trigger_table.get("viewers") {
+                    Some(t) => match t.clone().into_uint() {
+                        Ok(v) => v,
+                        Err(e) => {
+                            warn!("{e:?}");
+                            warn!(
+                                "viewers is an invalid type, recieved '{t:?}'. A trigger_type of '{}' needs the properties: {:?}. Defaulting to \"{:?}\"",
+                                trigger_type,
+                                required_keys,
+                                u64::default()
+                            );
+                            u64::default()
+                        }
+                    },
+                    None => {
+                        warn!(
+                            "A trigger_type of '{}', missing 'viewers', needs the properties: {:?}. Defaulting to \"{:?}\"",
+                            trigger_type,
+                            required_keys,
+                            u64::default()
+                        );
+                        u64::default()
+                    }
+                };
+                let comparison_operator = match trigger_table.get("comparison_operator") {
+                    Some(t) => t.clone().into(),
+                    None => {
+                        warn!(
+                            "A trigger_type of '{}', missing 'comparison_operator', needs the properties: {:?} Defaulting to \"{:?}\"",
+                            trigger_type,
+                            required_keys,
+                            ComparisonOperator::default()
+                        );
+                        ComparisonOperator::default()
+                    }
+                };
+                Ok(Self::Raid {
+                    viewers,
+                    comparison_operator,
+                })
+            }
+            "hypetrain" => {
+                let required_keys = ["state", "level", "comparison_operator", "level_increased"];
+                let state = match trigger_table.get("state") {
+                    Some(t) => match t.clone().into_string() {
+                        Ok(s) if s.eq_ignore_ascii_case("any") => None,
+                        Ok(s) => match HypeTrainState::try_from(s) {
+                            Ok(state) => Some(state),
+                            Err(e) => {
+                                warn!("{e:?}. Defaulting to \"Any\"");
+                                None
+                            }
+                        },
+                        Err(e) => {
+                            warn!("{e:?}. Defaulting to \"Any\"");
+                            None
+                        }
+                    },
+                    None => None,
+                };
+                let level = match trigger_table.get("level") {
+                    Some(t) => match t.clone().into_int() {
+                        Ok(l) => l,
+                        Err(e) => {
+                            warn!("{e:?}");
+                            warn!(
+                                "level is an invalid type, recieved '{t:?}'. A trigger_type of '{}' needs the properties: {:?}. Defaulting to \"{:?}\"",
+                                trigger_type,
+                                required_keys,
+                                i64::default()
+                            );
+                            i64::default()
+                        }
+                    },
+                    None => i64::default(),
+                };
+                let comparison_operator = match trigger_table.get("comparison_operator") {
+                    Some(t) => t.clone().into(),
+                    None => ComparisonOperator::default(),
+                };
+                let level_increased = match trigger_table.get("level_increased") {
+                    Some(t) => t.clone().into_bool().unwrap_or_else(|e| {
+                        warn!("{e:?}. Defaulting level_increased to false");
+                        false
+                    }),
+                    None => false,
+                };
+                Ok(Self::HypeTrain {
+                    state,
+                    level,
+                    comparison_operator,
+                    level_increased,
+                })
+            }
+            "pollresult" => {
+                let choice = match trigger_table.get("choice") {
+                    Some(c) => match c.clone().into_string() {
+                        Ok(c) => c,
+                        Err(e) => bail!(e),
+                    },
+                    None => bail!(
+                        "A trigger_type of '{}' needs the properties: {:?}",
+                        trigger_type,
+                        vec!["choice"]
+                    ),
+                };
+                let title = match trigger_table.get("title") {
+                    Some(t) => t.clone().into_string().unwrap_or_else(|e| {
+                        warn!("{e:?}. Matching any poll.");
+                        String::new()
+                    }),
+                    None => String::new(),
+                };
+                Ok(Self::PollResult { title, choice })
+            }
+            "jsonpath" => {
+                let event_type = match trigger_table.get("event_type") {
+                    Some(t) => t.clone().into_string().unwrap_or_else(|e| {
+                        warn!("{e:?}. Matching any event type.");
+                        String::new()
+                    }),
+                    None => String::new(),
+                };
+                let (path, comparison_operator, value) = match trigger_table.get("expression") {
+                    Some(e) => match e.clone().into_string() {
+                        Ok(e) => json_path::parse_expression(&e),
+                        Err(e) => bail!(e),
+                    },
+                    None => {
+                        let path = match trigger_table.get("path") {
+                            Some(p) => match p.clone().into_string() {
+                                Ok(p) => p,
+                                Err(e) => bail!(e),
+                            },
+                            None => bail!(
+                                "A trigger_type of '{}' needs the properties: {:?} or {:?}",
+                                trigger_type,
+                                vec!["expression"],
+                                vec!["path", "comparison_operator", "value"]
+                            ),
+                        };
+                        let comparison_operator = match trigger_table.get("comparison_operator") {
+                            Some(t) => t.clone().into(),
+                            None => ComparisonOperator::default(),
+                        };
+                        let value = match trigger_table.get("value") {
+                            Some(v) => v.to_string(),
+                            None => String::new(),
+                        };
+                        (path, comparison_operator, value)
+                    }
+                };
+                json_path::validate(&path)?;
+                Ok(Self::JsonPath {
+                    event_type,
+                    path,
+                    comparison_operator,
+                    value,
+                })
+            }
+            "donation" => {
+                let required_keys = ["amount", "currency", "comparison_operator"];
+                let amount = match trigger_table.get("amount") {
+                    Some(t) => match currency::parse_cents(&t.to_string()) {
+                        Some(c) => c,
+                        None => bail!(
+                            "amount is an invalid amount, recieved '{t}'. A trigger_type of '{}' needs the properties: {:?}",
+                            trigger_type,
+                            required_keys
+                        ),
+                    },
+                    None => {
+                        warn!(
+                            "A trigger_type of '{}', missing 'amount', needs the properties: {:?}. Defaulting to \"{:?}\"",
+                            trigger_type,
+                            required_keys,
+                            u64::default()
+                        );
+                        u64::default()
+                    }
+                };
+                let currency = match trigger_table.get("currency") {
+                    Some(t) => t
+                        .clone()
+                        .into_string()
+                        .map(|c| c.to_uppercase())
+                        .unwrap_or_else(|e| {
+                            warn!("{e:?}. Matching any currency.");
+                            String::new()
+                        }),
+                    None => String::new(),
+                };
+                let comparison_operator = match trigger_table.get("comparison_operator") {
+                    Some(t) => t.clone().into(),
+                    None => ComparisonOperator::default(),
+                };
+                Ok(Self::Donation {
+                    amount,
+                    currency,
+                    comparison_operator,
+                })
+            }
+            "superchat" => {
+                let required_keys = ["amount", "comparison_operator"];
+                let usd_cents = match trigger_table.get("amount") {
+                    Some(t) => match currency::parse_cents(&t.to_string()) {
+                        Some(c) => c,
+                        None => bail!(
+                            "amount is an invalid amount in USD, recieved '{t}'. A trigger_type of '{}' needs the properties: {:?}",
+                            trigger_type,
+                            required_keys
+                        ),
+                    },
+                    None => {
+                        warn!(
+                            "A trigger_type of '{}', missing 'amount', needs the properties: {:?}. Defaulting to \"{:?}\"",
+                            trigger_type,
+                            required_keys,
+                            u64::default()
+                        );
+                        u64::default()
+                    }
+                };
+                let comparison_operator = match trigger_table.get("comparison_operator") {
+                    Some(t) => t.clone().into(),
+                    None => ComparisonOperator::default(),
+                };
+                Ok(Self::SuperChat {
+                    usd_cents,
+                    comparison_operator,
+                })
+            }
+            "membership" => {
+                let level = match trigger_table.get("level") {
+                    Some(t) => t.clone().into_string().unwrap_or_else(|e| {
+                        warn!("{e:?}. Matching any level.");
+                        String::new()
+                    }),
+                    None => String::new(),
+                };
+                Ok(Self::Membership { level })
+            }
+            "webhook" => {
+                let required_keys = ["source", "amount", "comparison_operator"];
+                let source = match trigger_table.get("source") {
+                    Some(t) => t.clone().into_string().unwrap_or_else(|e| {
+                        warn!("{e:?}. Matching any webhook.");
+                        String::new()
+                    }),
+                    None => String::new(),
+                };
+                let amount = match trigger_table.get("amount") {
+                    Some(t) => match currency::parse_cents(&t.to_string()) {
+                        Some(c) => c,
+                        None => bail!(
+                            "amount is an invalid amount, recieved '{t}'. A trigger_type of '{}' needs the properties: {:?}",
+                            trigger_type,
+                            required_keys
+                        ),
+                    },
+                    None => u64::default(),
+                };
+                let comparison_operator = match trigger_table.get("comparison_operator") {
+                    Some(t) => t.clone().into(),
+                    None => ComparisonOperator::default(),
+                };
+                Ok(Self::Webhook {
+                    source,
+                    amount,
+                    comparison_operator,
+                })
+            }
+            "slashcommand" => {
+                let name = match trigger_table.get("name") {
+                    Some(n) => match n.clone().into_string() {
+                        Ok(n) => n,
+                        Err(e) => bail!(e),
+                    },
+                    None => bail!(
+                        "A trigger_type of '{}' needs the properties: {:?}",
+                        trigger_type,
+                        vec!["name", "roles"]
+                    ),
+                };
+                let name = match discord::command_name(&name) {
+                    n if n.is_empty() => bail!(
+                        "'{name}' is not a valid slash command name, it needs letters, digits, '-' or '_'"
+                    ),
+                    n => n,
+                };
+                // Role ids are too long to be anything but text, but may be written as numbers.
+                let roles = match trigger_table.get("roles") {
+                    Some(r) => match r.clone().into_array() {
+                        Ok(roles) => roles
+                            .into_iter()
+                            .filter_map(|r| r.into_string().ok())
+                            .collect(),
+                        Err(e) => {
+                            warn!("{e:?}. Allowing anyone.");
+                            Vec::new()
+                        }
+                    },
+                    None => Vec::new(),
+                };
+                Ok(Self::SlashCommand { name, roles })
+            }
+            "pledge" => {
+                let required_keys = ["tier", "amount", "comparison_operator", "action"];
+                let tier = match trigger_table.get("tier") {
+                    Some(t) => t.clone().into_string().unwrap_or_else(|e| {
+                        warn!("{e:?}. Matching any tier.");
+                        String::new()
+                    }),
+                    None => String::new(),
+                };
+                let amount = match trigger_table.get("amount") {
+                    Some(t) => match currency::parse_cents(&t.to_string()) {
+                        Some(c) => c,
+                        None => bail!(
+                            "amount is an invalid amount, recieved '{t}'. A trigger_type of '{}' needs the properties: {:?}",
+                            trigger_type,
+                            required_keys
+                        ),
+                    },
+                    None => u64::default(),
+                };
+                let comparison_operator = match trigger_table.get("comparison_operator") {
+                    Some(t) => t.clone().into(),
+                    None => ComparisonOperator::default(),
+                };
+                // New pledges unless told otherwise, a deleted pledge is no reason to celebrate.
+                let action = match trigger_table.get("action") {
+                    Some(t) => match t.clone().into_string() {
+                        Ok(s) if s.eq_ignore_ascii_case("any") => None,
+                        Ok(s) => match PledgeAction::try_from(s) {
+                            Ok(action) => Some(action),
+                            Err(e) => {
+                                warn!("{e:?}. Defaulting to \"Create\"");
+                                Some(PledgeAction::Create)
+                            }
+                        },
+                        Err(e) => {
+                            warn!("{e:?}. Defaulting to \"Create\"");
+                            Some(PledgeAction::Create)
+                        }
+                    },
+                    None => Some(PledgeAction::Create),
+                };
+                Ok(Self::Pledge {
+                    tier,
+                    amount,
+                    comparison_operator,
+                    action,
+                })
+            }
+            "value" => {
+                let required_keys = ["amount", "comparison_operator"];
+                let amount = match trigger_table.get("amount") {
+                    Some(t) => match currency::parse_cents(&t.to_string()) {
+                        Some(c) => c,
+                        None => bail!(
+                            "amount is an invalid amount in USD, recieved '{t}'. A trigger_type of '{}' needs the properties: {:?}",
+                            trigger_type,
+                            required_keys
+                        ),
+                    },
                     None => {
                         warn!(
-                        "A trigger_type of '{}', missing 'bits', needs the properties: {:?}. Defaulting to \"{:?}\"",
-                        trigger_type,
-                        vec!["bits", "comparison_operator"],
-                        u64::default()
-                    );
-
+                            "A trigger_type of '{}', missing 'amount', needs the properties: {:?}. Defaulting to \"{:?}\"",
+                            trigger_type,
+                            required_keys,
+                            u64::default()
+                        );
                         u64::default()
                     }
                 };
                 let comparison_operator = match trigger_table.get("comparison_operator") {
                     Some(t) => t.clone().into(),
-                    None => {
-                        warn!(
-                        "A trigger_type of '{}', missing 'comparison_operator', needs the properties: {:?} Defaulting to \"{:?}\"",
-                        trigger_type,
-                        vec!["tier", "comparison_operator"],
-                        ComparisonOperator::default()
-
-                    );
-                        ComparisonOperator::default()
-                    }
+                    None => ComparisonOperator::default(),
                 };
-                Ok(Self::Bits {
-                    bits,
+                Ok(Self::Value {
+                    amount,
                     comparison_operator,
                 })
             }
@@ -577,6 +1392,201 @@ impl From<Trigger> for Value {
                     ValueKind::from(comparison_operator),
                 );
             }
+            Trigger::Follow => {
+                map.insert(
+                    "trigger_type".to_string(),
+                    ValueKind::from(stringify!(Follow)),
+                );
+            }
+            Trigger::Raid {
+                viewers,
+                comparison_operator,
+            } => {
+                map.insert(
+                    "trigger_type".to_string(),
+                    ValueKind::from(stringify!(Raid)),
+                );
+                map.insert("viewers".to_string(), ValueKind::from(viewers));
+                map.insert(
+                    "comparison_operator".to_string(),
+                    ValueKind::from(comparison_operator),
+                );
+            }
+            Trigger::HypeTrain {
+                state,
+                level,
+                comparison_operator,
+                level_increased,
+            } => {
+                map.insert(
+                    "trigger_type".to_string(),
+                    ValueKind::from(stringify!(HypeTrain)),
+                );
+                map.insert(
+                    "state".to_string(),
+                    ValueKind::from(state.map_or("Any".to_string(), |s| s.to_string())),
+                );
+                map.insert("level".to_string(), ValueKind::from(level));
+                map.insert(
+                    "comparison_operator".to_string(),
+                    ValueKind::from(comparison_operator),
+                );
+                map.insert(
+                    "level_increased".to_string(),
+                    ValueKind::from(level_increased),
+                );
+            }
+            Trigger::PollResult { title, choice } => {
+                map.insert(
+                    "trigger_type".to_string(),
+                    ValueKind::from(stringify!(PollResult)),
+                );
+                if !title.is_empty() {
+                    map.insert("title".to_string(), ValueKind::from(title));
+                }
+                map.insert("choice".to_string(), ValueKind::from(choice));
+            }
+            Trigger::JsonPath {
+                event_type,
+                path,
+                comparison_operator,
+                value,
+            } => {
+                map.insert(
+                    "trigger_type".to_string(),
+                    ValueKind::from(stringify!(JsonPath)),
+                );
+                if !event_type.is_empty() {
+                    map.insert("event_type".to_string(), ValueKind::from(event_type));
+                }
+                map.insert("path".to_string(), ValueKind::from(path));
+                map.insert(
+                    "comparison_operator".to_string(),
+                    ValueKind::from(comparison_operator),
+                );
+                map.insert("value".to_string(), ValueKind::from(value));
+            }
+            Trigger::Donation {
+                amount,
+                currency,
+                comparison_operator,
+            } => {
+                map.insert(
+                    "trigger_type".to_string(),
+                    ValueKind::from(stringify!(Donation)),
+                );
+                map.insert(
+                    "amount".to_string(),
+                    ValueKind::from(currency::format_cents(amount)),
+                );
+                if !currency.is_empty() {
+                    map.insert("currency".to_string(), ValueKind::from(currency));
+                }
+                map.insert(
+                    "comparison_operator".to_string(),
+                    ValueKind::from(comparison_operator),
+                );
+            }
+            Trigger::SuperChat {
+                usd_cents,
+                comparison_operator,
+            } => {
+                map.insert(
+                    "trigger_type".to_string(),
+                    ValueKind::from(stringify!(SuperChat)),
+                );
+                map.insert(
+                    "amount".to_string(),
+                    ValueKind::from(currency::format_cents(usd_cents)),
+                );
+                map.insert(
+                    "comparison_operator".to_string(),
+                    ValueKind::from(comparison_operator),
+                );
+            }
+            Trigger::Membership { level } => {
+                map.insert(
+                    "trigger_type".to_string(),
+                    ValueKind::from(stringify!(Membership)),
+                );
+                if !level.is_empty() {
+                    map.insert("level".to_string(), ValueKind::from(level));
+                }
+            }
+            Trigger::Webhook {
+                source,
+                amount,
+                comparison_operator,
+            } => {
+                map.insert(
+                    "trigger_type".to_string(),
+                    ValueKind::from(stringify!(Webhook)),
+                );
+                if !source.is_empty() {
+                    map.insert("source".to_string(), ValueKind::from(source));
+                }
+                map.insert(
+                    "amount".to_string(),
+                    ValueKind::from(currency::format_cents(amount)),
+                );
+                map.insert(
+                    "comparison_operator".to_string(),
+                    ValueKind::from(comparison_operator),
+                );
+            }
+            Trigger::SlashCommand { name, roles } => {
+                map.insert(
+                    "trigger_type".to_string(),
+                    ValueKind::from(stringify!(SlashCommand)),
+                );
+                map.insert("name".to_string(), ValueKind::from(name));
+                if !roles.is_empty() {
+                    map.insert("roles".to_string(), ValueKind::from(roles));
+                }
+            }
+            Trigger::Pledge {
+                tier,
+                amount,
+                comparison_operator,
+                action,
+            } => {
+                map.insert(
+                    "trigger_type".to_string(),
+                    ValueKind::from(stringify!(Pledge)),
+                );
+                if !tier.is_empty() {
+                    map.insert("tier".to_string(), ValueKind::from(tier));
+                }
+                map.insert(
+                    "amount".to_string(),
+                    ValueKind::from(currency::format_cents(amount)),
+                );
+                map.insert(
+                    "comparison_operator".to_string(),
+                    ValueKind::from(comparison_operator),
+                );
+                map.insert(
+                    "action".to_string(),
+                    ValueKind::from(action.map_or("Any".to_string(), |a| a.to_string())),
+                );
+            }
+            Trigger::Value {
+                amount,
+                comparison_operator,
+            } => {
+                map.insert(
+                    "trigger_type".to_string(),
+                    ValueKind::from(stringify!(Value)),
+                );
+                map.insert(
+                    "amount".to_string(),
+                    ValueKind::from(currency::format_cents(amount)),
+                );
+                map.insert(
+                    "comparison_operator".to_string(),
+                    ValueKind::from(comparison_operator),
+                );
+            }
             Trigger::Server => {
                 map.insert(
                     "trigger_type".to_string(),
@@ -594,12 +1604,12 @@ mod tests {
     use rstest::rstest;
 
     #[rstest]
-    #[case("test", false, IntegrationEvent::Chat { msg: "test".to_string(), author: String::new() })]
-    #[case("test", true, IntegrationEvent::Chat { msg: "test".to_string(), author: String::new() })]
-    #[case("Test", false, IntegrationEvent::Chat { msg: "test".to_string(), author: String::new() })]
-    #[case("test", false, IntegrationEvent::Chat { msg: "testa".to_string(), author: String::new() })]
-    #[case("test", true, IntegrationEvent::Chat { msg: "testa".to_string(), author: String::new() })]
-    #[case("Test", false, IntegrationEvent::Chat { msg: "testa".to_string(), author: String::new() })]
+    #[case("test", false, IntegrationEvent::Chat { msg: "test".to_string(), author: String::new(), chatter: Default::default() })]
+    #[case("test", true, IntegrationEvent::Chat { msg: "test".to_string(), author: String::new(), chatter: Default::default() })]
+    #[case("Test", false, IntegrationEvent::Chat { msg: "test".to_string(), author: String::new(), chatter: Default::default() })]
+    #[case("test", false, IntegrationEvent::Chat { msg: "testa".to_string(), author: String::new(), chatter: Default::default() })]
+    #[case("test", true, IntegrationEvent::Chat { msg: "testa".to_string(), author: String::new(), chatter: Default::default() })]
+    #[case("Test", false, IntegrationEvent::Chat { msg: "testa".to_string(), author: String::new(), chatter: Default::default() })]
     fn chat_triggered(
         #[case] pattern: &str,
         #[case] case_sensitive: bool,
@@ -615,9 +1625,9 @@ mod tests {
     }
 
     #[rstest]
-    #[case("testa", false, IntegrationEvent::Chat { msg: "test".to_string(), author: String::new() })]
-    #[case("testa", true, IntegrationEvent::Chat { msg: "test".to_string(), author: String::new() })]
-    #[case("Test", true, IntegrationEvent::Chat { msg: "test".to_string(), author: String::new() })]
+    #[case("testa", false, IntegrationEvent::Chat { msg: "test".to_string(), author: String::new(), chatter: Default::default() })]
+    #[case("testa", true, IntegrationEvent::Chat { msg: "test".to_string(), author: String::new(), chatter: Default::default() })]
+    #[case("Test", true, IntegrationEvent::Chat { msg: "test".to_string(), author: String::new(), chatter: Default::default() })]
     fn chat_not_triggered(
         #[case] pattern: &str,
         #[case] case_sensitive: bool,
@@ -856,7 +1866,7 @@ mod tests {
     #[case(
         SubscriptionTier::Tier2,
         ComparisonOperator::Gt, 
-        IntegrationEvent::Chat { msg: String::from("Test"), author: String::from("Test") }
+        IntegrationEvent::Chat { msg: String::from("Test"), author: String::from("Test"), chatter: Default::default() }
     )]
     fn channel_subscription_not_triggered(
         #[case] tier: SubscriptionTier,
@@ -918,7 +1928,7 @@ mod tests {
         ComparisonOperator::Any,
         5,
         ComparisonOperator::Any,
-        IntegrationEvent::Chat { msg: String::from("Test"), author: String::from("Test") }
+        IntegrationEvent::Chat { msg: String::from("Test"), author: String::from("Test"), chatter: Default::default() }
     )]
     #[case(
         SubscriptionTier::Tier2,
@@ -951,4 +1961,395 @@ mod tests {
             event
         )
     }
+
+    #[rstest]
+    #[case(50, ComparisonOperator::Ge, 50, true)]
+    #[case(50, ComparisonOperator::Ge, 120, true)]
+    #[case(50, ComparisonOperator::Ge, 12, false)]
+    #[case(0, ComparisonOperator::Any, 1, true)]
+    fn raid_triggered(
+        #[case] viewers: u64,
+        #[case] comparison_operator: ComparisonOperator,
+        #[case] event_viewers: u64,
+        #[case] expected: bool,
+    ) {
+        let trigger = Trigger::Raid {
+            viewers,
+            comparison_operator,
+        };
+        let event = IntegrationEvent::Raid {
+            from: String::from("Raider"),
+            viewers: event_viewers,
+        };
+        assert_eq!(trigger.is_match(&event), expected);
+        assert_ne!(Trigger::Follow, event);
+    }
+
+    #[rstest]
+    #[case(Trigger::Follow)]
+    #[case(Trigger::Raid { viewers: 50, comparison_operator: ComparisonOperator::Ge })]
+    #[case(Trigger::PollResult { title: String::new(), choice: "Biters".into() })]
+    #[case(Trigger::PollResult { title: "Which disaster next?".into(), choice: "Meteor".into() })]
+    #[case(Trigger::JsonPath { event_type: "channel.ad_break.begin".into(), path: "$.duration_seconds".into(), comparison_operator: ComparisonOperator::Ge, value: "60".into() })]
+    #[case(Trigger::HypeTrain { state: None, level: 0, comparison_operator: ComparisonOperator::Any, level_increased: false })]
+    #[case(Trigger::HypeTrain { state: Some(HypeTrainState::Progress), level: 3, comparison_operator: ComparisonOperator::Ge, level_increased: true })]
+    #[case(Trigger::SuperChat { usd_cents: 499, comparison_operator: ComparisonOperator::Ge })]
+    #[case(Trigger::Membership { level: String::new() })]
+    #[case(Trigger::Donation { amount: 1000, currency: String::new(), comparison_operator: ComparisonOperator::Ge })]
+    #[case(Trigger::Donation { amount: 550, currency: "EUR".into(), comparison_operator: ComparisonOperator::Gt })]
+    #[case(Trigger::Membership { level: "Engineer".into() })]
+    #[case(Trigger::Value { amount: 2000, comparison_operator: ComparisonOperator::Ge })]
+    #[case(Trigger::Pledge { tier: String::new(), amount: 0, comparison_operator: ComparisonOperator::Any, action: None })]
+    #[case(Trigger::Webhook { source: "kofi".into(), amount: 300, comparison_operator: ComparisonOperator::Ge })]
+    #[case(Trigger::SlashCommand { name: "spawn-biters".into(), roles: Vec::new() })]
+    #[case(Trigger::SlashCommand { name: "nuke".into(), roles: vec!["1234567890123456789".into()] })]
+    #[case(Trigger::Pledge { tier: "Engineer".into(), amount: 500, comparison_operator: ComparisonOperator::Ge, action: Some(PledgeAction::Delete) })]
+    fn config_round_trip(#[case] trigger: Trigger) {
+        assert_eq!(
+            Trigger::try_from(Value::from(trigger.clone())).unwrap(),
+            trigger
+        );
+    }
+
+    fn hype_train(state: HypeTrainState, level: i64, level_increased: bool) -> IntegrationEvent {
+        IntegrationEvent::HypeTrain {
+            state,
+            id: String::from("1"),
+            level,
+            progress: 0,
+            goal: 0,
+            total: 0,
+            expires_at: twitch_types::Timestamp::new("2025-01-01T00:00:00Z".to_string()).unwrap(),
+            level_increased,
+        }
+    }
+
+    #[rstest]
+    #[case(
+        Some(HypeTrainState::Progress),
+        2,
+        ComparisonOperator::Ge,
+        true,
+        hype_train(HypeTrainState::Progress, 2, true),
+        true
+    )]
+    #[case(
+        Some(HypeTrainState::Progress),
+        2,
+        ComparisonOperator::Ge,
+        true,
+        hype_train(HypeTrainState::Progress, 2, false),
+        false
+    )]
+    #[case(
+        Some(HypeTrainState::Progress),
+        2,
+        ComparisonOperator::Ge,
+        true,
+        hype_train(HypeTrainState::Progress, 1, true),
+        false
+    )]
+    #[case(
+        Some(HypeTrainState::Progress),
+        2,
+        ComparisonOperator::Ge,
+        false,
+        hype_train(HypeTrainState::Progress, 3, false),
+        true
+    )]
+    #[case(
+        Some(HypeTrainState::End),
+        0,
+        ComparisonOperator::Any,
+        false,
+        hype_train(HypeTrainState::End, 4, false),
+        true
+    )]
+    #[case(
+        Some(HypeTrainState::End),
+        0,
+        ComparisonOperator::Any,
+        false,
+        hype_train(HypeTrainState::Progress, 4, true),
+        false
+    )]
+    #[case(
+        None,
+        1,
+        ComparisonOperator::Eq,
+        true,
+        hype_train(HypeTrainState::Begin, 1, true),
+        true
+    )]
+    fn hype_train_triggered(
+        #[case] state: Option<HypeTrainState>,
+        #[case] level: i64,
+        #[case] comparison_operator: ComparisonOperator,
+        #[case] level_increased: bool,
+        #[case] event: IntegrationEvent,
+        #[case] expected: bool,
+    ) {
+        let trigger = Trigger::HypeTrain {
+            state,
+            level,
+            comparison_operator,
+            level_increased,
+        };
+        assert_eq!(trigger.is_match(&event), expected);
+    }
+
+    #[rstest]
+    #[case("", "biters", true)]
+    #[case("which disaster next?", "Biters", true)]
+    #[case("Which base next?", "Biters", false)]
+    #[case("", "Meteor", false)]
+    fn poll_result_triggered(#[case] title: &str, #[case] choice: &str, #[case] expected: bool) {
+        let trigger = Trigger::PollResult {
+            title: title.to_string(),
+            choice: choice.to_string(),
+        };
+        let event = IntegrationEvent::PollEnd {
+            title: String::from("Which disaster next?"),
+            winning_choice: String::from("Biters"),
+            votes: 12,
+        };
+        assert_eq!(trigger.is_match(&event), expected);
+    }
+
+    #[rstest]
+    #[case(
+        "channel.channel_points_automatic_reward_redemption.add",
+        "$.reward.cost >= 1000",
+        true
+    )]
+    #[case("", "$.reward.cost >= 1000", true)]
+    #[case("channel.ad_break.begin", "$.reward.cost >= 1000", false)]
+    #[case("", "$.reward.cost > 1000", false)]
+    #[case("", "$.reward.type == gigantify_an_emote", true)]
+    fn json_path_triggered(
+        #[case] event_type: &str,
+        #[case] expression: &str,
+        #[case] expected: bool,
+    ) {
+        let mut table = Map::new();
+        table.insert("trigger_type".to_string(), Value::from("JsonPath"));
+        table.insert("event_type".to_string(), Value::from(event_type));
+        table.insert("expression".to_string(), Value::from(expression));
+        let trigger = Trigger::try_from(Value::new(None, ValueKind::from(table))).unwrap();
+        let event = IntegrationEvent::Generic {
+            event_type: "channel.channel_points_automatic_reward_redemption.add".to_string(),
+            payload: serde_json::json!({
+                "user_name": "Cool_User",
+                "reward": { "type": "gigantify_an_emote", "cost": 1000 }
+            }),
+        };
+        assert_eq!(trigger.is_match(&event), expected);
+    }
+
+    #[rstest]
+    #[case("webhook.kofi", "$.type == Donation", true)]
+    #[case("", "$.type == Donation", true)]
+    #[case("webhook.humble", "$.type == Donation", false)]
+    #[case("webhook.kofi", "$.type == Subscription", false)]
+    fn json_path_webhook_triggered(
+        #[case] event_type: &str,
+        #[case] expression: &str,
+        #[case] expected: bool,
+    ) {
+        let (path, comparison_operator, value) = json_path::parse_expression(expression);
+        let trigger = Trigger::JsonPath {
+            event_type: event_type.to_string(),
+            path,
+            comparison_operator,
+            value,
+        };
+        let event = IntegrationEvent::Webhook {
+            source: "kofi".to_string(),
+            amount: 300,
+            currency: "USD".to_string(),
+            usd_cents: Some(300),
+            user: "Cool_User".to_string(),
+            message: String::new(),
+            payload: serde_json::json!({ "type": "Donation" }),
+        };
+        assert_eq!(trigger.is_match(&event), expected);
+    }
+
+    #[rstest]
+    #[case("", 300, true)]
+    #[case("KoFi", 300, true)]
+    #[case("humble", 300, false)]
+    #[case("kofi", 301, false)]
+    fn webhook_triggered(#[case] source: &str, #[case] amount: u64, #[case] expected: bool) {
+        let trigger = Trigger::Webhook {
+            source: source.to_string(),
+            amount,
+            comparison_operator: ComparisonOperator::Ge,
+        };
+        let event = IntegrationEvent::Webhook {
+            source: "kofi".to_string(),
+            amount: 300,
+            currency: "USD".to_string(),
+            usd_cents: Some(300),
+            user: "Cool_User".to_string(),
+            message: String::new(),
+            payload: serde_json::Value::Null,
+        };
+        assert_eq!(trigger.is_match(&event), expected);
+    }
+
+    #[rstest]
+    #[case("spawn", vec![], true)]
+    #[case("spawn", vec!["1".into(), "2".into()], true)]
+    #[case("spawn", vec!["3".into()], false)]
+    #[case("nuke", vec![], false)]
+    fn slash_command_triggered(
+        #[case] name: &str,
+        #[case] roles: Vec<String>,
+        #[case] expected: bool,
+    ) {
+        let trigger = Trigger::SlashCommand {
+            name: name.to_string(),
+            roles,
+        };
+        let event = IntegrationEvent::SlashCommand {
+            name: "spawn".to_string(),
+            user_name: "Cool User".to_string(),
+            user_id: "42".to_string(),
+            roles: vec!["2".to_string()],
+            options: vec![("COUNT".to_string(), "5".to_string())],
+            application_id: "1".to_string(),
+            interaction_id: "3".to_string(),
+            token: "token".to_string(),
+        };
+        assert_eq!(trigger.is_match(&event), expected);
+    }
+
+    #[rstest]
+    #[case(500, ComparisonOperator::Ge, Some(1080), true)]
+    #[case(500, ComparisonOperator::Ge, Some(499), false)]
+    #[case(500, ComparisonOperator::Ge, None, false)]
+    #[case(0, ComparisonOperator::Any, None, true)]
+    fn super_chat_triggered(
+        #[case] usd_cents: u64,
+        #[case] comparison_operator: ComparisonOperator,
+        #[case] event_cents: Option<u64>,
+        #[case] expected: bool,
+    ) {
+        let trigger = Trigger::SuperChat {
+            usd_cents,
+            comparison_operator,
+        };
+        let event = IntegrationEvent::SuperChat {
+            user_name: String::from("Cool_User"),
+            amount_micros: 10_000_000,
+            currency: String::from("EUR"),
+            usd_cents: event_cents,
+            message: String::new(),
+            sticker: None,
+        };
+        assert_eq!(trigger.is_match(&event), expected);
+    }
+
+    #[rstest]
+    #[case("", true)]
+    #[case("engineer", true)]
+    #[case("Manager", false)]
+    fn membership_triggered(#[case] level: &str, #[case] expected: bool) {
+        let trigger = Trigger::Membership {
+            level: level.to_string(),
+        };
+        let event = IntegrationEvent::Membership {
+            user_name: String::from("Cool_User"),
+            level: String::from("Engineer"),
+            months: 0,
+            gifts: 0,
+        };
+        assert_eq!(trigger.is_match(&event), expected);
+    }
+
+    #[rstest]
+    #[case(1000, "", ComparisonOperator::Ge, true)]
+    #[case(1000, "usd", ComparisonOperator::Ge, true)]
+    #[case(1000, "EUR", ComparisonOperator::Ge, false)]
+    #[case(2000, "USD", ComparisonOperator::Ge, false)]
+    #[case(0, "", ComparisonOperator::Any, true)]
+    fn donation_triggered(
+        #[case] amount: u64,
+        #[case] currency: &str,
+        #[case] comparison_operator: ComparisonOperator,
+        #[case] expected: bool,
+    ) {
+        let trigger = Trigger::Donation {
+            amount,
+            currency: currency.to_string(),
+            comparison_operator,
+        };
+        let event = IntegrationEvent::Donation {
+            user_name: String::from("Cool_User"),
+            amount: 1337,
+            currency: String::from("USD"),
+            usd_cents: Some(1337),
+            message: String::new(),
+        };
+        assert_eq!(trigger.is_match(&event), expected);
+    }
+
+    #[rstest]
+    #[case(IntegrationEvent::Bits { user_name: String::new(), bits: 500 }, true)]
+    #[case(IntegrationEvent::Bits { user_name: String::new(), bits: 499 }, false)]
+    #[case(IntegrationEvent::GiftSub { tier: SubscriptionTier::Tier1, count: 1, user_name: None }, false)]
+    #[case(IntegrationEvent::GiftSub { tier: SubscriptionTier::Tier1, count: 2, user_name: None }, true)]
+    #[case(IntegrationEvent::Donation { user_name: String::new(), amount: 500, currency: "EUR".into(), usd_cents: Some(540), message: String::new() }, true)]
+    #[case(IntegrationEvent::Donation { user_name: String::new(), amount: 500, currency: "XYZ".into(), usd_cents: None, message: String::new() }, false)]
+    #[case(IntegrationEvent::Follow { user_name: String::new() }, false)]
+    fn value_triggered(#[case] event: IntegrationEvent, #[case] expected: bool) {
+        let trigger = Trigger::Value {
+            amount: 500,
+            comparison_operator: ComparisonOperator::Ge,
+        };
+        assert_eq!(trigger.is_match(&event), expected);
+    }
+
+    #[rstest]
+    #[case("", Some(PledgeAction::Create), PledgeAction::Create, true)]
+    #[case("engineer", Some(PledgeAction::Create), PledgeAction::Create, true)]
+    #[case("Manager", Some(PledgeAction::Create), PledgeAction::Create, false)]
+    #[case("", Some(PledgeAction::Create), PledgeAction::Delete, false)]
+    #[case("", None, PledgeAction::Delete, true)]
+    fn pledge_triggered(
+        #[case] tier: &str,
+        #[case] action: Option<PledgeAction>,
+        #[case] event_action: PledgeAction,
+        #[case] expected: bool,
+    ) {
+        let trigger = Trigger::Pledge {
+            tier: tier.to_string(),
+            amount: 500,
+            comparison_operator: ComparisonOperator::Ge,
+            action,
+        };
+        let event = IntegrationEvent::Pledge {
+            action: event_action,
+            tier: String::from("Engineer"),
+            amount_cents: 500,
+            user: String::from("Cool_User"),
+        };
+        assert_eq!(trigger.is_match(&event), expected);
+    }
+
+    #[rstest]
+    fn pledge_defaults_to_create() {
+        let mut table = Map::new();
+        table.insert("trigger_type".to_string(), ValueKind::from("Pledge"));
+        assert_eq!(
+            Trigger::try_from(Value::new(None, ValueKind::from(table))).unwrap(),
+            Trigger::Pledge {
+                tier: String::new(),
+                amount: 0,
+                comparison_operator: ComparisonOperator::Any,
+                action: Some(PledgeAction::Create),
+            }
+        );
+    }
 }
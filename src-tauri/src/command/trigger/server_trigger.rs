@@ -1,10 +1,10 @@
 use anyhow::{bail, Context};
 use config::{Map, Value};
 use serde::{Deserialize, Serialize};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::{
-    integration::IntegrationEvent,
+    integration::{IntegrationEvent, ViewerRole},
     servers::{self, GameServer},
 };
 
@@ -16,6 +16,13 @@ pub struct GameServerTrigger {
     pub server: GameServer,
     pub trigger: Trigger,
     enabled: bool,
+    /// Matching events wait in the [approval queue](crate::command::approval) instead of
+    /// being sent straight to the server.
+    #[serde(default)]
+    requires_approval: bool,
+    /// Only chatters with one of these roles trigger it, anyone when empty.
+    #[serde(default)]
+    viewer_roles: Vec<ViewerRole>,
 }
 
 impl PartialOrd for GameServerTrigger {
@@ -46,6 +53,8 @@ impl GameServerTrigger {
             server,
             trigger,
             enabled: false,
+            requires_approval: false,
+            viewer_roles: Vec::new(),
         }
     }
 
@@ -71,6 +80,33 @@ impl GameServerTrigger {
         self.enabled = false;
     }
 
+    /// Tests whether a moderator has to approve the command before it is sent.
+    pub fn requires_approval(&self) -> bool {
+        self.requires_approval
+    }
+
+    pub fn set_requires_approval(&mut self, requires_approval: bool) {
+        self.requires_approval = requires_approval;
+    }
+
+    /// Roles a chatter needs one of, empty when anyone may trigger it.
+    pub fn viewer_roles(&self) -> &[ViewerRole] {
+        &self.viewer_roles
+    }
+
+    pub fn set_viewer_roles(&mut self, viewer_roles: Vec<ViewerRole>) {
+        self.viewer_roles = viewer_roles;
+    }
+
+    /// Whether the author of the event has one of the [`viewer_roles`](Self::viewer_roles),
+    /// only chat events have an author with roles.
+    fn permitted(&self, event: &IntegrationEvent) -> bool {
+        self.viewer_roles.is_empty()
+            || event
+                .chatter()
+                .is_some_and(|chatter| chatter.has_any_role(&self.viewer_roles))
+    }
+
     /// Tests whether for a given [IntegrationEvent], it triggers, if so returns the [GameServer]
     /// as an option otherwise [None].
     ///
@@ -78,7 +114,7 @@ impl GameServerTrigger {
     pub fn event_triggered(&self, event: &IntegrationEvent) -> Option<GameServer> {
         if !self.enabled {
             None
-        } else if self.trigger.is_match(event) {
+        } else if self.trigger.is_match(event) && self.permitted(event) {
             Some(self.server.clone())
         } else {
             None
@@ -94,6 +130,17 @@ impl From<GameServerTrigger> for Value {
     fn from(server_trigger: GameServerTrigger) -> Self {
         let mut map = Map::new();
         map.insert("enabled".to_string(), Value::from(server_trigger.enabled()));
+        if server_trigger.requires_approval() {
+            map.insert("requires_approval".to_string(), Value::from(true));
+        }
+        if !server_trigger.viewer_roles.is_empty() {
+            let viewer_roles: Vec<String> = server_trigger
+                .viewer_roles
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            map.insert("viewer_roles".to_string(), Value::from(viewer_roles));
+        }
         map.insert(
             "server_name".to_string(),
             Value::from(server_trigger.server.name),
@@ -139,6 +186,29 @@ impl TryFrom<Value> for GameServerTrigger {
                 false
             }
         };
+        let requires_approval = match gst_map.get("requires_approval") {
+            Some(r) => match r.clone().into_bool() {
+                Ok(r) => r,
+                Err(e) => bail!(e),
+            },
+            None => false,
+        };
+        let viewer_roles = match gst_map.get("viewer_roles") {
+            Some(r) => match r.clone().into_array() {
+                Ok(roles) => roles
+                    .into_iter()
+                    .filter_map(|role| match ViewerRole::try_from(role.to_string()) {
+                        Ok(role) => Some(role),
+                        Err(e) => {
+                            warn!("{e}, ignoring it for server {}", &server_name);
+                            None
+                        }
+                    })
+                    .collect(),
+                Err(e) => bail!(e),
+            },
+            None => Vec::new(),
+        };
         let server = match servers::GameServer::try_get(&server_name) {
             Some(s) => s,
             None => {
@@ -161,6 +231,8 @@ impl TryFrom<Value> for GameServerTrigger {
             enabled,
             server,
             trigger,
+            requires_approval,
+            viewer_roles,
         })
     }
 }
@@ -171,7 +243,7 @@ mod tests {
 
     use rstest::{fixture, rstest};
 
-    use crate::servers::Game;
+    use crate::{integration::Chatter, servers::Game};
 
     use super::*;
 
@@ -200,6 +272,7 @@ mod tests {
         IntegrationEvent::Chat {
             msg: "test".into(),
             author: "test".into(),
+            chatter: Default::default(),
         }
     }
 
@@ -208,6 +281,7 @@ mod tests {
         IntegrationEvent::Chat {
             msg: "not".into(),
             author: "not".into(),
+            chatter: Default::default(),
         }
     }
 
@@ -251,6 +325,56 @@ mod tests {
         assert!(server_trigger.disabled());
     }
 
+    #[rstest]
+    fn test_requires_approval(mut server_trigger: GameServerTrigger) {
+        assert!(!server_trigger.requires_approval());
+        let other = server_trigger.clone();
+        server_trigger.set_requires_approval(true);
+        assert!(server_trigger.requires_approval());
+        assert_eq!(server_trigger, other);
+    }
+
+    #[rstest]
+    #[case(vec![], vec![], true)]
+    #[case(vec![ViewerRole::Moderator], vec![], false)]
+    #[case(vec![ViewerRole::Moderator], vec![ViewerRole::Subscriber], false)]
+    #[case(vec![ViewerRole::Moderator, ViewerRole::Vip], vec![ViewerRole::Vip], true)]
+    #[case(vec![ViewerRole::Subscriber], vec![ViewerRole::Broadcaster], true)]
+    fn test_viewer_roles(
+        mut server_trigger: GameServerTrigger,
+        #[case] viewer_roles: Vec<ViewerRole>,
+        #[case] chatter_roles: Vec<ViewerRole>,
+        #[case] triggered: bool,
+    ) {
+        server_trigger.enable();
+        server_trigger.set_viewer_roles(viewer_roles);
+        let event = IntegrationEvent::Chat {
+            msg: "test".into(),
+            author: "test".into(),
+            chatter: Chatter {
+                roles: chatter_roles,
+                ..Default::default()
+            },
+        };
+        assert_eq!(server_trigger.event_triggered(&event).is_some(), triggered);
+    }
+
+    #[rstest]
+    fn test_viewer_roles_to_config(mut server_trigger: GameServerTrigger) {
+        let map = Value::from(server_trigger.clone()).into_table().unwrap();
+        assert!(!map.contains_key("viewer_roles"));
+        server_trigger.set_viewer_roles(vec![ViewerRole::Moderator, ViewerRole::Vip]);
+        let map = Value::from(server_trigger).into_table().unwrap();
+        let viewer_roles: Vec<String> = map["viewer_roles"]
+            .clone()
+            .into_array()
+            .unwrap()
+            .into_iter()
+            .map(|role| role.to_string())
+            .collect();
+        assert_eq!(viewer_roles, vec!["Moderator", "Vip"]);
+    }
+
     #[rstest]
     fn test_set_enabled(mut server_trigger: GameServerTrigger) {
         assert!(!server_trigger.enabled());
@@ -0,0 +1,383 @@
+//! Viewers blocked from triggering any command, and the viewers a command is
+//! limited to with [`allowed_users`](Command::allowed_users). Checked before a
+//! command is sent or queued for approval.
+use std::{
+    fmt::Display,
+    sync::{Arc, LazyLock},
+};
+
+use anyhow::{bail, Result};
+use config::{Config, Map, Value, ValueKind};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use super::{command_logs::CommandLog, settings::ScriptSettings, Command};
+use crate::{integration::IntegrationEvent, settings::Settings, AsyncMutex};
+
+/// Config key of the blocked viewers in the main config file.
+pub const BLOCKED_USERS_KEY: &str = "moderation.blocked_users";
+
+pub static USER_POLICY: LazyLock<Arc<AsyncMutex<UserPolicy>>> = LazyLock::new(|| {
+    let policy = match Settings::new() {
+        Ok(settings) => UserPolicy::from_config(&settings.config()),
+        Err(e) => {
+            error!("Reading the blocked users: {e}");
+            UserPolicy::default()
+        }
+    };
+    Arc::new(AsyncMutex::new(policy))
+});
+
+/// A Twitch, YouTube or Discord user, by id where the platform gives one.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Viewer {
+    /// Empty when only the name is known.
+    #[serde(default)]
+    pub user_id: String,
+    #[serde(default)]
+    pub user_name: String,
+}
+
+impl Viewer {
+    /// The author of the event, with an id for chat and slash commands.
+    pub fn from_event(event: &IntegrationEvent) -> Self {
+        Self {
+            user_id: event.user_id().unwrap_or_default(),
+            user_name: event.username(),
+        }
+    }
+
+    /// Ids are compared when both have one, as names can change, otherwise the
+    /// names ignoring case.
+    pub fn is(&self, other: &Viewer) -> bool {
+        if !self.user_id.is_empty() && !other.user_id.is_empty() {
+            self.user_id == other.user_id
+        } else {
+            !self.user_name.is_empty() && self.user_name.eq_ignore_ascii_case(&other.user_name)
+        }
+    }
+
+    /// Whether an allowlist entry, either an id or a name, is this viewer.
+    pub fn is_entry(&self, entry: &str) -> bool {
+        let entry = entry.trim().trim_start_matches('@');
+        (!self.user_id.is_empty() && self.user_id == entry)
+            || (!self.user_name.is_empty() && self.user_name.eq_ignore_ascii_case(entry))
+    }
+}
+
+impl Display for Viewer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.user_id.as_str() {
+            "" => write!(f, "{}", self.user_name),
+            id => write!(f, "{} ({})", self.user_name, id),
+        }
+    }
+}
+
+impl From<Viewer> for Value {
+    fn from(viewer: Viewer) -> Self {
+        let mut map = Map::new();
+        if !viewer.user_id.is_empty() {
+            map.insert("user_id".to_string(), ValueKind::from(viewer.user_id));
+        }
+        map.insert("user_name".to_string(), ValueKind::from(viewer.user_name));
+        Self::new(None, ValueKind::from(map))
+    }
+}
+
+impl TryFrom<Value> for Viewer {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let viewer = match value.kind {
+            // A bare name.
+            ValueKind::String(user_name) => Self {
+                user_id: String::new(),
+                user_name,
+            },
+            _ => value.try_deserialize::<Viewer>()?,
+        };
+        if viewer.user_id.is_empty() && viewer.user_name.is_empty() {
+            bail!("A blocked user needs a user_id or a user_name");
+        }
+        Ok(viewer)
+    }
+}
+
+/// Why a viewer can't trigger a command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Denial {
+    Blocked(Viewer),
+    NotAllowed { viewer: Viewer, command: String },
+}
+
+impl Display for Denial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Denial::Blocked(viewer) => write!(f, "{viewer} is blocked"),
+            Denial::NotAllowed { viewer, command } => {
+                write!(f, "{viewer} is not allowed to use {command}")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct UserPolicy {
+    blocked: Vec<Viewer>,
+}
+
+impl UserPolicy {
+    /// Reads the blocked viewers, skipping invalid entries.
+    pub fn from_config(config: &Config) -> Self {
+        let blocked = match config.get_array(BLOCKED_USERS_KEY) {
+            Ok(blocked) => blocked
+                .into_iter()
+                .filter_map(|value| match Viewer::try_from(value) {
+                    Ok(viewer) => Some(viewer),
+                    Err(e) => {
+                        error!("Invalid blocked user: {e}");
+                        None
+                    }
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        Self { blocked }
+    }
+
+    pub fn blocked(&self) -> &[Viewer] {
+        &self.blocked
+    }
+
+    pub fn is_blocked(&self, viewer: &Viewer) -> bool {
+        self.blocked.iter().any(|blocked| blocked.is(viewer))
+    }
+
+    /// Adds the viewer, returning `false` if they were already blocked. A
+    /// known id is filled in on an entry blocked by name.
+    pub fn block(&mut self, viewer: Viewer) -> bool {
+        match self.blocked.iter_mut().find(|blocked| blocked.is(&viewer)) {
+            Some(blocked) => {
+                if blocked.user_id.is_empty() && !viewer.user_id.is_empty() {
+                    blocked.user_id = viewer.user_id;
+                }
+                false
+            }
+            None => {
+                self.blocked.push(viewer);
+                true
+            }
+        }
+    }
+
+    /// Removes the viewer, returning `false` if they weren't blocked.
+    pub fn unblock(&mut self, viewer: &Viewer) -> bool {
+        let before = self.blocked.len();
+        self.blocked.retain(|blocked| !blocked.is(viewer));
+        self.blocked.len() != before
+    }
+
+    /// Whether the author of the event may trigger the command.
+    pub fn check(&self, command: &Command, event: &IntegrationEvent) -> Result<(), Denial> {
+        let viewer = Viewer::from_event(event);
+        if self.is_blocked(&viewer) {
+            return Err(Denial::Blocked(viewer));
+        }
+        if !command.allowed_users.is_empty()
+            && !command
+                .allowed_users
+                .iter()
+                .any(|entry| viewer.is_entry(entry))
+        {
+            return Err(Denial::NotAllowed {
+                viewer,
+                command: command.name.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Writes the blocked viewers to the main config file.
+    pub fn save(&self) -> Result<()> {
+        let blocked: Vec<Value> = self.blocked.iter().cloned().map(Value::from).collect();
+        let mut settings = Settings::new()?;
+        if let Err(e) = settings.set_config(BLOCKED_USERS_KEY, blocked) {
+            bail!("Saving the blocked users: {e}");
+        }
+        Ok(())
+    }
+}
+
+async fn update_blocked(update: impl FnOnce(&mut UserPolicy) -> bool) -> Result<bool, String> {
+    let mut policy = USER_POLICY.lock().await;
+    let before = policy.clone();
+    let changed = update(&mut policy);
+    if *policy != before {
+        policy.save().map_err(|e| e.to_string())?;
+    }
+    Ok(changed)
+}
+
+#[tauri::command]
+pub async fn blocked_users() -> Result<Vec<Viewer>, String> {
+    Ok(USER_POLICY.lock().await.blocked().to_vec())
+}
+
+/// Blocks the viewer from every command, returning `false` if they already were.
+#[tauri::command]
+pub async fn block_user(viewer: Viewer) -> Result<bool, String> {
+    info!("Blocking {viewer}");
+    update_blocked(|policy| policy.block(viewer)).await
+}
+
+#[tauri::command]
+pub async fn unblock_user(viewer: Viewer) -> Result<bool, String> {
+    info!("Unblocking {viewer}");
+    update_blocked(|policy| policy.unblock(&viewer)).await
+}
+
+/// Blocks whoever triggered the logged command.
+#[tauri::command]
+pub async fn block_command_log_user(command_log: CommandLog) -> Result<bool, String> {
+    let viewer = Viewer {
+        user_id: command_log.event().user_id().unwrap_or_default(),
+        user_name: command_log.username().clone(),
+    };
+    if viewer.user_name.is_empty() || viewer.user_name == "<server>" {
+        return Err("The command wasn't triggered by a viewer.".to_string());
+    }
+    block_user(viewer).await
+}
+
+/// Adds an id or name to the users the command is limited to.
+#[tauri::command]
+pub async fn allow_user(name: String, user: String) -> Result<Command, String> {
+    update_allowed(&name, |allowed_users| {
+        if !allowed_users.contains(&user) {
+            allowed_users.push(user);
+        }
+    })
+}
+
+/// Removes an id or name from the users the command is limited to, anyone can
+/// use it once the list is empty.
+#[tauri::command]
+pub async fn disallow_user(name: String, user: String) -> Result<Command, String> {
+    update_allowed(&name, |allowed_users| {
+        allowed_users.retain(|allowed| !allowed.eq_ignore_ascii_case(&user))
+    })
+}
+
+fn update_allowed(name: &str, update: impl FnOnce(&mut Vec<String>)) -> Result<Command, String> {
+    let Some(mut command) = ScriptSettings::get_commands()
+        .into_iter()
+        .find(|c| c.id() == name)
+    else {
+        return Err(format!("Command {name} not found."));
+    };
+    update(&mut command.allowed_users);
+    command.update_config();
+    Ok(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        command::{Prefix, RconCommand, RconCommandLua},
+        integration::Chatter,
+    };
+    use rstest::rstest;
+
+    fn viewer(user_id: &str, user_name: &str) -> Viewer {
+        Viewer {
+            user_id: user_id.into(),
+            user_name: user_name.into(),
+        }
+    }
+
+    fn chat(user_id: &str, author: &str) -> IntegrationEvent {
+        IntegrationEvent::Chat {
+            msg: "!boom".into(),
+            author: author.into(),
+            chatter: Chatter {
+                user_id: user_id.into(),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn command(allowed_users: &[&str]) -> Command {
+        let rcon_lua = RconCommand {
+            prefix: Prefix::SC,
+            lua_command: RconCommandLua::Inline("game.print('boom')".into()),
+            variables: None,
+        };
+        let mut command = Command::new("boom", rcon_lua);
+        command.allowed_users = allowed_users.iter().map(|u| u.to_string()).collect();
+        command
+    }
+
+    #[rstest]
+    #[case(viewer("1", "a"), viewer("1", "renamed"), true)]
+    #[case(viewer("1", "a"), viewer("2", "a"), false)]
+    #[case(viewer("", "Legend"), viewer("1", "legend"), true)]
+    #[case(viewer("1", "a"), viewer("", "A"), true)]
+    #[case(viewer("", ""), viewer("", ""), false)]
+    fn viewer_is(#[case] blocked: Viewer, #[case] viewer: Viewer, #[case] expected: bool) {
+        assert_eq!(blocked.is(&viewer), expected);
+    }
+
+    #[rstest]
+    #[case(&[], chat("1", "Legend"), None)]
+    #[case(&[], chat("2", "Blocked"), Some(Denial::Blocked(viewer("2", "Blocked"))))]
+    #[case(&[], chat("", "blocked"), Some(Denial::Blocked(viewer("", "blocked"))))]
+    #[case(&["Legend"], chat("1", "legend"), None)]
+    #[case(&["1"], chat("1", "Renamed"), None)]
+    #[case(&["@legend"], chat("1", "Legend"), None)]
+    #[case(&["Legend"], chat("3", "Other"), Some(Denial::NotAllowed { viewer: viewer("3", "Other"), command: "boom".into() }))]
+    #[case(&["Blocked"], chat("2", "Blocked"), Some(Denial::Blocked(viewer("2", "Blocked"))))]
+    fn check(
+        #[case] allowed_users: &[&str],
+        #[case] event: IntegrationEvent,
+        #[case] denial: Option<Denial>,
+    ) {
+        let policy = UserPolicy {
+            blocked: vec![viewer("2", "Blocked")],
+        };
+        assert_eq!(policy.check(&command(allowed_users), &event).err(), denial);
+    }
+
+    #[rstest]
+    fn block_and_unblock() {
+        let mut policy = UserPolicy::default();
+        assert!(policy.block(viewer("", "Legend")));
+        assert!(!policy.block(viewer("1", "legend")));
+        assert_eq!(policy.blocked(), &[viewer("1", "Legend")]);
+        assert!(policy.unblock(&viewer("1", "Renamed")));
+        assert!(!policy.unblock(&viewer("1", "Renamed")));
+        assert!(policy.blocked().is_empty());
+    }
+
+    #[rstest]
+    fn from_config() {
+        let config = Config::builder()
+            .set_override(
+                BLOCKED_USERS_KEY,
+                vec![
+                    Value::from(viewer("1", "Legend")),
+                    Value::from("NameOnly"),
+                    Value::from(Map::<String, Value>::new()),
+                ],
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        let policy = UserPolicy::from_config(&config);
+        assert_eq!(
+            policy.blocked(),
+            &[viewer("1", "Legend"), viewer("", "NameOnly")]
+        );
+    }
+}
@@ -44,14 +44,14 @@ impl Variable {
         T: Display,
     {
         if self.name() == "USERNAME" {
-            return format!(r#"local {} = "{}";"#, &self.name, username);
+            return format!(r#"local {} = "{}";"#, &self.name, escape_lua(username));
         }
         match &self.r#type {
             VariableType::String(default) => {
                 format!(
                     r#"local {} = "{}";"#,
                     &self.name,
-                    value.map(|v| v.to_string()).unwrap_or(default.clone())
+                    escape_lua(&value.map(|v| v.to_string()).unwrap_or(default.clone()))
                 )
             }
             VariableType::Int(default) => {
@@ -172,6 +172,23 @@ impl Variable {
         None
     }
 
+    /// Returns a [`Variable`] with the value parsed from `value` as this variable's type.
+    pub fn with_value(&self, value: &str) -> Result<Self, VariableError> {
+        let r#type = match self.r#type {
+            VariableType::String(_) => VariableType::String(value.to_string()),
+            VariableType::Int(_) => VariableType::Int(
+                i64::from_str(value.trim()).map_err(VariableError::ParseIntError)?,
+            ),
+            VariableType::Float(_) => VariableType::Float(
+                f64::from_str(value.trim()).map_err(VariableError::ParseFloatError)?,
+            ),
+        };
+        Ok(Self {
+            name: self.name.clone(),
+            r#type,
+        })
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -189,6 +206,34 @@ impl Variable {
     // }
 }
 
+/// Escapes text for a double quoted Lua string, so a viewer's message can't end
+/// the string and run their own Lua.
+pub fn escape_lua(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl Display for Variable {
+    /// The `name:type=default` form read by [`Variable::from_config`].
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.r#type {
+            VariableType::String(default) if default.is_empty() => write!(f, "{}", self.name),
+            VariableType::String(default) => write!(f, "{}={}", self.name, default),
+            VariableType::Int(default) => write!(f, "{}:int={}", self.name, default),
+            VariableType::Float(default) => write!(f, "{}:float={}", self.name, default),
+        }
+    }
+}
+
 impl Ord for Variable {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         self.name.cmp(&other.name)
@@ -370,6 +415,48 @@ mod tests {
         assert_eq!(variable.command_local_lua(value, "test").as_str(), expected);
     }
 
+    #[rstest]
+    #[case("x")]
+    #[case("x=hello-world")]
+    #[case("x:int=5")]
+    #[case("x:float=5.1")]
+    fn display_round_trip(#[case] input_str: &str) {
+        let variable = Variable::from_str(input_str).unwrap();
+        assert_eq!(variable.to_string(), input_str);
+    }
+
+    #[rstest]
+    #[case(r#"a\b"#, r#"a\\b"#)]
+    #[case("line\nnext\r", r#"line\nnext\r"#)]
+    #[case(r#"say "hi""#, r#"say \"hi\""#)]
+    fn escape_lua_text(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(escape_lua(text), expected);
+    }
+
+    #[rstest]
+    fn command_local_lua_escapes_username() {
+        let variable = V {
+            name: "USERNAME".into(),
+            r#type: VT::String("".into()),
+        };
+        assert_eq!(
+            variable.command_local_lua(None::<&str>, r#"Le"gend"#),
+            r#"local USERNAME = "Le\"gend";"#
+        );
+    }
+
+    #[rstest]
+    fn command_local_lua_escapes_strings() {
+        let variable = V {
+            name: "MESSAGE".into(),
+            r#type: VT::String("".into()),
+        };
+        assert_eq!(
+            variable.command_local_lua(Some(r#"hi"); game.print("boom"#), "test"),
+            r#"local MESSAGE = "hi\"); game.print(\"boom";"#
+        );
+    }
+
     #[rstest]
     #[case(V{ name: "x".into(), r#type: VT::Int(5)}, "", None)]
     #[case(V{ name: "x".into(), r#type: VT::Int(5)}, "HI you awesome", None)]
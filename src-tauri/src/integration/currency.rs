@@ -0,0 +1,248 @@
+//! Converting paid messages and donations in any currency to US cents, so a
+//! single trigger amount covers every viewer.
+//!
+//! [`ValueRates`] go further and give bits, subscriptions and memberships a
+//! value in US cents too, so any support can be compared as `VALUE`.
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, RwLock},
+};
+
+use config::Config;
+use tracing::warn;
+
+/// Approximate value of one unit of each currency in USD, override or extend
+/// them with `currency_rates.<CODE>` in the config.
+const DEFAULT_RATES: [(&str, f64); 24] = [
+    ("USD", 1.0),
+    ("AUD", 0.66),
+    ("BRL", 0.18),
+    ("CAD", 0.73),
+    ("CHF", 1.13),
+    ("CZK", 0.043),
+    ("DKK", 0.145),
+    ("EUR", 1.08),
+    ("GBP", 1.27),
+    ("HKD", 0.128),
+    ("INR", 0.012),
+    ("JPY", 0.0067),
+    ("KRW", 0.00073),
+    ("MXN", 0.055),
+    ("NOK", 0.093),
+    ("NZD", 0.6),
+    ("PHP", 0.018),
+    ("PLN", 0.25),
+    ("RUB", 0.011),
+    ("SEK", 0.095),
+    ("SGD", 0.74),
+    ("TRY", 0.03),
+    ("TWD", 0.031),
+    ("ZAR", 0.054),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurrencyRates {
+    /// USD per unit, keyed by ISO 4217 code in upper case.
+    rates: HashMap<String, f64>,
+}
+
+impl Default for CurrencyRates {
+    fn default() -> Self {
+        Self {
+            rates: DEFAULT_RATES
+                .iter()
+                .map(|(code, rate)| (code.to_string(), *rate))
+                .collect(),
+        }
+    }
+}
+
+impl CurrencyRates {
+    /// The default rates with any in the `currency_rates` table of the config.
+    pub fn from_config(config: &Config) -> Self {
+        let mut currency_rates = Self::default();
+        if let Ok(table) = config.get_table("currency_rates") {
+            for (code, rate) in table {
+                match rate.into_float() {
+                    Ok(rate) if rate > 0.0 => {
+                        currency_rates.rates.insert(code.to_uppercase(), rate);
+                    }
+                    _ => {
+                        warn!("Invalid rate for currency_rates.{code}, expected a positive number")
+                    }
+                }
+            }
+        }
+        currency_rates
+    }
+
+    /// The amount in US cents, [`None`] for an unknown currency.
+    pub fn to_usd_cents(&self, amount_micros: u64, currency: &str) -> Option<u64> {
+        let rate = self.rates.get(&currency.to_uppercase())?;
+        Some((amount_micros as f64 * rate / 10_000.0).round() as u64)
+    }
+
+    /// The amount in cents of `currency` as US cents, [`None`] for an unknown currency.
+    pub fn cents_to_usd_cents(&self, cents: u64, currency: &str) -> Option<u64> {
+        let rate = self.rates.get(&currency.to_uppercase())?;
+        Some((cents as f64 * rate).round() as u64)
+    }
+}
+
+/// What bits, subscriptions and memberships are worth in US cents, override them
+/// with the `value` table in the config.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueRates {
+    /// US cents per bit.
+    pub bit_cents: f64,
+    pub tier1_cents: u64,
+    pub tier2_cents: u64,
+    pub tier3_cents: u64,
+    pub prime_cents: u64,
+    /// Price of a YouTube membership, whatever its level.
+    pub membership_cents: u64,
+}
+
+impl Default for ValueRates {
+    fn default() -> Self {
+        Self {
+            bit_cents: 1.0,
+            tier1_cents: 499,
+            tier2_cents: 999,
+            tier3_cents: 2499,
+            prime_cents: 499,
+            membership_cents: 499,
+        }
+    }
+}
+
+impl ValueRates {
+    /// The defaults with any in the `value` table of the config, in USD.
+    pub fn from_config(config: &Config) -> Self {
+        let mut value_rates = Self::default();
+        let usd = |key: &str| match config.get_float(&format!("value.{key}")) {
+            Ok(usd) if usd >= 0.0 => Some(usd * 100.0),
+            Ok(_) => {
+                warn!("Invalid value.{key}, expected a positive number of USD");
+                None
+            }
+            Err(_) => None,
+        };
+        if let Some(cents) = usd("bit") {
+            value_rates.bit_cents = cents;
+        }
+        for (key, rate) in [
+            ("tier1", &mut value_rates.tier1_cents),
+            ("tier2", &mut value_rates.tier2_cents),
+            ("tier3", &mut value_rates.tier3_cents),
+            ("prime", &mut value_rates.prime_cents),
+            ("membership", &mut value_rates.membership_cents),
+        ] {
+            if let Some(cents) = usd(key) {
+                *rate = cents.round() as u64;
+            }
+        }
+        value_rates
+    }
+
+    pub fn bits_cents(&self, bits: u64) -> u64 {
+        (bits as f64 * self.bit_cents).round() as u64
+    }
+}
+
+/// Rates used by triggers and the `VALUE` variable, set when an integration
+/// connects.
+static VALUE_RATES: LazyLock<RwLock<ValueRates>> =
+    LazyLock::new(|| RwLock::new(ValueRates::default()));
+
+pub fn value_rates() -> ValueRates {
+    VALUE_RATES
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+pub fn set_value_rates(value_rates: ValueRates) {
+    *VALUE_RATES.write().unwrap_or_else(|e| e.into_inner()) = value_rates;
+}
+
+/// Parses an amount such as `5` or `4.99` into cents, as used by triggers.
+pub fn parse_cents(amount: &str) -> Option<u64> {
+    let amount: f64 = amount.trim().trim_start_matches('$').parse().ok()?;
+    if amount.is_sign_negative() || !amount.is_finite() {
+        return None;
+    }
+    Some((amount * 100.0).round() as u64)
+}
+
+/// Formats cents as a decimal amount, e.g. `499` as `4.99`.
+pub fn format_cents(cents: u64) -> String {
+    format!("{}.{:02}", cents / 100, cents % 100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(5_000_000, "USD", Some(500))]
+    #[case(5_000_000, "usd", Some(500))]
+    #[case(10_000_000, "EUR", Some(1080))]
+    #[case(1_000_000_000, "JPY", Some(670))]
+    #[case(5_000_000, "XXX", None)]
+    fn usd_cents(
+        #[case] amount_micros: u64,
+        #[case] currency: &str,
+        #[case] expected: Option<u64>,
+    ) {
+        assert_eq!(
+            CurrencyRates::default().to_usd_cents(amount_micros, currency),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case(500, "USD", Some(500))]
+    #[case(1000, "EUR", Some(1080))]
+    #[case(u64::MAX, "USD", Some(u64::MAX))]
+    #[case(500, "XXX", None)]
+    fn cents_as_usd_cents(
+        #[case] cents: u64,
+        #[case] currency: &str,
+        #[case] expected: Option<u64>,
+    ) {
+        assert_eq!(
+            CurrencyRates::default().cents_to_usd_cents(cents, currency),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn value_rates_from_config() {
+        let config = Config::builder()
+            .set_override("value.bit", 0.014)
+            .unwrap()
+            .set_override("value.tier1", 5)
+            .unwrap()
+            .build()
+            .unwrap();
+        let value_rates = ValueRates::from_config(&config);
+        assert_eq!(value_rates.bits_cents(1000), 1400);
+        assert_eq!(value_rates.tier1_cents, 500);
+        assert_eq!(value_rates.tier2_cents, 999);
+    }
+
+    #[rstest]
+    #[case("5", Some(500))]
+    #[case("4.99", Some(499))]
+    #[case("$10.5", Some(1050))]
+    #[case("-1", None)]
+    #[case("five", None)]
+    fn cents(#[case] amount: &str, #[case] expected: Option<u64>) {
+        assert_eq!(parse_cents(amount), expected);
+        if let Some(cents) = expected {
+            assert_eq!(parse_cents(&format_cents(cents)), Some(cents));
+        }
+    }
+}
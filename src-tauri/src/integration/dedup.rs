@@ -0,0 +1,207 @@
+//! Twitch delivers EventSub notifications at least once and may resend them,
+//! especially around reconnects. Message and event ids seen recently are kept
+//! on disk so a resent notification is dropped, even across a restart.
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{LazyLock, Mutex},
+    time::Duration,
+};
+
+use anyhow::Result;
+use time::OffsetDateTime;
+use tracing::{debug, error};
+
+use super::metrics::METRICS;
+
+/// Most ids remembered, the oldest are forgotten first.
+pub const SEEN_CAPACITY: usize = 10_000;
+
+/// Twitch treats notifications older than 10 minutes as replays.
+pub const SEEN_TTL: Duration = Duration::from_secs(10 * 60);
+
+pub static SEEN_IDS: LazyLock<Mutex<SeenIds>> = LazyLock::new(|| {
+    let folder = if cfg!(dev) { "RCON2.0-dev" } else { "RCON2.0" };
+    let path = dirs::cache_dir().unwrap().join(folder).join("seen_ids");
+    let seen_ids = sled::open(&path)
+        .map_err(anyhow::Error::from)
+        .and_then(|db| SeenIds::persisted(SEEN_CAPACITY, SEEN_TTL, db.open_tree("seen_ids")?));
+    match seen_ids {
+        Ok(seen_ids) => Mutex::new(seen_ids),
+        Err(e) => {
+            error!(
+                "Failed to open {}, duplicates will only be dropped until restart: {e}",
+                path.display()
+            );
+            Mutex::new(SeenIds::new(SEEN_CAPACITY, SEEN_TTL))
+        }
+    }
+});
+
+/// Returns `true` if the id was seen within [`SEEN_TTL`] and counts it in
+/// [`METRICS`]. The id is only remembered by [`mark_seen`], once the event was
+/// passed on, so a notification that failed is handled when it is resent.
+pub fn is_seen(id: &str) -> bool {
+    let mut seen_ids = SEEN_IDS.lock().unwrap_or_else(|e| e.into_inner());
+    if seen_ids.contains(id, OffsetDateTime::now_utc()) {
+        debug!("Dropped duplicate: {id}");
+        METRICS.duplicate_dropped();
+        true
+    } else {
+        false
+    }
+}
+
+/// Remembers the id for [`is_seen`].
+pub fn mark_seen(id: &str) {
+    let mut seen_ids = SEEN_IDS.lock().unwrap_or_else(|e| e.into_inner());
+    seen_ids.insert(id, OffsetDateTime::now_utc());
+}
+
+/// A bounded set of ids which expire after `ttl`.
+#[derive(Debug)]
+pub struct SeenIds {
+    seen: HashMap<String, OffsetDateTime>,
+    /// Oldest first.
+    order: VecDeque<(String, OffsetDateTime)>,
+    capacity: usize,
+    ttl: Duration,
+    tree: Option<sled::Tree>,
+}
+
+impl SeenIds {
+    /// Only kept in memory.
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            seen: HashMap::new(),
+            order: VecDeque::new(),
+            capacity,
+            ttl,
+            tree: None,
+        }
+    }
+
+    /// Loads the ids stored in `tree` and keeps it up to date.
+    pub fn persisted(capacity: usize, ttl: Duration, tree: sled::Tree) -> Result<Self> {
+        let mut stored = Vec::new();
+        for entry in tree.iter() {
+            let (id, seen_at) = entry?;
+            let Ok(seen_at) = <[u8; 8]>::try_from(seen_at.as_ref()) else {
+                tree.remove(&id)?;
+                continue;
+            };
+            let seen_at = OffsetDateTime::from_unix_timestamp(i64::from_be_bytes(seen_at))?;
+            stored.push((String::from_utf8_lossy(&id).to_string(), seen_at));
+        }
+        stored.sort_by_key(|(_, seen_at)| *seen_at);
+        let mut seen_ids = Self::new(capacity, ttl);
+        for (id, seen_at) in stored {
+            seen_ids.seen.insert(id.clone(), seen_at);
+            seen_ids.order.push_back((id, seen_at));
+        }
+        seen_ids.tree = Some(tree);
+        seen_ids.expire(OffsetDateTime::now_utc());
+        Ok(seen_ids)
+    }
+
+    /// Returns `false` if the id was already seen within the ttl.
+    pub fn insert(&mut self, id: &str, now: OffsetDateTime) -> bool {
+        self.expire(now);
+        if self.seen.contains_key(id) {
+            return false;
+        }
+        self.seen.insert(id.to_string(), now);
+        self.order.push_back((id.to_string(), now));
+        if let Some(tree) = &self.tree {
+            if let Err(e) = tree.insert(id, &now.unix_timestamp().to_be_bytes()) {
+                error!("Failed to store seen id: {e}");
+            }
+        }
+        while self.order.len() > self.capacity {
+            self.pop_oldest();
+        }
+        true
+    }
+
+    /// Returns `true` if the id was seen within the ttl.
+    pub fn contains(&mut self, id: &str, now: OffsetDateTime) -> bool {
+        self.expire(now);
+        self.seen.contains_key(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    fn expire(&mut self, now: OffsetDateTime) {
+        while self
+            .order
+            .front()
+            .is_some_and(|(_, seen_at)| *seen_at + self.ttl <= now)
+        {
+            self.pop_oldest();
+        }
+    }
+
+    fn pop_oldest(&mut self) {
+        if let Some((id, _)) = self.order.pop_front() {
+            self.seen.remove(&id);
+            if let Some(tree) = &self.tree {
+                let _ = tree.remove(&id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn duplicates_expire() {
+        let start = OffsetDateTime::now_utc();
+        let mut seen_ids = SeenIds::new(10, Duration::from_secs(60));
+        assert!(seen_ids.insert("a", start));
+        assert!(!seen_ids.insert("a", start + Duration::from_secs(59)));
+        assert!(seen_ids.insert("a", start + Duration::from_secs(60)));
+    }
+
+    #[rstest]
+    fn contains_does_not_remember() {
+        let now = OffsetDateTime::now_utc();
+        let mut seen_ids = SeenIds::new(10, Duration::from_secs(60));
+        assert!(!seen_ids.contains("a", now));
+        assert!(!seen_ids.contains("a", now));
+        assert!(seen_ids.insert("a", now));
+        assert!(seen_ids.contains("a", now + Duration::from_secs(59)));
+        assert!(!seen_ids.contains("a", now + Duration::from_secs(60)));
+    }
+
+    #[rstest]
+    fn oldest_forgotten_at_capacity() {
+        let now = OffsetDateTime::now_utc();
+        let mut seen_ids = SeenIds::new(2, Duration::from_secs(60));
+        assert!(seen_ids.insert("a", now));
+        assert!(seen_ids.insert("b", now));
+        assert!(seen_ids.insert("c", now));
+        assert_eq!(seen_ids.len(), 2);
+        assert!(seen_ids.insert("a", now));
+        assert!(!seen_ids.insert("c", now));
+    }
+
+    #[rstest]
+    fn kept_across_restart() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let tree = db.open_tree("seen_ids").unwrap();
+        let mut seen_ids = SeenIds::persisted(10, SEEN_TTL, tree.clone()).unwrap();
+        assert!(seen_ids.insert("a", OffsetDateTime::now_utc()));
+        drop(seen_ids);
+        let mut seen_ids = SeenIds::persisted(10, SEEN_TTL, tree).unwrap();
+        assert_eq!(seen_ids.len(), 1);
+        assert!(!seen_ids.insert("a", OffsetDateTime::now_utc()));
+    }
+}
@@ -0,0 +1,207 @@
+//! Just enough of a [Discord gateway](https://discord.com/developers/docs/events/gateway)
+//! client to receive interactions: identify, heartbeat and pass on dispatches.
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::time::{sleep_until, Instant};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, warn};
+use url::Url;
+
+type WebsocketStream =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// Slash commands need no privileged intents, or any intents at all.
+pub const INTENTS: u64 = 0;
+
+/// A gateway payload, `t` and `s` are only set for dispatches.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Payload {
+    pub op: u8,
+    #[serde(default)]
+    pub d: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub s: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub t: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Opcode {
+    Dispatch {
+        name: String,
+        data: Value,
+    },
+    Heartbeat,
+    Reconnect,
+    /// Whether the session can be resumed, this client always identifies again.
+    InvalidSession(bool),
+    Hello {
+        heartbeat_interval: u64,
+    },
+    HeartbeatAck,
+    Unknown(u8),
+}
+
+impl From<Payload> for Opcode {
+    fn from(payload: Payload) -> Self {
+        match payload.op {
+            0 => Self::Dispatch {
+                name: payload.t.unwrap_or_default(),
+                data: payload.d,
+            },
+            1 => Self::Heartbeat,
+            7 => Self::Reconnect,
+            9 => Self::InvalidSession(payload.d.as_bool().unwrap_or_default()),
+            10 => Self::Hello {
+                heartbeat_interval: payload.d["heartbeat_interval"].as_u64().unwrap_or(41_250),
+            },
+            11 => Self::HeartbeatAck,
+            op => Self::Unknown(op),
+        }
+    }
+}
+
+/// An identified connection, heartbeating as the hello asks.
+pub struct Gateway {
+    stream: WebsocketStream,
+    heartbeat_interval: Duration,
+    next_heartbeat: Instant,
+    /// Sequence of the last dispatch, sent with each heartbeat.
+    sequence: Option<u64>,
+    /// Whether the last heartbeat was acknowledged, a zombied connection isn't.
+    acknowledged: bool,
+}
+
+impl Gateway {
+    /// Connects, waits for the hello and identifies with the bot token.
+    pub async fn connect(url: &Url, token: &str) -> Result<Self> {
+        let (mut stream, _response) = tokio_tungstenite::connect_async(url.as_str()).await?;
+        let heartbeat_interval = loop {
+            match stream.next().await {
+                Some(Ok(Message::Text(text))) => {
+                    match Opcode::from(serde_json::from_str::<Payload>(&text)?) {
+                        Opcode::Hello { heartbeat_interval } => break heartbeat_interval,
+                        opcode => debug!("Before hello: {opcode:?}"),
+                    }
+                }
+                Some(Ok(Message::Close(frame))) => bail!("Gateway closed: {frame:?}"),
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => bail!(e),
+                None => bail!("Gateway closed before the hello"),
+            }
+        };
+        let identify = Payload {
+            op: 2,
+            d: json!({
+                "token": token,
+                "intents": INTENTS,
+                "properties": { "os": std::env::consts::OS, "browser": "RCON2", "device": "RCON2" },
+            }),
+            s: None,
+            t: None,
+        };
+        stream
+            .send(Message::text(serde_json::to_string(&identify)?))
+            .await?;
+        let heartbeat_interval = Duration::from_millis(heartbeat_interval);
+        Ok(Self {
+            stream,
+            heartbeat_interval,
+            next_heartbeat: Instant::now() + heartbeat_interval,
+            sequence: None,
+            acknowledged: true,
+        })
+    }
+
+    async fn heartbeat(&mut self) -> Result<()> {
+        let heartbeat = Payload {
+            op: 1,
+            d: json!(self.sequence),
+            s: None,
+            t: None,
+        };
+        self.stream
+            .send(Message::text(serde_json::to_string(&heartbeat)?))
+            .await?;
+        self.next_heartbeat = Instant::now() + self.heartbeat_interval;
+        Ok(())
+    }
+
+    /// The next dispatch, [`None`] once the gateway closes the connection or
+    /// asks to reconnect.
+    pub async fn next_dispatch(&mut self) -> Result<Option<(String, Value)>> {
+        loop {
+            let message = tokio::select! {
+                message = self.stream.next() => message,
+                _ = sleep_until(self.next_heartbeat) => {
+                    if !self.acknowledged {
+                        warn!("Discord gateway stopped acknowledging heartbeats");
+                        return Ok(None);
+                    }
+                    self.acknowledged = false;
+                    self.heartbeat().await?;
+                    continue;
+                }
+            };
+            let text = match message {
+                Some(Ok(Message::Text(text))) => text,
+                Some(Ok(Message::Close(frame))) => {
+                    debug!("Discord gateway closed: {frame:?}");
+                    return Ok(None);
+                }
+                None => return Ok(None),
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => bail!(e),
+            };
+            let payload: Payload = match serde_json::from_str(&text) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    warn!("Unknown Discord gateway payload {text}: {e}");
+                    continue;
+                }
+            };
+            if payload.s.is_some() {
+                self.sequence = payload.s;
+            }
+            match Opcode::from(payload) {
+                Opcode::Dispatch { name, data } => return Ok(Some((name, data))),
+                Opcode::Heartbeat => self.heartbeat().await?,
+                Opcode::HeartbeatAck => self.acknowledged = true,
+                Opcode::Reconnect => return Ok(None),
+                Opcode::InvalidSession(_) => bail!("Discord gateway session is invalid"),
+                opcode @ (Opcode::Hello { .. } | Opcode::Unknown(_)) => {
+                    debug!("Ignoring {opcode:?}")
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(
+        r#"{"op":10,"d":{"heartbeat_interval":45000}}"#,
+        Opcode::Hello { heartbeat_interval: 45000 }
+    )]
+    #[case(r#"{"op":11}"#, Opcode::HeartbeatAck)]
+    #[case(r#"{"op":1,"d":null}"#, Opcode::Heartbeat)]
+    #[case(r#"{"op":7,"d":null}"#, Opcode::Reconnect)]
+    #[case(r#"{"op":9,"d":false}"#, Opcode::InvalidSession(false))]
+    #[case(
+        r#"{"op":0,"t":"READY","s":1,"d":{"v":10}}"#,
+        Opcode::Dispatch { name: "READY".into(), data: json!({"v": 10}) }
+    )]
+    #[case(r#"{"op":42,"d":null}"#, Opcode::Unknown(42))]
+    fn parse(#[case] text: &str, #[case] expected: Opcode) {
+        let payload: Payload = serde_json::from_str(text).unwrap();
+        assert_eq!(Opcode::from(payload), expected);
+    }
+}
@@ -0,0 +1,580 @@
+//! A Discord bot registering a [slash command](https://discord.com/developers/docs/interactions/application-commands)
+//! for each command with a `SlashCommand` trigger. The command's variables are
+//! its options, and the result is posted back as a follow-up to the interaction.
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use anyhow::{bail, Result};
+use config::Config;
+use serde_json::{json, Value};
+use tokio::sync::{broadcast::error::RecvError, mpsc::Sender};
+use tracing::{debug, error, info, instrument, warn};
+use url::Url;
+
+pub mod gateway;
+
+use gateway::Gateway;
+
+use super::{
+    feedback::{Feedback, FEEDBACK},
+    status::{IntegrationError, IntegrationStatus},
+    Api, IntegrationEvent,
+};
+use crate::command::{Command, Trigger, VariableType};
+
+pub const GATEWAY_URL: &str = "wss://gateway.discord.gg/?v=10&encoding=json";
+pub const API_URL: &str = "https://discord.com/api/v10";
+
+/// Wait before reconnecting after the gateway drops.
+pub const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Interaction callback types.
+const CHANNEL_MESSAGE: u8 = 4;
+const DEFERRED_CHANNEL_MESSAGE: u8 = 5;
+/// Message flag only showing the message to the user who used the command.
+const EPHEMERAL: u64 = 1 << 6;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiscordSettings {
+    pub bot_token: String,
+    /// Server to register the commands in, [`None`] registers them globally.
+    pub guild_id: Option<String>,
+}
+
+impl DiscordSettings {
+    /// Reads `auth.discord`.
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            bot_token: config
+                .get_string("auth.discord.bot_token")
+                .unwrap_or_default(),
+            guild_id: config
+                .get_string("auth.discord.guild_id")
+                .ok()
+                .filter(|id| !id.is_empty()),
+        }
+    }
+}
+
+/// `DISCORD_GATEWAY_URL` replaces the gateway for testing.
+pub fn gateway_url() -> Result<Url> {
+    let url = std::env::var("DISCORD_GATEWAY_URL").unwrap_or_else(|_| GATEWAY_URL.to_string());
+    Ok(Url::parse(&url)?)
+}
+
+/// `DISCORD_API_URL` replaces the REST api for testing.
+pub fn api_url() -> String {
+    std::env::var("DISCORD_API_URL")
+        .unwrap_or_else(|_| API_URL.to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// Lowercase letters, digits, `-` and `_`, at most 32 characters, as Discord
+/// accepts for command and option names.
+pub fn command_name(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .map(|c| if c.is_whitespace() { '-' } else { c })
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .take(32)
+        .collect()
+}
+
+/// Discord limits descriptions to 100 characters.
+fn description(text: &str) -> String {
+    text.chars().take(100).collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlashOption {
+    pub name: String,
+    /// Name of the command variable the value is for.
+    pub variable: String,
+    /// Application command option type.
+    pub kind: u8,
+    pub description: String,
+}
+
+impl SlashOption {
+    pub fn from_variable(name: &str, variable_type: &VariableType) -> Self {
+        let kind = match variable_type {
+            VariableType::String(_) => 3,
+            VariableType::Int(_) => 4,
+            VariableType::Float(_) => 10,
+        };
+        Self {
+            name: command_name(name),
+            variable: name.to_string(),
+            kind,
+            description: description(&format!("{name}, {variable_type} by default")),
+        }
+    }
+}
+
+/// A slash command mirroring one or more [`Command`]s.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlashCommand {
+    pub name: String,
+    pub description: String,
+    pub options: Vec<SlashOption>,
+    /// Roles of each enabled trigger, one without roles lets anyone use it.
+    pub roles: Vec<Vec<String>>,
+}
+
+impl SlashCommand {
+    /// Whether a member with the roles may use the command.
+    pub fn permitted(&self, member_roles: &[String]) -> bool {
+        self.roles
+            .iter()
+            .any(|roles| roles.is_empty() || roles.iter().any(|r| member_roles.contains(r)))
+    }
+
+    /// The application command to register, all options are optional as the
+    /// variables have defaults.
+    pub fn to_json(&self) -> Value {
+        let options: Vec<Value> = self
+            .options
+            .iter()
+            .map(|option| {
+                json!({
+                    "type": option.kind,
+                    "name": option.name,
+                    "description": option.description,
+                    "required": false,
+                })
+            })
+            .collect();
+        json!({
+            "type": 1,
+            "name": self.name,
+            "description": self.description,
+            "options": options,
+        })
+    }
+
+    /// Maps an `INTERACTION_CREATE` to this command. Options are keyed by the
+    /// variable they are for, and the user is the member's nickname if set.
+    pub fn event(&self, interaction: &Value) -> IntegrationEvent {
+        let member = &interaction["member"];
+        // Outside a server the user isn't a member.
+        let user = match &member["user"] {
+            Value::Null => &interaction["user"],
+            user => user,
+        };
+        let user_name = [&member["nick"], &user["global_name"], &user["username"]]
+            .into_iter()
+            .find_map(|name| name.as_str().filter(|n| !n.is_empty()))
+            .unwrap_or_default()
+            .to_string();
+        let options = interaction["data"]["options"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|option| {
+                let name = option["name"].as_str()?;
+                let variable = &self.options.iter().find(|o| o.name == name)?.variable;
+                let value = match &option["value"] {
+                    Value::String(s) => s.clone(),
+                    value => value.to_string(),
+                };
+                Some((variable.clone(), value))
+            })
+            .collect();
+        let text = |value: &Value| value.as_str().unwrap_or_default().to_string();
+        IntegrationEvent::SlashCommand {
+            name: self.name.clone(),
+            user_name,
+            user_id: text(&user["id"]),
+            roles: member_roles(interaction),
+            options,
+            application_id: text(&interaction["application_id"]),
+            interaction_id: text(&interaction["id"]),
+            token: text(&interaction["token"]),
+        }
+    }
+}
+
+fn member_roles(interaction: &Value) -> Vec<String> {
+    interaction["member"]["roles"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|r| r.as_str().map(|r| r.to_string()))
+        .collect()
+}
+
+/// A slash command for each name used by the enabled `SlashCommand` triggers,
+/// with the options of the first command using it.
+pub fn slash_commands(commands: &[Command]) -> Vec<SlashCommand> {
+    let mut slash_commands: Vec<SlashCommand> = Vec::new();
+    for command in commands {
+        for server_trigger in command.server_triggers.iter().filter(|st| st.enabled()) {
+            let Trigger::SlashCommand { name, roles } = &server_trigger.trigger else {
+                continue;
+            };
+            if let Some(slash_command) = slash_commands.iter_mut().find(|c| &c.name == name) {
+                slash_command.roles.push(roles.clone());
+                continue;
+            }
+            let options = command
+                .rcon_lua
+                .variables
+                .iter()
+                .flatten()
+                .filter(|v| v.name() != "USERNAME")
+                .map(|v| SlashOption::from_variable(v.name(), v.variable_type()))
+                .collect();
+            slash_commands.push(SlashCommand {
+                name: name.clone(),
+                description: description(&format!("Runs {}", command.name)),
+                options,
+                roles: vec![roles.clone()],
+            });
+        }
+    }
+    slash_commands
+}
+
+/// The few REST endpoints the bot uses.
+#[derive(Debug, Clone)]
+pub struct DiscordRest {
+    client: reqwest::Client,
+    base: String,
+    bot_token: String,
+}
+
+impl DiscordRest {
+    pub fn new(bot_token: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base: api_url(),
+            bot_token: bot_token.to_string(),
+        }
+    }
+
+    async fn request(&self, method: reqwest::Method, path: &str, body: &Value) -> Result<()> {
+        let response = self
+            .client
+            .request(method, format!("{}{path}", self.base))
+            .header("Authorization", format!("Bot {}", self.bot_token))
+            .json(body)
+            .send()
+            .await?;
+        if let Err(e) = response.error_for_status_ref() {
+            bail!("{e}: {}", response.text().await.unwrap_or_default());
+        }
+        Ok(())
+    }
+
+    /// Replaces the registered commands, in the guild if there is one.
+    pub async fn register_commands(
+        &self,
+        application_id: &str,
+        guild_id: Option<&str>,
+        commands: &[SlashCommand],
+    ) -> Result<()> {
+        let path = match guild_id {
+            Some(guild_id) => format!("/applications/{application_id}/guilds/{guild_id}/commands"),
+            None => format!("/applications/{application_id}/commands"),
+        };
+        let commands: Vec<Value> = commands.iter().map(|c| c.to_json()).collect();
+        self.request(reqwest::Method::PUT, &path, &json!(commands))
+            .await
+    }
+
+    /// Responds to the interaction within the 3 seconds Discord waits.
+    pub async fn respond(&self, interaction_id: &str, token: &str, response: Value) -> Result<()> {
+        let path = format!("/interactions/{interaction_id}/{token}/callback");
+        self.request(reqwest::Method::POST, &path, &response).await
+    }
+
+    /// Posts a message after a deferred response, the first replaces "thinking".
+    pub async fn follow_up(&self, application_id: &str, token: &str, content: &str) -> Result<()> {
+        let path = format!("/webhooks/{application_id}/{token}");
+        let content: String = content.trim().chars().take(2000).collect();
+        self.request(reqwest::Method::POST, &path, &json!({ "content": content }))
+            .await
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DiscordApiConnection {
+    connected: Arc<AtomicBool>,
+    joinhandle: Option<tauri::async_runtime::JoinHandle<()>>,
+    /// Posts the replies to interactions.
+    feedback_joinhandle: Option<tauri::async_runtime::JoinHandle<()>>,
+}
+
+impl DiscordApiConnection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Connects to the gateway with slash commands for `commands`, reconnecting
+    /// whenever it drops, replacing any running connection.
+    #[instrument(skip_all)]
+    pub async fn run(
+        &mut self,
+        config: Config,
+        commands: Vec<Command>,
+        event_tx: Sender<IntegrationEvent>,
+    ) -> Result<()> {
+        let settings = DiscordSettings::from_config(&config);
+        if settings.bot_token.is_empty() {
+            bail!("auth.discord.bot_token is required");
+        }
+        let url = gateway_url()?;
+        let rest = DiscordRest::new(&settings.bot_token);
+        let slash_commands = slash_commands(&commands);
+        for jh in [self.joinhandle.take(), self.feedback_joinhandle.take()]
+            .into_iter()
+            .flatten()
+        {
+            jh.abort();
+        }
+        self.feedback_joinhandle = Some(send_replies(rest.clone()));
+        let connected = Arc::clone(&self.connected);
+        self.joinhandle = Some(tauri::async_runtime::spawn(async move {
+            loop {
+                match Gateway::connect(&url, &settings.bot_token).await {
+                    Ok(gateway) => {
+                        info!("Connected to Discord");
+                        connected.store(true, Ordering::Relaxed);
+                        let _ = event_tx.send(IntegrationEvent::Connected).await;
+                        if let Err(e) =
+                            listen(gateway, &settings, &rest, &slash_commands, &event_tx).await
+                        {
+                            error!("Discord gateway error: {e}");
+                        }
+                        connected.store(false, Ordering::Relaxed);
+                        if event_tx.is_closed() {
+                            return;
+                        }
+                        let _ = event_tx.send(IntegrationEvent::Disconnected).await;
+                    }
+                    Err(e) => error!("Failed to connect to Discord: {e}"),
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        }));
+        Ok(())
+    }
+
+    pub fn check_status(&self) -> Result<IntegrationStatus, IntegrationError> {
+        match &self.joinhandle {
+            None => Ok(IntegrationStatus::NotStarted),
+            Some(_) if self.connected.load(Ordering::Relaxed) => Ok(IntegrationStatus::Connected {
+                api: Api::Discord,
+                expires_at: None,
+            }),
+            Some(jh) if jh.inner().is_finished() => {
+                Ok(IntegrationStatus::Disconnected(Api::Discord))
+            }
+            Some(_) => Ok(IntegrationStatus::Connecting(Api::Discord)),
+        }
+    }
+}
+
+async fn listen(
+    mut gateway: Gateway,
+    settings: &DiscordSettings,
+    rest: &DiscordRest,
+    slash_commands: &[SlashCommand],
+    event_tx: &Sender<IntegrationEvent>,
+) -> Result<()> {
+    while let Some((name, data)) = gateway.next_dispatch().await? {
+        match name.as_str() {
+            "READY" => {
+                let application_id = data["application"]["id"].as_str().unwrap_or_default();
+                info!(
+                    "Discord bot {} is ready",
+                    data["user"]["username"].as_str().unwrap_or_default()
+                );
+                match rest
+                    .register_commands(application_id, settings.guild_id.as_deref(), slash_commands)
+                    .await
+                {
+                    Ok(()) => info!("Registered {} slash commands", slash_commands.len()),
+                    Err(e) => error!("Failed to register the slash commands: {e}"),
+                }
+            }
+            "INTERACTION_CREATE" => {
+                if let Err(e) = handle_interaction(&data, rest, slash_commands, event_tx).await {
+                    error!("Failed to handle the Discord interaction: {e}");
+                }
+            }
+            name => debug!("Ignoring Discord {name}"),
+        }
+    }
+    Ok(())
+}
+
+/// Checks the member may use the command and passes it on, the result follows
+/// the deferred response. Denied or unknown commands only get an answer the
+/// user can see.
+async fn handle_interaction(
+    interaction: &Value,
+    rest: &DiscordRest,
+    slash_commands: &[SlashCommand],
+    event_tx: &Sender<IntegrationEvent>,
+) -> Result<()> {
+    // Only application commands, not autocomplete or components.
+    if interaction["type"].as_u64() != Some(2) {
+        return Ok(());
+    }
+    let id = interaction["id"].as_str().unwrap_or_default();
+    let token = interaction["token"].as_str().unwrap_or_default();
+    let name = interaction["data"]["name"].as_str().unwrap_or_default();
+    let ephemeral = |content: String| json!({ "type": CHANNEL_MESSAGE, "data": { "content": content, "flags": EPHEMERAL } });
+    let Some(slash_command) = slash_commands.iter().find(|c| c.name == name) else {
+        warn!("Unknown slash command /{name}");
+        return rest
+            .respond(id, token, ephemeral(format!("/{name} isn't set up.")))
+            .await;
+    };
+    if !slash_command.permitted(&member_roles(interaction)) {
+        info!("Denied /{name}, missing a role");
+        return rest
+            .respond(
+                id,
+                token,
+                ephemeral(format!("You don't have a role allowed to use /{name}.")),
+            )
+            .await;
+    }
+    rest.respond(id, token, json!({ "type": DEFERRED_CHANNEL_MESSAGE }))
+        .await?;
+    event_tx.send(slash_command.event(interaction)).await?;
+    Ok(())
+}
+
+/// Posts the [`Feedback::Reply`]s to slash commands in the background.
+fn send_replies(rest: DiscordRest) -> tauri::async_runtime::JoinHandle<()> {
+    let mut rx = FEEDBACK.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(Feedback::Reply {
+                    event:
+                        IntegrationEvent::SlashCommand {
+                            application_id,
+                            token,
+                            ..
+                        },
+                    message,
+                }) => {
+                    if let Err(e) = rest.follow_up(&application_id, &token, &message).await {
+                        warn!("Failed to reply to the slash command: {e}");
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => warn!("Dropped {skipped} replies."),
+                Err(RecvError::Closed) => break,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        command::{GameServerTrigger, Prefix, RconCommand, RconCommandLua, Variable},
+        servers::GameServer,
+    };
+    use rstest::rstest;
+
+    fn command(name: &str, roles: Vec<String>) -> Command {
+        let rcon = RconCommand {
+            prefix: Prefix::default(),
+            lua_command: RconCommandLua::Inline(String::new()),
+            variables: Variable::from_config("USERNAME,COUNT:int=1,SPEED:float=1.5,MOB").unwrap(),
+        };
+        let mut server_trigger = GameServerTrigger::new(
+            GameServer::default(),
+            Trigger::SlashCommand {
+                name: command_name(name),
+                roles,
+            },
+        );
+        server_trigger.enable();
+        Command::from_config(name, rcon, vec![server_trigger])
+    }
+
+    #[rstest]
+    #[case("Spawn Biters", "spawn-biters")]
+    #[case("nuke!", "nuke")]
+    #[case(&"a".repeat(40), &"a".repeat(32))]
+    fn names(#[case] name: &str, #[case] expected: &str) {
+        assert_eq!(command_name(name), expected);
+    }
+
+    #[rstest]
+    fn options_from_variables() {
+        let slash_commands = slash_commands(&[command("Spawn", Vec::new())]);
+        assert_eq!(
+            slash_commands[0].to_json()["options"],
+            json!([
+                { "type": 4, "name": "count", "description": "COUNT, 1 by default", "required": false },
+                { "type": 10, "name": "speed", "description": "SPEED, 1.5 by default", "required": false },
+                { "type": 3, "name": "mob", "description": "MOB,  by default", "required": false },
+            ])
+        );
+    }
+
+    #[rstest]
+    #[case(vec![], vec![], true)]
+    #[case(vec!["1".into()], vec![], false)]
+    #[case(vec!["1".into()], vec!["2".into(), "1".into()], true)]
+    #[case(vec!["1".into()], vec!["2".into()], false)]
+    fn permitted(
+        #[case] roles: Vec<String>,
+        #[case] member_roles: Vec<String>,
+        #[case] expected: bool,
+    ) {
+        let slash_commands = slash_commands(&[command("Spawn", roles)]);
+        assert_eq!(slash_commands[0].permitted(&member_roles), expected);
+    }
+
+    #[rstest]
+    fn interaction_event() {
+        let slash_commands = slash_commands(&[command("Spawn", Vec::new())]);
+        let interaction = json!({
+            "id": "3",
+            "application_id": "1",
+            "type": 2,
+            "token": "interaction-token",
+            "data": {
+                "name": "spawn",
+                "options": [
+                    { "name": "count", "type": 4, "value": 5 },
+                    { "name": "mob", "type": 3, "value": "biter" }
+                ]
+            },
+            "member": {
+                "nick": null,
+                "roles": ["7"],
+                "user": { "id": "42", "username": "cool_user", "global_name": "Cool User" }
+            }
+        });
+        assert_eq!(
+            slash_commands[0].event(&interaction),
+            IntegrationEvent::SlashCommand {
+                name: "spawn".into(),
+                user_name: "Cool User".into(),
+                user_id: "42".into(),
+                roles: vec!["7".into()],
+                options: vec![("COUNT".into(), "5".into()), ("MOB".into(), "biter".into())],
+                application_id: "1".into(),
+                interaction_id: "3".into(),
+                token: "interaction-token".into(),
+            }
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use std::fmt::Display;
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+use super::Platform;
+
+/// Standing of a chatter in the channel, see `roles` on a server trigger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ViewerRole {
+    Broadcaster,
+    Moderator,
+    Vip,
+    /// A subscriber on Twitch, a member on YouTube.
+    Subscriber,
+}
+
+impl Display for ViewerRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Broadcaster => write!(f, "Broadcaster"),
+            Self::Moderator => write!(f, "Moderator"),
+            Self::Vip => write!(f, "Vip"),
+            Self::Subscriber => write!(f, "Subscriber"),
+        }
+    }
+}
+
+impl TryFrom<String> for ViewerRole {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "broadcaster" | "owner" => Ok(Self::Broadcaster),
+            "moderator" | "mod" => Ok(Self::Moderator),
+            "vip" => Ok(Self::Vip),
+            "subscriber" | "sub" | "member" => Ok(Self::Subscriber),
+            _ => bail!("Invalid input for viewer role: {}", value),
+        }
+    }
+}
+
+/// A chat badge, e.g. `subscriber` version `3012` with the months in `info`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Badge {
+    pub set_id: String,
+    pub id: String,
+    #[serde(default)]
+    pub info: String,
+}
+
+/// Who sent a chat message.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Chatter {
+    pub user_id: String,
+    pub message_id: String,
+    pub roles: Vec<ViewerRole>,
+    pub badges: Vec<Badge>,
+    /// Months subscribed as shown on the badge, 0 when not subscribed.
+    pub sub_months: u64,
+    /// Twitch or YouTube chat.
+    #[serde(default)]
+    pub platform: Platform,
+}
+
+impl Chatter {
+    /// Reads the roles and months from Twitch chat badges.
+    pub fn from_badges(user_id: &str, message_id: &str, badges: Vec<Badge>) -> Self {
+        let mut roles: Vec<ViewerRole> = badges
+            .iter()
+            .filter_map(|badge| match badge.set_id.as_str() {
+                "broadcaster" => Some(ViewerRole::Broadcaster),
+                "moderator" | "lead_moderator" => Some(ViewerRole::Moderator),
+                "vip" => Some(ViewerRole::Vip),
+                "subscriber" | "founder" => Some(ViewerRole::Subscriber),
+                _ => None,
+            })
+            .collect();
+        roles.sort();
+        roles.dedup();
+        let sub_months = badges
+            .iter()
+            .filter(|badge| matches!(badge.set_id.as_str(), "subscriber" | "founder"))
+            .find_map(|badge| badge.info.parse().ok())
+            .unwrap_or_default();
+        Self {
+            user_id: user_id.to_string(),
+            message_id: message_id.to_string(),
+            roles,
+            badges,
+            sub_months,
+            platform: Platform::Twitch,
+        }
+    }
+
+    /// Whether the chatter has any of the roles, the broadcaster has them all.
+    pub fn has_any_role(&self, roles: &[ViewerRole]) -> bool {
+        self.roles.contains(&ViewerRole::Broadcaster)
+            || roles.iter().any(|role| self.roles.contains(role))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn badge(set_id: &str, id: &str, info: &str) -> Badge {
+        Badge {
+            set_id: set_id.into(),
+            id: id.into(),
+            info: info.into(),
+        }
+    }
+
+    #[rstest]
+    #[case(vec![], vec![], 0)]
+    #[case(vec![badge("moderator", "1", ""), badge("subscriber", "3012", "14")], vec![ViewerRole::Moderator, ViewerRole::Subscriber], 14)]
+    #[case(vec![badge("founder", "0", "3"), badge("bits", "100", "")], vec![ViewerRole::Subscriber], 3)]
+    #[case(vec![badge("broadcaster", "1", ""), badge("vip", "1", "")], vec![ViewerRole::Broadcaster, ViewerRole::Vip], 0)]
+    fn from_badges(
+        #[case] badges: Vec<Badge>,
+        #[case] roles: Vec<ViewerRole>,
+        #[case] sub_months: u64,
+    ) {
+        let chatter = Chatter::from_badges("1", "2", badges);
+        assert_eq!(chatter.roles, roles);
+        assert_eq!(chatter.sub_months, sub_months);
+    }
+
+    #[rstest]
+    #[case(vec![ViewerRole::Moderator], &[ViewerRole::Moderator], true)]
+    #[case(vec![ViewerRole::Subscriber], &[ViewerRole::Moderator, ViewerRole::Vip], false)]
+    #[case(vec![ViewerRole::Broadcaster], &[ViewerRole::Subscriber], true)]
+    #[case(vec![], &[ViewerRole::Subscriber], false)]
+    fn has_any_role(
+        #[case] roles: Vec<ViewerRole>,
+        #[case] wanted: &[ViewerRole],
+        #[case] expected: bool,
+    ) {
+        let chatter = Chatter {
+            roles,
+            ..Default::default()
+        };
+        assert_eq!(chatter.has_any_role(wanted), expected);
+    }
+}
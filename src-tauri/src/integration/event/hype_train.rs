@@ -1,3 +1,9 @@
+use std::{
+    fmt::Display,
+    sync::{LazyLock, Mutex},
+};
+
+use anyhow::bail;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
@@ -6,3 +12,95 @@ pub enum HypeTrainState {
     Progress,
     End,
 }
+
+impl Display for HypeTrainState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Begin => write!(f, "Begin"),
+            Self::Progress => write!(f, "Progress"),
+            Self::End => write!(f, "End"),
+        }
+    }
+}
+
+impl TryFrom<String> for HypeTrainState {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "begin" => Ok(Self::Begin),
+            "progress" => Ok(Self::Progress),
+            "end" => Ok(Self::End),
+            _ => bail!("Invalid input for hype train state: {}", value),
+        }
+    }
+}
+
+/// Levels of the current hype train, shared by every websocket client so a
+/// reconnect doesn't report the current level again.
+pub static HYPE_TRAIN_LEVELS: LazyLock<Mutex<HypeTrainLevels>> =
+    LazyLock::new(|| Mutex::new(HypeTrainLevels::default()));
+
+/// Highest level reached by the current hype train, so each level up is only
+/// reported once however many progress events arrive at that level.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HypeTrainLevels {
+    id: Option<String>,
+    level: i64,
+}
+
+impl HypeTrainLevels {
+    /// Records the level, returns the levels reached since the last event of this
+    /// hype train, or only `level` for a new one.
+    pub fn levels_crossed(&mut self, id: &str, level: i64) -> Vec<i64> {
+        if self.id.as_deref() != Some(id) {
+            self.id = Some(id.to_string());
+            self.level = level;
+            return vec![level];
+        }
+        let crossed = (self.level + 1..=level).collect();
+        self.level = self.level.max(level);
+        crossed
+    }
+
+    /// The `level` and `level_increased` of each event to send, one per level
+    /// crossed or a single one at `level` if none was.
+    pub fn events(&mut self, id: &str, level: i64) -> Vec<(i64, bool)> {
+        match self.levels_crossed(id, level) {
+            crossed if crossed.is_empty() => vec![(level, false)],
+            crossed => crossed.into_iter().map(|l| (l, true)).collect(),
+        }
+    }
+
+    pub fn end(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn level_increased_once() {
+        let mut levels = HypeTrainLevels::default();
+        assert_eq!(levels.events("1", 1), vec![(1, true)]);
+        assert_eq!(levels.events("1", 1), vec![(1, false)]);
+        assert_eq!(levels.events("1", 2), vec![(2, true)]);
+        assert_eq!(levels.events("1", 2), vec![(2, false)]);
+        assert_eq!(levels.events("1", 1), vec![(1, false)]);
+        assert_eq!(levels.events("2", 1), vec![(1, true)]);
+        levels.end();
+        assert_eq!(levels.events("2", 1), vec![(1, true)]);
+    }
+
+    #[rstest]
+    fn every_level_crossed() {
+        let mut levels = HypeTrainLevels::default();
+        assert_eq!(levels.levels_crossed("1", 1), vec![1]);
+        assert_eq!(levels.levels_crossed("1", 4), vec![2, 3, 4]);
+        assert!(levels.levels_crossed("1", 3).is_empty());
+        assert_eq!(levels.levels_crossed("1", 5), vec![5]);
+    }
+}
@@ -2,14 +2,33 @@ use std::fmt::Display;
 
 use anyhow::bail;
 use config::ValueKind;
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use twitch_types::{SubscriptionTier, Timestamp};
 
 mod hype_train;
-pub use hype_train::HypeTrainState;
+pub use hype_train::{HypeTrainState, HYPE_TRAIN_LEVELS};
+mod chatter;
+pub use chatter::{Badge, Chatter, ViewerRole};
+mod pledge;
+pub use pledge::PledgeAction;
 
+use super::currency;
 use crate::command::trigger;
 
+/// Where an event came from, replies go back to the same platform.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Platform {
+    #[default]
+    Twitch,
+    YouTube,
+    Streamlabs,
+    Patreon,
+    Discord,
+    /// A generic webhook, see [`webhooks`](super::webhooks).
+    Webhook,
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Default, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
 #[serde(tag = "type")]
@@ -20,6 +39,9 @@ pub enum IntegrationEvent {
     Chat {
         msg: String,
         author: String,
+        /// Roles, badges and ids of the author.
+        #[serde(default)]
+        chatter: Chatter,
     },
     ChannelPoint(CustomRewardEvent),
     Subscription {
@@ -35,6 +57,100 @@ pub enum IntegrationEvent {
         user_name: String,
         bits: u64,
     },
+    Follow {
+        user_name: String,
+    },
+    Raid {
+        /// Display name of the raiding broadcaster.
+        from: String,
+        viewers: u64,
+    },
+    /// A poll or prediction ended.
+    PollEnd {
+        /// Title of the poll or prediction.
+        title: String,
+        winning_choice: String,
+        votes: u64,
+    },
+    /// A Streamlabs donation.
+    Donation {
+        user_name: String,
+        /// Amount in cents of the currency.
+        amount: u64,
+        /// ISO 4217 code, e.g. `EUR`.
+        currency: String,
+        /// The amount in US cents, [`None`] for a currency without a rate.
+        usd_cents: Option<u64>,
+        message: String,
+    },
+    /// A YouTube Super Chat, or a Super Sticker if `sticker` is set.
+    SuperChat {
+        user_name: String,
+        /// Amount in millionths of the currency.
+        amount_micros: u64,
+        /// ISO 4217 code, e.g. `EUR`.
+        currency: String,
+        /// The amount in US cents, [`None`] for a currency without a rate.
+        usd_cents: Option<u64>,
+        message: String,
+        /// Alt text of the sticker.
+        sticker: Option<String>,
+    },
+    /// A new, milestone or gifted YouTube membership.
+    Membership {
+        user_name: String,
+        /// Name of the membership level.
+        level: String,
+        /// Months as a member, 0 for a new member.
+        months: u64,
+        /// Memberships given to other viewers, 0 when not gifting.
+        gifts: u64,
+    },
+    /// A Patreon member pledging, changing or deleting their pledge.
+    Pledge {
+        action: PledgeAction,
+        /// Title of the tier, empty without one.
+        tier: String,
+        /// In cents of the campaign's currency.
+        amount_cents: u64,
+        user: String,
+    },
+    /// A request to a generic webhook route, see [`webhooks`](super::webhooks).
+    Webhook {
+        /// Name of the route, e.g. `kofi`.
+        source: String,
+        /// In cents of the currency, 0 without an amount.
+        amount: u64,
+        currency: String,
+        /// The amount in US cents, [`None`] without an amount or a rate.
+        usd_cents: Option<u64>,
+        user: String,
+        message: String,
+        /// The JSON body.
+        payload: serde_json::Value,
+    },
+    /// A Discord slash command, see [`discord`](super::discord).
+    SlashCommand {
+        /// Without the `/`.
+        name: String,
+        user_name: String,
+        user_id: String,
+        /// Ids of the member's roles, empty outside a server.
+        roles: Vec<String>,
+        /// Values for the command's variables, by variable name.
+        options: Vec<(String, String)>,
+        application_id: String,
+        interaction_id: String,
+        /// Replies to the interaction for 15 minutes.
+        token: String,
+    },
+    /// Any other EventSub notification, see `Trigger::JsonPath`.
+    Generic {
+        /// Subscription type, e.g. `channel.ad_break.begin`.
+        event_type: String,
+        /// The `event` object of the notification.
+        payload: serde_json::Value,
+    },
     HypeTrain {
         state: HypeTrainState,
         id: String,
@@ -43,6 +159,9 @@ pub enum IntegrationEvent {
         goal: i64,
         total: i64,
         expires_at: Timestamp,
+        /// First event at a new level of this hype train, see [`HYPE_TRAIN_LEVELS`].
+        #[serde(default)]
+        level_increased: bool,
     },
     Unknown,
     Stop,
@@ -70,14 +189,86 @@ impl IntegrationEvent {
         matches!(self, Self::ChannelPoint { .. })
     }
 
+    /// Who sent a chat message, [`None`] for other events.
+    pub fn chatter(&self) -> Option<&Chatter> {
+        match self {
+            IntegrationEvent::Chat { chatter, .. } => Some(chatter),
+            _ => None,
+        }
+    }
+
+    /// Platform id of the author, for chat and slash commands.
+    pub fn user_id(&self) -> Option<String> {
+        match self {
+            IntegrationEvent::Chat { chatter, .. } if !chatter.user_id.is_empty() => {
+                Some(chatter.user_id.clone())
+            }
+            IntegrationEvent::SlashCommand { user_id, .. } => Some(user_id.clone()),
+            _ => None,
+        }
+    }
+
+    /// Platform the event came from, [`None`] for the app's own events.
+    pub fn platform(&self) -> Option<Platform> {
+        match self {
+            IntegrationEvent::Chat { chatter, .. } => Some(chatter.platform),
+            IntegrationEvent::ChannelPoint(_)
+            | IntegrationEvent::Subscription { .. }
+            | IntegrationEvent::GiftSub { .. }
+            | IntegrationEvent::Bits { .. }
+            | IntegrationEvent::Follow { .. }
+            | IntegrationEvent::Raid { .. }
+            | IntegrationEvent::PollEnd { .. }
+            | IntegrationEvent::Generic { .. }
+            | IntegrationEvent::HypeTrain { .. } => Some(Platform::Twitch),
+            // Streamlabs also relays Super Chats, they are answered on YouTube.
+            IntegrationEvent::SuperChat { .. } | IntegrationEvent::Membership { .. } => {
+                Some(Platform::YouTube)
+            }
+            IntegrationEvent::Donation { .. } => Some(Platform::Streamlabs),
+            IntegrationEvent::Pledge { .. } => Some(Platform::Patreon),
+            IntegrationEvent::Webhook { .. } => Some(Platform::Webhook),
+            IntegrationEvent::SlashCommand { .. } => Some(Platform::Discord),
+            IntegrationEvent::Connected
+            | IntegrationEvent::Disconnected
+            | IntegrationEvent::Server
+            | IntegrationEvent::Unknown
+            | IntegrationEvent::Stop
+            | IntegrationEvent::Pause
+            | IntegrationEvent::Continue
+            | IntegrationEvent::Update => None,
+        }
+    }
+
+    /// Identifies a single occurrence, the same if it is delivered again.
+    pub fn event_id(&self) -> Option<String> {
+        match self {
+            IntegrationEvent::ChannelPoint(custom_reward_event) => Some(format!(
+                "ChannelPoint:{}:{}",
+                custom_reward_event.variant, custom_reward_event.event_id
+            )),
+            _ => None,
+        }
+    }
+
     pub fn message(&self) -> Option<&str> {
         match self {
             IntegrationEvent::ChannelPoint(custom_reward_event) => {
                 Some(&custom_reward_event.message)
             }
             IntegrationEvent::Chat { msg, .. } => Some(&msg),
+            IntegrationEvent::SuperChat { message, .. } => Some(message),
+            IntegrationEvent::Donation { message, .. } => Some(message),
+            IntegrationEvent::Webhook { message, .. } => Some(message),
             IntegrationEvent::HypeTrain { .. }
             | IntegrationEvent::Bits { .. }
+            | IntegrationEvent::Follow { .. }
+            | IntegrationEvent::Raid { .. }
+            | IntegrationEvent::PollEnd { .. }
+            | IntegrationEvent::Generic { .. }
+            | IntegrationEvent::Membership { .. }
+            | IntegrationEvent::Pledge { .. }
+            | IntegrationEvent::SlashCommand { .. }
             | IntegrationEvent::Connected
             | IntegrationEvent::Disconnected
             | IntegrationEvent::Server
@@ -100,7 +291,20 @@ impl IntegrationEvent {
             IntegrationEvent::Subscription { user_name, .. } => user_name.clone(),
             IntegrationEvent::GiftSub { user_name, .. } => user_name.clone().unwrap_or_default(),
             IntegrationEvent::Bits { user_name, .. } => user_name.clone(),
+            IntegrationEvent::Follow { user_name } => user_name.clone(),
+            IntegrationEvent::Raid { from, .. } => from.clone(),
+            IntegrationEvent::SuperChat { user_name, .. } => user_name.clone(),
+            IntegrationEvent::Donation { user_name, .. } => user_name.clone(),
+            IntegrationEvent::Membership { user_name, .. } => user_name.clone(),
+            IntegrationEvent::Pledge { user, .. } => user.clone(),
+            IntegrationEvent::Webhook { user, .. } => user.clone(),
+            IntegrationEvent::SlashCommand { user_name, .. } => user_name.clone(),
+            IntegrationEvent::Generic { payload, .. } => match payload["user_name"].as_str() {
+                Some(user_name) => user_name.to_string(),
+                None => "<server>".to_string(),
+            },
             IntegrationEvent::HypeTrain { .. }
+            | IntegrationEvent::PollEnd { .. }
             | IntegrationEvent::Connected
             | IntegrationEvent::Disconnected
             | IntegrationEvent::Server
@@ -111,6 +315,121 @@ impl IntegrationEvent {
             | IntegrationEvent::Update => "<server>".to_string(),
         }
     }
+    /// Values from the event for command variables of the same name, e.g. `VIEWERS`
+    /// for a raid, so a command can scale with the size of the event. `MESSAGE` is
+    /// the whole message of events that have one.
+    pub fn values(&self) -> IndexMap<String, String> {
+        let mut values = self.event_values();
+        if let Some(message) = self.message() {
+            values.insert("MESSAGE".to_string(), message.to_string());
+        }
+        if let Some(cents) = self.value_cents(&currency::value_rates()) {
+            values.insert("VALUE".to_string(), currency::format_cents(cents));
+        }
+        values
+    }
+
+    /// What the event is worth in US cents, [`None`] if it isn't monetary or the
+    /// currency has no rate. Gift subs count each subscription.
+    pub fn value_cents(&self, value_rates: &currency::ValueRates) -> Option<u64> {
+        let tier_cents = |tier: &trigger::SubscriptionTier| match tier {
+            trigger::SubscriptionTier::Tier1 => Some(value_rates.tier1_cents),
+            trigger::SubscriptionTier::Tier2 => Some(value_rates.tier2_cents),
+            trigger::SubscriptionTier::Tier3 => Some(value_rates.tier3_cents),
+            trigger::SubscriptionTier::Prime => Some(value_rates.prime_cents),
+            trigger::SubscriptionTier::Other(_) => None,
+        };
+        match self {
+            IntegrationEvent::Bits { bits, .. } => Some(value_rates.bits_cents(*bits)),
+            IntegrationEvent::Subscription { tier, .. } => tier_cents(tier),
+            IntegrationEvent::GiftSub { tier, count, .. } => tier_cents(tier).map(|c| c * count),
+            IntegrationEvent::Donation { usd_cents, .. }
+            | IntegrationEvent::SuperChat { usd_cents, .. }
+            | IntegrationEvent::Webhook { usd_cents, .. } => *usd_cents,
+            IntegrationEvent::Membership { gifts, .. } => {
+                Some(value_rates.membership_cents * (*gifts).max(1))
+            }
+            // Patreon doesn't say the currency, campaigns are usually in USD.
+            IntegrationEvent::Pledge {
+                action: PledgeAction::Create,
+                amount_cents,
+                ..
+            } => Some(*amount_cents),
+            _ => None,
+        }
+    }
+
+    fn event_values(&self) -> IndexMap<String, String> {
+        match self {
+            IntegrationEvent::Raid { viewers, .. } => {
+                IndexMap::from([("VIEWERS".to_string(), viewers.to_string())])
+            }
+            IntegrationEvent::Bits { bits, .. } => {
+                IndexMap::from([("BITS".to_string(), bits.to_string())])
+            }
+            IntegrationEvent::GiftSub { count, .. } => {
+                IndexMap::from([("COUNT".to_string(), count.to_string())])
+            }
+            IntegrationEvent::HypeTrain { level, .. } => {
+                IndexMap::from([("LEVEL".to_string(), level.to_string())])
+            }
+            IntegrationEvent::PollEnd { votes, .. } => {
+                IndexMap::from([("VOTES".to_string(), votes.to_string())])
+            }
+            IntegrationEvent::SuperChat {
+                amount_micros,
+                currency,
+                usd_cents,
+                ..
+            } => {
+                let mut values = IndexMap::from([
+                    (
+                        "AMOUNT".to_string(),
+                        currency::format_cents(amount_micros / 10_000),
+                    ),
+                    ("CURRENCY".to_string(), currency.clone()),
+                ]);
+                if let Some(usd_cents) = usd_cents {
+                    values.insert("USD".to_string(), currency::format_cents(*usd_cents));
+                }
+                values
+            }
+            IntegrationEvent::Donation {
+                amount,
+                currency,
+                usd_cents,
+                ..
+            }
+            | IntegrationEvent::Webhook {
+                amount,
+                currency,
+                usd_cents,
+                ..
+            } => {
+                let mut values = IndexMap::from([
+                    ("AMOUNT".to_string(), currency::format_cents(*amount)),
+                    ("CURRENCY".to_string(), currency.clone()),
+                ]);
+                if let Some(usd_cents) = usd_cents {
+                    values.insert("USD".to_string(), currency::format_cents(*usd_cents));
+                }
+                values
+            }
+            IntegrationEvent::Membership { months, gifts, .. } => IndexMap::from([
+                ("MONTHS".to_string(), months.to_string()),
+                ("COUNT".to_string(), gifts.to_string()),
+            ]),
+            IntegrationEvent::Pledge {
+                tier, amount_cents, ..
+            } => IndexMap::from([
+                ("AMOUNT".to_string(), currency::format_cents(*amount_cents)),
+                ("TIER".to_string(), tier.clone()),
+            ]),
+            IntegrationEvent::SlashCommand { options, .. } => options.iter().cloned().collect(),
+            _ => IndexMap::new(),
+        }
+    }
+
     /// A default implementation of each enum which can be used for being a key.
     pub fn event_type(&self) -> Self {
         use IntegrationEvent::*;
@@ -118,6 +437,7 @@ impl IntegrationEvent {
             Chat { .. } => Chat {
                 msg: Default::default(),
                 author: Default::default(),
+                chatter: Default::default(),
             },
             Connected => Connected,
             ChannelPoint(..) => ChannelPoint(CustomRewardEvent::default()),
@@ -141,6 +461,68 @@ impl IntegrationEvent {
                 user_name: Default::default(),
                 bits: Default::default(),
             },
+            Follow { .. } => Follow {
+                user_name: Default::default(),
+            },
+            Raid { .. } => Raid {
+                from: Default::default(),
+                viewers: Default::default(),
+            },
+            Donation { .. } => Donation {
+                user_name: Default::default(),
+                amount: Default::default(),
+                currency: Default::default(),
+                usd_cents: Default::default(),
+                message: Default::default(),
+            },
+            SuperChat { .. } => SuperChat {
+                user_name: Default::default(),
+                amount_micros: Default::default(),
+                currency: Default::default(),
+                usd_cents: Default::default(),
+                message: Default::default(),
+                sticker: Default::default(),
+            },
+            Membership { .. } => Membership {
+                user_name: Default::default(),
+                level: Default::default(),
+                months: Default::default(),
+                gifts: Default::default(),
+            },
+            Pledge { .. } => Pledge {
+                action: Default::default(),
+                tier: Default::default(),
+                amount_cents: Default::default(),
+                user: Default::default(),
+            },
+            Webhook { source, .. } => Webhook {
+                source: source.clone(),
+                amount: Default::default(),
+                currency: Default::default(),
+                usd_cents: Default::default(),
+                user: Default::default(),
+                message: Default::default(),
+                payload: Default::default(),
+            },
+            SlashCommand { name, .. } => SlashCommand {
+                name: name.clone(),
+                user_name: Default::default(),
+                user_id: Default::default(),
+                roles: Default::default(),
+                options: Default::default(),
+                application_id: Default::default(),
+                interaction_id: Default::default(),
+                token: Default::default(),
+            },
+            Generic { event_type, .. } => Generic {
+                event_type: event_type.clone(),
+                payload: Default::default(),
+            },
+            PollEnd { .. } => PollEnd {
+                title: Default::default(),
+                winning_choice: Default::default(),
+                votes: Default::default(),
+            },
             HypeTrain { .. } => HypeTrain {
                 state: HypeTrainState::Begin,
                 id: Default::default(),
@@ -149,6 +531,7 @@ impl IntegrationEvent {
                 goal: Default::default(),
                 total: Default::default(),
                 expires_at: Timestamp::new("1970-01-01T00:00:00+00:00".to_string()).unwrap(),
+                level_increased: Default::default(),
             },
         }
     }
@@ -0,0 +1,36 @@
+use std::fmt::Display;
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+/// Which Patreon `members:pledge:*` webhook the pledge came from.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum PledgeAction {
+    #[default]
+    Create,
+    Update,
+    Delete,
+}
+
+impl Display for PledgeAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Create => write!(f, "Create"),
+            Self::Update => write!(f, "Update"),
+            Self::Delete => write!(f, "Delete"),
+        }
+    }
+}
+
+impl TryFrom<String> for PledgeAction {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "create" => Ok(Self::Create),
+            "update" => Ok(Self::Update),
+            "delete" => Ok(Self::Delete),
+            _ => bail!("Invalid input for pledge action: {}", value),
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Messages from the commands back to the platforms, e.g. the outcome of a
+//! channel point redemption.
+use std::sync::LazyLock;
+
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{debug, warn};
+use twitch_api::helix::points::CustomRewardRedemptionStatus;
+
+use super::{CustomRewardEvent, IntegrationEvent};
+use crate::{localhost::TwitchState, servers::GameServer};
+
+pub static FEEDBACK: LazyLock<broadcast::Sender<Feedback>> =
+    LazyLock::new(|| broadcast::channel(100).0);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Feedback {
+    /// Mark a channel point redemption as fulfilled or cancel it, refunding the points.
+    Redemption {
+        redemption: CustomRewardEvent,
+        status: CustomRewardRedemptionStatus,
+    },
+    /// A game server connected or disconnected, its rewards are paused while
+    /// disconnected.
+    ServerConnection { server: GameServer, connected: bool },
+    /// Chat message in reply to the event.
+    Reply {
+        event: IntegrationEvent,
+        message: String,
+    },
+}
+
+/// Sends the feedback to the platforms, ignored when none are listening.
+pub fn send(feedback: Feedback) {
+    debug!("Feedback: {:?}", feedback);
+    let _ = FEEDBACK.send(feedback);
+}
+
+/// Fulfils a channel point redemption if the command succeeded, otherwise
+/// cancels it. Does nothing for other events.
+pub fn redemption_outcome(event: &IntegrationEvent, succeeded: bool) {
+    if let IntegrationEvent::ChannelPoint(redemption) = event {
+        send(Feedback::Redemption {
+            redemption: redemption.clone(),
+            status: if succeeded {
+                CustomRewardRedemptionStatus::Fulfilled
+            } else {
+                CustomRewardRedemptionStatus::Canceled
+            },
+        });
+    }
+}
+
+/// Tells the user of a slash command what became of it when it wasn't sent, as
+/// the interaction shows "thinking" until a follow-up. Chat events only get the
+/// command's [`Reply`](crate::command::Reply).
+pub fn interaction_notice(event: &IntegrationEvent, message: String) {
+    if let IntegrationEvent::SlashCommand { .. } = event {
+        send(Feedback::Reply {
+            event: event.clone(),
+            message,
+        });
+    }
+}
+
+/// Handles the feedback for Twitch in the background.
+pub fn start(twitch: TwitchState) {
+    let mut rx = FEEDBACK.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(Feedback::Redemption { redemption, status }) => {
+                    if let Err(e) = twitch
+                        .lock()
+                        .await
+                        .update_redemption_status(&redemption, status)
+                        .await
+                    {
+                        warn!(
+                            "Failed to set redemption {} to {:?}: {:?}",
+                            redemption.event_id, status, e
+                        );
+                    }
+                }
+                Ok(Feedback::ServerConnection { server, connected }) => {
+                    twitch
+                        .lock()
+                        .await
+                        .set_server_rewards_paused(&server, !connected)
+                        .await
+                }
+                // Rate limited separately, see twitch::chat.
+                Ok(Feedback::Reply { .. }) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Dropped {skipped} feedback messages.")
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(true, CustomRewardRedemptionStatus::Fulfilled)]
+    #[case(false, CustomRewardRedemptionStatus::Canceled)]
+    fn redemption_outcome_status(
+        #[case] succeeded: bool,
+        #[case] expected: CustomRewardRedemptionStatus,
+    ) {
+        let mut rx = FEEDBACK.subscribe();
+        let redemption = CustomRewardEvent {
+            event_id: format!("outcome-{succeeded}"),
+            ..Default::default()
+        };
+        redemption_outcome(
+            &IntegrationEvent::Chat {
+                msg: "Hello".into(),
+                author: "Legend".into(),
+                chatter: Default::default(),
+            },
+            succeeded,
+        );
+        redemption_outcome(
+            &IntegrationEvent::ChannelPoint(redemption.clone()),
+            succeeded,
+        );
+        // Other tests share the channel.
+        loop {
+            let Feedback::Redemption {
+                redemption: received,
+                status,
+            } = rx.try_recv().unwrap()
+            else {
+                continue;
+            };
+            if received == redemption {
+                assert_eq!(status, expected);
+                break;
+            }
+        }
+    }
+
+    #[rstest]
+    fn notice_only_for_slash_commands() {
+        let mut rx = FEEDBACK.subscribe();
+        let slash_command = IntegrationEvent::SlashCommand {
+            name: "nuke".into(),
+            user_name: "Legend".into(),
+            user_id: "1".into(),
+            roles: Vec::new(),
+            options: Vec::new(),
+            application_id: "app".into(),
+            interaction_id: "notice".into(),
+            token: "token".into(),
+        };
+        interaction_notice(
+            &IntegrationEvent::Chat {
+                msg: "!nuke".into(),
+                author: "Legend".into(),
+                chatter: Default::default(),
+            },
+            "nuke was not sent".into(),
+        );
+        interaction_notice(&slash_command, "nuke was not sent".into());
+        // Other tests share the channel.
+        loop {
+            let Feedback::Reply { event, message } = rx.try_recv().unwrap() else {
+                continue;
+            };
+            match event {
+                IntegrationEvent::Chat { author, .. } if author == "Legend" => {
+                    panic!("Chat got a notice")
+                }
+                IntegrationEvent::SlashCommand { .. } if event == slash_command => {
+                    assert_eq!(message, "nuke was not sent");
+                    break;
+                }
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! Counters for the integrations, served on `/api/metrics`.
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+pub static METRICS: Metrics = Metrics::new();
+
+#[derive(Debug)]
+pub struct Metrics {
+    duplicates_dropped: AtomicU64,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    /// Notifications and events dropped as already handled, see [`super::dedup`].
+    pub duplicates_dropped: u64,
+}
+
+impl Metrics {
+    const fn new() -> Self {
+        Self {
+            duplicates_dropped: AtomicU64::new(0),
+        }
+    }
+
+    pub fn duplicate_dropped(&self) {
+        self.duplicates_dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            duplicates_dropped: self.duplicates_dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_metrics() -> MetricsSnapshot {
+    METRICS.snapshot()
+}
@@ -11,6 +11,14 @@ use serde::{Deserialize, Serialize};
 use tauri::State;
 use tracing::{error, info, instrument};
 pub use twitch::TwitchApiConnection;
+pub mod discord;
+pub use discord::DiscordApiConnection;
+pub mod patreon;
+pub mod streamlabs;
+pub mod webhooks;
+pub use streamlabs::StreamLabsApiConnection;
+pub mod youtube;
+pub use youtube::YouTubeApiConnection;
 
 pub mod websocket;
 pub use websocket::{
@@ -19,8 +27,15 @@ pub use websocket::{
 };
 
 mod event;
-pub use event::{CustomRewardEvent, CustomRewardVariant, IntegrationEvent};
+pub use event::{
+    Badge, Chatter, CustomRewardEvent, CustomRewardVariant, HypeTrainState, IntegrationEvent,
+    Platform, PledgeAction, ViewerRole,
+};
 
+pub mod currency;
+pub mod dedup;
+pub mod feedback;
+pub mod metrics;
 pub mod status;
 pub use status::{integration_status, IntegrationError, IntegrationStatus};
 use twitch_oauth2::TwitchToken;
@@ -31,6 +46,7 @@ pub enum Api {
     YouTube,
     Patreon,
     StreamLabs,
+    Discord,
 }
 
 impl TryFrom<config::Value> for Api {
@@ -46,6 +62,9 @@ impl TryFrom<config::Value> for Api {
         {
             "twitch" => Ok(Twitch),
             "youtube" => Ok(YouTube),
+            "patreon" => Ok(Patreon),
+            "streamlabs" => Ok(StreamLabs),
+            "discord" => Ok(Discord),
             api => bail!("Non valid api: {}", api),
         }
     }
@@ -165,6 +184,9 @@ pub enum TokenError {
 pub async fn connect_to_integration(
     api: Api,
     twitch_integration: State<'_, Arc<futures::lock::Mutex<TwitchApiConnection>>>,
+    youtube_integration: State<'_, Arc<futures::lock::Mutex<YouTubeApiConnection>>>,
+    streamlabs_integration: State<'_, Arc<futures::lock::Mutex<StreamLabsApiConnection>>>,
+    discord_integration: State<'_, Arc<futures::lock::Mutex<DiscordApiConnection>>>,
     config: State<'_, Arc<futures::lock::Mutex<config::Config>>>,
     force: bool,
 ) -> Result<IntegrationStatus, IntegrationError> {
@@ -188,8 +210,72 @@ pub async fn connect_to_integration(
                 }
             }
         }
-        _ => Err(IntegrationError::NotImplemented(api)),
+        YouTube => {
+            let event_tx = runner_tx(&twitch_integration, &config).await;
+            match youtube_integration.lock().await.run(config, event_tx).await {
+                Ok(token) => Ok(IntegrationStatus::Connected {
+                    api: Api::YouTube,
+                    expires_at: Some(IntegrationStatus::seconds_to(
+                        token.expires_in(time::OffsetDateTime::now_utc()),
+                    )),
+                }),
+                Err(e) => {
+                    error!("Failed to connect to YouTube: {:?}", e);
+                    Err(IntegrationError::Token(TokenError::NotAuthorized))
+                }
+            }
+        }
+        StreamLabs => {
+            let event_tx = runner_tx(&twitch_integration, &config).await;
+            let mut streamlabs = streamlabs_integration.lock().await;
+            match streamlabs.run(config, event_tx).await {
+                Ok(()) => Ok(IntegrationStatus::Connecting(Api::StreamLabs)),
+                Err(e) => {
+                    error!("Failed to connect to Streamlabs: {:?}", e);
+                    Err(IntegrationError::Token(TokenError::InvalidToken))
+                }
+            }
+        }
+        Patreon => {
+            let event_tx = runner_tx(&twitch_integration, &config).await;
+            match patreon::connect(&config, event_tx) {
+                Ok(()) => patreon::check_status(),
+                Err(e) => {
+                    error!("Failed to connect to Patreon: {:?}", e);
+                    Err(IntegrationError::Token(TokenError::InvalidToken))
+                }
+            }
+        }
+        Discord => {
+            let event_tx = runner_tx(&twitch_integration, &config).await;
+            let commands = crate::command::settings::ScriptSettings::get_commands();
+            let mut discord = discord_integration.lock().await;
+            match discord.run(config, commands, event_tx).await {
+                Ok(()) => Ok(IntegrationStatus::Connecting(Api::Discord)),
+                Err(e) => {
+                    error!("Failed to connect to Discord: {:?}", e);
+                    Err(IntegrationError::Token(TokenError::InvalidToken))
+                }
+            }
+        }
+    }
+}
+
+/// Other integrations' events go to the same runner as Twitch's, starting it
+/// if needed.
+pub(crate) async fn runner_tx(
+    twitch_integration: &Arc<futures::lock::Mutex<TwitchApiConnection>>,
+    config: &config::Config,
+) -> tokio::sync::mpsc::Sender<IntegrationEvent> {
+    let mut twitch = twitch_integration.lock().await;
+    twitch
+        .runner
+        .set_pause_settings(crate::command::pause::PauseSettings::from_config(config));
+    currency::set_value_rates(currency::ValueRates::from_config(config));
+    if let Err(e) = twitch.runner.run() {
+        error!("{:?}", e);
     }
+    twitch.runner.tx()
 }
 
 #[tauri::command]
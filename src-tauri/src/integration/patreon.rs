@@ -0,0 +1,243 @@
+//! Patreon pledges, received as [webhooks](https://docs.patreon.com/#webhooks).
+//! Patreon posts to the webhook url set up for the campaign, which has to
+//! forward to [`WEBHOOK_PATH`] on the localhost server.
+use std::sync::{LazyLock, Mutex};
+
+use anyhow::{bail, Result};
+use config::Config;
+use crypto_hmac::{Hmac, Mac};
+use http::{Request, Response, StatusCode};
+use md5::Md5;
+use serde_json::Value;
+use tokio::sync::mpsc::Sender;
+use tracing::{debug, error, info, warn};
+
+use super::{
+    status::{IntegrationError, IntegrationStatus},
+    twitch::webhook::hex_decode,
+    Api, IntegrationEvent, PledgeAction,
+};
+use crate::localhost::text_response;
+
+/// Path on the localhost server receiving the webhooks.
+pub const WEBHOOK_PATH: &str = "/webhook/patreon";
+
+/// Receives the webhooks once connected, see [`connect`].
+static WEBHOOK: LazyLock<Mutex<Option<PatreonWebhook>>> = LazyLock::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone)]
+pub struct PatreonWebhook {
+    /// Secret Patreon signs the webhooks with.
+    pub secret: String,
+    pub event_tx: Sender<IntegrationEvent>,
+}
+
+/// Reads `auth.patreon.webhook_secret` and starts passing pledges to the runner.
+pub fn connect(config: &Config, event_tx: Sender<IntegrationEvent>) -> Result<()> {
+    let secret = config
+        .get_string("auth.patreon.webhook_secret")
+        .unwrap_or_default();
+    if secret.is_empty() {
+        bail!("auth.patreon.webhook_secret is required");
+    }
+    set_webhook(Some(PatreonWebhook { secret, event_tx }));
+    info!("Receiving Patreon webhooks at {WEBHOOK_PATH}");
+    Ok(())
+}
+
+/// Replaces the webhook receiver, [`None`] rejects any webhooks.
+pub fn set_webhook(webhook: Option<PatreonWebhook>) {
+    *WEBHOOK.lock().unwrap_or_else(|e| e.into_inner()) = webhook;
+}
+
+fn webhook() -> Option<PatreonWebhook> {
+    WEBHOOK.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+pub fn check_status() -> Result<IntegrationStatus, IntegrationError> {
+    match webhook() {
+        Some(webhook) if webhook.event_tx.is_closed() => {
+            Ok(IntegrationStatus::Disconnected(Api::Patreon))
+        }
+        Some(_) => Ok(IntegrationStatus::Connected {
+            api: Api::Patreon,
+            expires_at: None,
+        }),
+        None => Ok(IntegrationStatus::NotStarted),
+    }
+}
+
+/// The hex HMAC-MD5 of the body, as sent in `X-Patreon-Signature`.
+pub fn signature(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Md5>::new_from_slice(secret).expect("HMAC takes any key length");
+    mac.update(body);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Whether the `X-Patreon-Signature` is the HMAC-MD5 of the body.
+pub fn verify_signature(secret: &[u8], body: &[u8], signature: &str) -> bool {
+    let Some(signature) = hex_decode(signature) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Md5>::new_from_slice(secret) else {
+        return false;
+    };
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// The action of a `members:pledge:*` trigger, [`None`] for any other.
+pub fn pledge_action(event_type: &str) -> Option<PledgeAction> {
+    match event_type {
+        "members:pledge:create" => Some(PledgeAction::Create),
+        "members:pledge:update" => Some(PledgeAction::Update),
+        "members:pledge:delete" => Some(PledgeAction::Delete),
+        _ => None,
+    }
+}
+
+/// The resource of `type` and `id` in the `included` array.
+fn included<'a>(body: &'a Value, relationship: &Value) -> Option<&'a Value> {
+    body["included"].as_array()?.iter().find(|resource| {
+        resource["type"] == relationship["type"] && resource["id"] == relationship["id"]
+    })
+}
+
+/// Maps the member resource of a pledge webhook.
+pub fn pledge_from_patreon(action: PledgeAction, body: &Value) -> IntegrationEvent {
+    let member = &body["data"];
+    let attributes = &member["attributes"];
+    let tier = member["relationships"]["currently_entitled_tiers"]["data"]
+        .as_array()
+        .and_then(|tiers| tiers.first())
+        .and_then(|tier| included(body, tier));
+    let amount_cents = attributes["currently_entitled_amount_cents"]
+        .as_u64()
+        .or_else(|| attributes["will_pay_amount_cents"].as_u64())
+        .or_else(|| tier.and_then(|t| t["attributes"]["amount_cents"].as_u64()))
+        .unwrap_or_default();
+    let user = match attributes["full_name"].as_str() {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => included(body, &member["relationships"]["user"]["data"])
+            .and_then(|user| user["attributes"]["full_name"].as_str())
+            .unwrap_or_default()
+            .to_string(),
+    };
+    IntegrationEvent::Pledge {
+        action,
+        tier: tier
+            .and_then(|t| t["attributes"]["title"].as_str())
+            .unwrap_or_default()
+            .to_string(),
+        amount_cents,
+        user,
+    }
+}
+
+/// Verifies the signature and maps the webhook, the error is the status to
+/// respond with. Webhooks other than pledges are [`None`].
+pub fn check_request(
+    request: &Request<Vec<u8>>,
+    secret: &str,
+) -> Result<Option<IntegrationEvent>, StatusCode> {
+    let header = |name: &str| {
+        request
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+    };
+    if !verify_signature(
+        secret.as_bytes(),
+        request.body(),
+        header("X-Patreon-Signature"),
+    ) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let body: Value =
+        serde_json::from_slice(request.body()).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let event_type = header("X-Patreon-Event");
+    match pledge_action(event_type) {
+        Some(action) => Ok(Some(pledge_from_patreon(action, &body))),
+        None => {
+            debug!("Ignoring Patreon webhook {event_type}");
+            Ok(None)
+        }
+    }
+}
+
+/// Handles a request to [`WEBHOOK_PATH`].
+pub async fn route(request: Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let Some(webhook) = webhook() else {
+        return text_response(StatusCode::NOT_FOUND, "Not Found");
+    };
+    match check_request(&request, &webhook.secret) {
+        Ok(Some(event)) => {
+            if let Err(e) = webhook.event_tx.send(event).await {
+                error!("Failed to pass on the Patreon pledge: {e}");
+                return text_response(StatusCode::SERVICE_UNAVAILABLE, "Not running");
+            }
+            text_response(StatusCode::OK, "OK")
+        }
+        Ok(None) => text_response(StatusCode::OK, "OK"),
+        Err(status) => {
+            warn!("Rejected Patreon webhook request: {status}");
+            text_response(status, status.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde_json::json;
+
+    // RFC 2104 test vector.
+    const SECRET: &str = "Jefe";
+    const BODY: &str = "what do ya want for nothing?";
+    const SIGNATURE: &str = "750c783e6ab0b503eaa86e310a5db738";
+
+    #[rstest]
+    #[case(SECRET, SIGNATURE, true)]
+    #[case("wrong-secret", SIGNATURE, false)]
+    #[case(SECRET, "750c", false)]
+    #[case(SECRET, "not hex", false)]
+    fn signature_verified(#[case] secret: &str, #[case] signature: &str, #[case] expected: bool) {
+        assert_eq!(
+            verify_signature(secret.as_bytes(), BODY.as_bytes(), signature),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn signature_signs() {
+        assert_eq!(signature(SECRET.as_bytes(), BODY.as_bytes()), SIGNATURE);
+    }
+
+    #[rstest]
+    fn pledge_without_tier() {
+        let body = json!({
+            "data": {
+                "type": "member",
+                "id": "1",
+                "attributes": { "will_pay_amount_cents": 300 },
+                "relationships": { "user": { "data": { "type": "user", "id": "2" } } }
+            },
+            "included": [{ "type": "user", "id": "2", "attributes": { "full_name": "Cool User" } }]
+        });
+        assert_eq!(
+            pledge_from_patreon(PledgeAction::Update, &body),
+            IntegrationEvent::Pledge {
+                action: PledgeAction::Update,
+                tier: String::new(),
+                amount_cents: 300,
+                user: "Cool User".to_string(),
+            }
+        );
+    }
+}
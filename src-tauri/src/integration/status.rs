@@ -7,7 +7,10 @@ use serde::{Deserialize, Serialize};
 use tauri::State;
 use tracing::instrument;
 
-use super::{Api, TokenError, TwitchApiConnection};
+use super::{
+    Api, DiscordApiConnection, StreamLabsApiConnection, TokenError, TwitchApiConnection,
+    YouTubeApiConnection,
+};
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(tag = "status", content = "api")]
@@ -44,6 +47,8 @@ pub enum IntegrationError {
     #[default]
     Unknown,
     WsError,
+    /// Configured websocket subscriptions which are not enabled.
+    Subscriptions(Vec<String>),
 }
 
 /// TODO: Add clearer UI indication of check.
@@ -52,6 +57,9 @@ pub enum IntegrationError {
 pub async fn integration_status(
     api: Api,
     twitch_integration: State<'_, Arc<futures::lock::Mutex<TwitchApiConnection>>>,
+    youtube_integration: State<'_, Arc<futures::lock::Mutex<YouTubeApiConnection>>>,
+    streamlabs_integration: State<'_, Arc<futures::lock::Mutex<StreamLabsApiConnection>>>,
+    discord_integration: State<'_, Arc<futures::lock::Mutex<DiscordApiConnection>>>,
 ) -> Result<IntegrationStatus, IntegrationError> {
     match api {
         Api::Twitch => {
@@ -70,6 +78,9 @@ pub async fn integration_status(
                 }
             }
         }
-        _ => Err(IntegrationError::NotImplemented(api)),
+        Api::YouTube => youtube_integration.lock().await.check_status(),
+        Api::StreamLabs => streamlabs_integration.lock().await.check_status(),
+        Api::Patreon => super::patreon::check_status(),
+        Api::Discord => discord_integration.lock().await.check_status(),
     }
 }
@@ -0,0 +1,409 @@
+//! Streamlabs donations, merch and the platform events it relays, read from the
+//! [Socket API](https://dev.streamlabs.com/docs/socket-api) with the socket token
+//! from Streamlabs' Settings > API Settings.
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use anyhow::{bail, Result};
+use config::Config;
+use serde_json::Value;
+use tokio::sync::mpsc::Sender;
+use tracing::{debug, error, info, instrument};
+use url::Url;
+
+pub mod profile;
+pub mod socket_io;
+
+use socket_io::SocketIo;
+
+use super::{
+    currency::{self, CurrencyRates},
+    dedup,
+    status::{IntegrationError, IntegrationStatus},
+    Api, IntegrationEvent,
+};
+use crate::command::trigger::SubscriptionTier;
+
+pub const SOCKET_URL: &str = "wss://sockets.streamlabs.com";
+
+/// Wait before reconnecting after the socket drops.
+pub const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamLabsSettings {
+    pub socket_token: String,
+    /// Also pass on the Twitch and YouTube events Streamlabs relays, leave off
+    /// when connected to them directly or each event fires twice.
+    pub platform_events: bool,
+}
+
+impl StreamLabsSettings {
+    /// Reads `auth.streamlabs`.
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            socket_token: config
+                .get_string("auth.streamlabs.socket_token")
+                .unwrap_or_default(),
+            platform_events: config
+                .get_bool("auth.streamlabs.platform_events")
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// The socket url for the token, `STREAMLABS_SOCKET_URL` replaces the server
+/// for testing.
+pub fn socket_url(token: &str) -> Result<Url> {
+    let base = std::env::var("STREAMLABS_SOCKET_URL").unwrap_or_else(|_| SOCKET_URL.to_string());
+    let mut url = Url::parse(&base)?;
+    url.set_path("/socket.io/");
+    url.query_pairs_mut()
+        .append_pair("token", token)
+        .append_pair("EIO", "3")
+        .append_pair("transport", "websocket");
+    Ok(url)
+}
+
+#[derive(Debug, Default)]
+pub struct StreamLabsApiConnection {
+    connected: Arc<AtomicBool>,
+    joinhandle: Option<tauri::async_runtime::JoinHandle<()>>,
+}
+
+impl StreamLabsApiConnection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Connects to the socket, reconnecting whenever it drops, replacing any
+    /// running connection.
+    #[instrument(skip_all)]
+    pub async fn run(&mut self, config: Config, event_tx: Sender<IntegrationEvent>) -> Result<()> {
+        let settings = StreamLabsSettings::from_config(&config);
+        if settings.socket_token.is_empty() {
+            bail!("auth.streamlabs.socket_token is required");
+        }
+        let url = socket_url(&settings.socket_token)?;
+        let rates = CurrencyRates::from_config(&config);
+        if let Some(jh) = self.joinhandle.take() {
+            jh.abort();
+        }
+        let connected = Arc::clone(&self.connected);
+        self.joinhandle = Some(tauri::async_runtime::spawn(async move {
+            loop {
+                match SocketIo::connect(&url).await {
+                    Ok(socket) => {
+                        info!("Connected to Streamlabs");
+                        connected.store(true, Ordering::Relaxed);
+                        let _ = event_tx.send(IntegrationEvent::Connected).await;
+                        if let Err(e) = listen(socket, &settings, &rates, &event_tx).await {
+                            error!("Streamlabs socket error: {e}");
+                        }
+                        connected.store(false, Ordering::Relaxed);
+                        if event_tx.is_closed() {
+                            return;
+                        }
+                        let _ = event_tx.send(IntegrationEvent::Disconnected).await;
+                    }
+                    Err(e) => error!("Failed to connect to Streamlabs: {e}"),
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        }));
+        Ok(())
+    }
+
+    pub fn check_status(&self) -> Result<IntegrationStatus, IntegrationError> {
+        match &self.joinhandle {
+            None => Ok(IntegrationStatus::NotStarted),
+            Some(_) if self.connected.load(Ordering::Relaxed) => Ok(IntegrationStatus::Connected {
+                api: Api::StreamLabs,
+                expires_at: None,
+            }),
+            Some(jh) if jh.inner().is_finished() => {
+                Ok(IntegrationStatus::Disconnected(Api::StreamLabs))
+            }
+            Some(_) => Ok(IntegrationStatus::Connecting(Api::StreamLabs)),
+        }
+    }
+}
+
+async fn listen(
+    mut socket: SocketIo,
+    settings: &StreamLabsSettings,
+    rates: &CurrencyRates,
+    event_tx: &Sender<IntegrationEvent>,
+) -> Result<()> {
+    while let Some((name, data)) = socket.next_event().await? {
+        if name != "event" {
+            continue;
+        }
+        let id = data["event_id"]
+            .as_str()
+            .map(|event_id| format!("streamlabs:{event_id}"));
+        if id.as_deref().is_some_and(dedup::is_seen) {
+            continue;
+        }
+        for event in events_from_streamlabs(&data, settings.platform_events, rates) {
+            event_tx.send(event).await?;
+        }
+        if let Some(id) = id {
+            dedup::mark_seen(&id);
+        }
+    }
+    Ok(())
+}
+
+/// Amounts come as numbers or strings such as `"13.37"`.
+fn amount_cents(amount: &Value) -> Option<u64> {
+    match amount {
+        Value::Number(n) => currency::parse_cents(&n.to_string()),
+        Value::String(s) => currency::parse_cents(s),
+        _ => None,
+    }
+}
+
+fn number(value: &Value) -> u64 {
+    match value {
+        Value::Number(n) => n.as_u64().unwrap_or_default(),
+        Value::String(s) => s.parse().unwrap_or_default(),
+        _ => 0,
+    }
+}
+
+fn text(value: &Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+/// The display name if there is one.
+fn best_name(item: &Value) -> String {
+    match item["display_name"].as_str() {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => text(&item["name"]),
+    }
+}
+
+fn sub_tier(plan: &Value) -> SubscriptionTier {
+    match plan.as_str().unwrap_or_default().to_lowercase().as_str() {
+        "1000" => SubscriptionTier::Tier1,
+        "2000" => SubscriptionTier::Tier2,
+        "3000" => SubscriptionTier::Tier3,
+        "prime" => SubscriptionTier::Prime,
+        plan => SubscriptionTier::Other(plan.to_string()),
+    }
+}
+
+/// Maps a Socket API `event`, each holds one or more messages.
+pub fn events_from_streamlabs(
+    data: &Value,
+    platform_events: bool,
+    rates: &CurrencyRates,
+) -> Vec<IntegrationEvent> {
+    let event_type = data["type"].as_str().unwrap_or_default();
+    let platform = data["for"].as_str().unwrap_or("streamlabs");
+    let items = match &data["message"] {
+        Value::Array(items) => items.clone(),
+        item @ Value::Object(_) => vec![item.clone()],
+        _ => Vec::new(),
+    };
+    if platform != "streamlabs" && !platform_events {
+        debug!("Skipping {platform} {event_type} relayed by Streamlabs");
+        return Vec::new();
+    }
+    items
+        .iter()
+        .filter_map(|item| event_from_item(platform, event_type, item, rates))
+        .collect()
+}
+
+fn event_from_item(
+    platform: &str,
+    event_type: &str,
+    item: &Value,
+    rates: &CurrencyRates,
+) -> Option<IntegrationEvent> {
+    match (platform, event_type) {
+        ("streamlabs", "donation") => {
+            let amount = amount_cents(&item["amount"])?;
+            let currency = item["currency"].as_str().unwrap_or("USD").to_uppercase();
+            Some(IntegrationEvent::Donation {
+                user_name: text(&item["name"]),
+                amount,
+                usd_cents: rates.cents_to_usd_cents(amount, &currency),
+                currency,
+                message: text(&item["message"]),
+            })
+        }
+        // Alerts being shown and label updates, not events.
+        ("streamlabs", t) if t.starts_with("streamlabels") || t.ends_with("Playing") => None,
+        ("streamlabs", event_type) => Some(IntegrationEvent::Generic {
+            event_type: format!("streamlabs.{event_type}"),
+            payload: item.clone(),
+        }),
+        ("twitch_account" | "youtube_account", "follow") => Some(IntegrationEvent::Follow {
+            user_name: best_name(item),
+        }),
+        ("twitch_account", "subscription" | "resub") => Some(IntegrationEvent::Subscription {
+            tier: sub_tier(&item["sub_plan"]),
+            user_name: best_name(item),
+        }),
+        ("twitch_account", "bits") => Some(IntegrationEvent::Bits {
+            user_name: best_name(item),
+            bits: number(&item["amount"]),
+        }),
+        ("twitch_account", "raid") => Some(IntegrationEvent::Raid {
+            from: best_name(item),
+            viewers: number(&item["raiders"]),
+        }),
+        ("youtube_account", "superchat") => {
+            let amount_micros = number(&item["amount"]);
+            let currency = text(&item["currency"]).to_uppercase();
+            Some(IntegrationEvent::SuperChat {
+                user_name: best_name(item),
+                amount_micros,
+                usd_cents: rates.to_usd_cents(amount_micros, &currency),
+                currency,
+                message: text(&item["comment"]),
+                sticker: None,
+            })
+        }
+        ("youtube_account", "subscription") => Some(IntegrationEvent::Membership {
+            user_name: best_name(item),
+            level: String::new(),
+            months: number(&item["months"]),
+            gifts: 0,
+        }),
+        (platform, event_type) => {
+            debug!("Ignoring {platform} {event_type} relayed by Streamlabs");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{SinkExt, StreamExt};
+    use rstest::rstest;
+    use serde_json::json;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message;
+
+    fn donation() -> Value {
+        json!({
+            "type": "donation",
+            "for": "streamlabs",
+            "event_id": "evt_1",
+            "message": [{
+                "name": "Cool_User",
+                "amount": "13.37",
+                "formatted_amount": "$13.37",
+                "message": "boom",
+                "currency": "USD",
+                "_id": "1c8d"
+            }]
+        })
+    }
+
+    #[rstest]
+    #[case(
+        donation(),
+        false,
+        vec![IntegrationEvent::Donation { user_name: "Cool_User".into(), amount: 1337, currency: "USD".into(), usd_cents: Some(1337), message: "boom".into() }]
+    )]
+    #[case(
+        json!({ "type": "donation", "for": "streamlabs", "message": [{ "name": "Euro_User", "amount": 10, "currency": "eur", "message": "" }] }),
+        false,
+        vec![IntegrationEvent::Donation { user_name: "Euro_User".into(), amount: 1000, currency: "EUR".into(), usd_cents: Some(1080), message: String::new() }]
+    )]
+    #[case(
+        json!({ "type": "merch", "for": "streamlabs", "message": [{ "name": "Cool_User", "product": "Hoodie" }] }),
+        false,
+        vec![IntegrationEvent::Generic { event_type: "streamlabs.merch".into(), payload: json!({ "name": "Cool_User", "product": "Hoodie" }) }]
+    )]
+    #[case(
+        json!({ "type": "streamlabels", "for": "streamlabs", "message": { "data": {} } }),
+        false,
+        vec![]
+    )]
+    #[case(
+        json!({ "type": "bits", "for": "twitch_account", "message": [{ "name": "cool_user", "display_name": "Cool_User", "amount": "500" }] }),
+        false,
+        vec![]
+    )]
+    #[case(
+        json!({ "type": "bits", "for": "twitch_account", "message": [{ "name": "cool_user", "display_name": "Cool_User", "amount": "500" }] }),
+        true,
+        vec![IntegrationEvent::Bits { user_name: "Cool_User".into(), bits: 500 }]
+    )]
+    #[case(
+        json!({ "type": "subscription", "for": "twitch_account", "message": [{ "name": "cool_user", "months": 3, "sub_plan": "2000" }] }),
+        true,
+        vec![IntegrationEvent::Subscription { tier: SubscriptionTier::Tier2, user_name: "cool_user".into() }]
+    )]
+    #[case(
+        json!({ "type": "superchat", "for": "youtube_account", "message": [{ "name": "Cool_User", "amount": "5000000", "currency": "USD", "comment": "hi" }] }),
+        true,
+        vec![IntegrationEvent::SuperChat { user_name: "Cool_User".into(), amount_micros: 5_000_000, currency: "USD".into(), usd_cents: Some(500), message: "hi".into(), sticker: None }]
+    )]
+    fn map_events(
+        #[case] data: Value,
+        #[case] platform_events: bool,
+        #[case] expected: Vec<IntegrationEvent>,
+    ) {
+        assert_eq!(
+            events_from_streamlabs(&data, platform_events, &CurrencyRates::default()),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn socket_url_has_token() {
+        let url = socket_url("abc").unwrap();
+        assert_eq!(url.path(), "/socket.io/");
+        assert_eq!(url.query(), Some("token=abc&EIO=3&transport=websocket"));
+    }
+
+    /// A Socket.IO stand-in sending a donation after the handshake.
+    #[rstest]
+    #[tokio::test]
+    async fn receives_from_socket_io_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!(
+            "ws://{}/socket.io/?token=abc&EIO=3&transport=websocket",
+            listener.local_addr().unwrap()
+        ))
+        .unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            for packet in [
+                r#"0{"sid":"abc","upgrades":[],"pingInterval":25000,"pingTimeout":60000}"#
+                    .to_string(),
+                "40".to_string(),
+                "2".to_string(),
+                format!(r#"42["event",{}]"#, donation()),
+            ] {
+                socket.send(Message::text(packet)).await.unwrap();
+            }
+            let pong = socket.next().await.unwrap().unwrap();
+            socket.close(None).await.unwrap();
+            pong
+        });
+
+        let mut socket = SocketIo::connect(&url).await.unwrap();
+        let (name, data) = socket.next_event().await.unwrap().unwrap();
+        assert_eq!(name, "event");
+        assert_eq!(
+            events_from_streamlabs(&data, false, &CurrencyRates::default()).len(),
+            1
+        );
+        assert_eq!(socket.next_event().await.unwrap(), None);
+        assert_eq!(server.await.unwrap(), Message::text("3"));
+    }
+}
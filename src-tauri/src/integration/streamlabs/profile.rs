@@ -0,0 +1,865 @@
+//! Imports the profiles of the old Streamlabs Rcon Integration as commands.
+//!
+//! Each filtered action of a reaction becomes a command with a trigger for every
+//! event the reaction handled. Placeholders such as `[BESTNAME]` become command
+//! variables, the condition an `if` around the Lua and the manipulator a local
+//! `CALCVALUE`. Anything without an RCON2.0 equivalent is listed in the
+//! [`ImportReport`] instead.
+use std::{
+    collections::BTreeSet,
+    fmt::{Display, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{bail, Context, Result};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument};
+
+use crate::{
+    command::{
+        escape_lua,
+        settings::ScriptSettings,
+        trigger::{ComparisonOperator, SubscriptionTier},
+        Command, GameServerTrigger, Prefix, RconCommand, RconCommandLua, Trigger, Variable,
+    },
+    integration::PledgeAction,
+    servers::GameServer,
+};
+
+/// The events of the old integration by platform and type, with the `valueType`
+/// that matched them.
+const EVENTS: [(&str, &str, &str); 15] = [
+    ("streamlabs", "donation", "money"),
+    ("patreon", "pledge", "money"),
+    ("twitch_account", "subscription", "money"),
+    ("twitch_account", "subscriptionGift", "money"),
+    ("twitch_account", "subMysteryGift", "money"),
+    ("twitch_account", "bits", "money"),
+    ("twitch_account", "follow", "follow"),
+    ("twitch_account", "host", "viewer"),
+    ("twitch_account", "raid", "viewer"),
+    ("youtube_account", "follow", "follow"),
+    ("youtube_account", "subscription", "money"),
+    ("youtube_account", "superchat", "money"),
+    ("mixer_account", "follow", "follow"),
+    ("mixer_account", "subscription", "money"),
+    ("mixer_account", "host", "viewer"),
+];
+
+/// Every attribute in the old `eventDefinitions.json`, anything else in square
+/// brackets is left alone as it may be Lua indexing.
+const PLACEHOLDERS: [&str; 22] = [
+    "PLATFORM",
+    "TYPE",
+    "ID",
+    "VALUETYPE",
+    "VALUE",
+    "CALCVALUE",
+    "BESTNAME",
+    "BESTCOMMENT",
+    "name",
+    "display_name",
+    "amount",
+    "formatted_amount",
+    "message",
+    "currency",
+    "months",
+    "sub_plan",
+    "gifter",
+    "gifter_display_name",
+    "raiders",
+    "viewers",
+    "comment",
+    "displayString",
+];
+
+type EventKind = (&'static str, &'static str);
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub reactions: Vec<Reaction>,
+    #[serde(default)]
+    pub actions: Vec<Action>,
+    #[serde(default)]
+    pub options: ProfileOptions,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reaction {
+    pub platform: Option<String>,
+    #[serde(rename = "type")]
+    pub event_type: Option<String>,
+    /// `money`, `follow` or `viewer`, for the events without their own reaction.
+    pub value_type: Option<String>,
+    #[serde(default)]
+    pub filtered_actions: Vec<FilteredAction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct FilteredAction {
+    /// Python expression, `[ALL]` always runs.
+    #[serde(default)]
+    pub condition: String,
+    /// Python expression or script giving `[CALCVALUE]`.
+    #[serde(default)]
+    pub manipulator: String,
+    /// The RCON command, `[ACTION_<name>]` for a shared action or `[NOTHING]`.
+    pub action: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Action {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub effect: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct ProfileOptions {
+    /// `donator` reacts to a community gift once, `receiver` once per viewer.
+    #[serde(rename = "twitchMysterSubGiftMode", default)]
+    pub gift_mode: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ImportReport {
+    pub profile: String,
+    pub commands: Vec<Command>,
+    /// Each placeholder, condition or event that could not be mapped.
+    pub unmapped: Vec<String>,
+}
+
+impl Display for ImportReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Imported {} commands from \"{}\"",
+            self.commands.len(),
+            self.profile
+        )?;
+        for command in &self.commands {
+            let triggers = command
+                .server_triggers
+                .iter()
+                .map(|st| st.trigger.name())
+                .join(", ");
+            writeln!(f, "  {} ({triggers})", command.name)?;
+        }
+        if !self.unmapped.is_empty() {
+            writeln!(f, "Not mapped:")?;
+            for line in &self.unmapped {
+                writeln!(f, "  {line}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn event_label((platform, event_type): &EventKind) -> String {
+    format!("{platform}-{event_type}")
+}
+
+fn trigger(kind: &EventKind) -> Option<Trigger> {
+    use ComparisonOperator::Any;
+    match *kind {
+        ("streamlabs", "donation") => Some(Trigger::Donation {
+            amount: 0,
+            currency: String::new(),
+            comparison_operator: Any,
+        }),
+        ("patreon", "pledge") => Some(Trigger::Pledge {
+            tier: String::new(),
+            amount: 0,
+            comparison_operator: Any,
+            action: Some(PledgeAction::Create),
+        }),
+        ("twitch_account", "subscription") => Some(Trigger::Subscription {
+            tier: SubscriptionTier::default(),
+            comparison_operator: Any,
+        }),
+        ("twitch_account", "subscriptionGift" | "subMysteryGift") => Some(Trigger::GiftSub {
+            tier: SubscriptionTier::default(),
+            tier_comparison_operator: Any,
+            count: 0,
+            count_comparison_operator: Any,
+        }),
+        ("twitch_account", "bits") => Some(Trigger::Bits {
+            bits: 0,
+            comparison_operator: Any,
+        }),
+        ("twitch_account" | "youtube_account", "follow") => Some(Trigger::Follow),
+        ("twitch_account", "raid") => Some(Trigger::Raid {
+            viewers: 0,
+            comparison_operator: Any,
+        }),
+        ("youtube_account", "subscription") => Some(Trigger::Membership {
+            level: String::new(),
+        }),
+        ("youtube_account", "superchat") => Some(Trigger::SuperChat {
+            usd_cents: 0,
+            comparison_operator: Any,
+        }),
+        _ => None,
+    }
+}
+
+/// The command variable given the placeholder's value by the event.
+fn variable(kind: &EventKind, placeholder: &str) -> Option<&'static str> {
+    match (kind.0, kind.1, placeholder) {
+        // The name is the receiver's, the event is the gifter's.
+        ("twitch_account", "subscriptionGift", "gifter") => Some("USERNAME"),
+        ("twitch_account", "subscriptionGift", _) => None,
+        ("twitch_account", "subMysteryGift", "gifter" | "gifter_display_name") => Some("USERNAME"),
+        ("twitch_account", "subMysteryGift", "amount") => Some("COUNT"),
+        (_, _, "BESTNAME" | "name" | "display_name") => Some("USERNAME"),
+        ("streamlabs", "donation", "BESTCOMMENT" | "message") => Some("MESSAGE"),
+        ("youtube_account", "superchat", "BESTCOMMENT" | "comment") => Some("MESSAGE"),
+        ("streamlabs", "donation", "amount") | ("youtube_account", "superchat", "amount") => {
+            Some("AMOUNT")
+        }
+        ("streamlabs", "donation", "currency") | ("youtube_account", "superchat", "currency") => {
+            Some("CURRENCY")
+        }
+        ("patreon", "pledge", "amount") => Some("AMOUNT"),
+        ("twitch_account", "bits", "amount") => Some("BITS"),
+        ("twitch_account", "raid", "VALUE" | "raiders") => Some("VIEWERS"),
+        (
+            "streamlabs" | "patreon" | "twitch_account" | "youtube_account",
+            "donation" | "pledge" | "subscription" | "subMysteryGift" | "bits" | "superchat",
+            "VALUE",
+        ) => Some("VALUE"),
+        ("youtube_account", "subscription", "months") => Some("MONTHS"),
+        _ => None,
+    }
+}
+
+fn declaration(variable: &str) -> String {
+    match variable {
+        "VALUE" | "AMOUNT" => format!("{variable}:float=0"),
+        "BITS" | "COUNT" | "MONTHS" | "VIEWERS" => format!("{variable}:int=0"),
+        _ => variable.to_string(),
+    }
+}
+
+/// The placeholder at the start of `text` and the text after it.
+fn placeholder(text: &str) -> Option<(&str, &str)> {
+    let (name, rest) = text.strip_prefix('[')?.split_once(']')?;
+    PLACEHOLDERS.contains(&name).then_some((name, rest))
+}
+
+/// Resolves the placeholders of one action for the events of its reaction.
+struct Placeholders<'a> {
+    kinds: &'a [EventKind],
+    calc_value: bool,
+    variables: BTreeSet<&'static str>,
+    unmapped: Vec<String>,
+}
+
+impl<'a> Placeholders<'a> {
+    fn new(kinds: &'a [EventKind]) -> Self {
+        Self {
+            kinds,
+            calc_value: false,
+            variables: BTreeSet::new(),
+            unmapped: Vec::new(),
+        }
+    }
+
+    /// The variable for the placeholder, [`None`] leaves it as it is.
+    fn resolve(&mut self, placeholder: &str) -> Option<String> {
+        if placeholder == "CALCVALUE" {
+            if !self.calc_value {
+                self.unmapped
+                    .push("[CALCVALUE] has no manipulator that could be converted".to_string());
+            }
+            return self.calc_value.then(|| placeholder.to_string());
+        }
+        let mapped = self
+            .kinds
+            .iter()
+            .map(|kind| (kind, variable(kind, placeholder)))
+            .collect_vec();
+        let Some(name) = mapped.iter().find_map(|(_, name)| *name) else {
+            self.unmapped
+                .push(format!("[{placeholder}] has no RCON2.0 variable"));
+            return None;
+        };
+        for (kind, _) in mapped.iter().filter(|(_, n)| *n != Some(name)) {
+            self.unmapped.push(format!(
+                "[{placeholder}] is {name}, which {} doesn't set",
+                event_label(kind)
+            ));
+        }
+        self.variables.insert(name);
+        Some(name.to_string())
+    }
+
+    /// Replaces the placeholders in Lua or Python code, splitting any string
+    /// they are in. With `python` its functions and `!=` become Lua's.
+    fn code(&mut self, code: &str, python: bool) -> String {
+        let mut lua = String::new();
+        let mut quote = None;
+        let mut rest = code;
+        while let Some(c) = rest.chars().next() {
+            if let Some((name, after)) = placeholder(rest) {
+                match (self.resolve(name), quote) {
+                    (Some(variable), Some(q)) => {
+                        let _ = write!(lua, "{q} .. {variable} .. {q}");
+                    }
+                    (Some(variable), None) => lua.push_str(&variable),
+                    (None, _) => lua.push_str(&rest[..rest.len() - after.len()]),
+                }
+                rest = after;
+                continue;
+            }
+            match (quote, c) {
+                (Some(_), '\\') => {
+                    let escaped = rest.chars().take(2).map(char::len_utf8).sum::<usize>();
+                    lua.push_str(&rest[..escaped]);
+                    rest = &rest[escaped..];
+                    continue;
+                }
+                (Some(q), c) if c == q => quote = None,
+                (None, '\'' | '"') => quote = Some(c),
+                (None, '!') if python && rest.starts_with("!=") => {
+                    lua.push_str("~=");
+                    rest = &rest[2..];
+                    continue;
+                }
+                (None, c) if python && (c.is_ascii_alphabetic() || c == '_') => {
+                    let end = rest
+                        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                        .unwrap_or(rest.len());
+                    let word = &rest[..end];
+                    let word = match word {
+                        _ if lua.ends_with('.') => word,
+                        "int" | "floor" => "math.floor",
+                        "ceil" => "math.ceil",
+                        "abs" => "math.abs",
+                        "min" => "math.min",
+                        "max" => "math.max",
+                        "True" => "true",
+                        "False" => "false",
+                        "None" => "nil",
+                        word => word,
+                    };
+                    lua.push_str(word);
+                    rest = &rest[end..];
+                    continue;
+                }
+                _ => {}
+            }
+            lua.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+        lua
+    }
+
+    /// A Lua expression joining the text and the placeholders' variables.
+    fn text(&mut self, text: &str) -> String {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut rest = text;
+        while let Some(c) = rest.chars().next() {
+            if let Some((name, after)) = placeholder(rest) {
+                match self.resolve(name) {
+                    Some(variable) => {
+                        if !literal.is_empty() {
+                            parts.push(format!("\"{}\"", escape_lua(&literal)));
+                            literal.clear();
+                        }
+                        parts.push(variable);
+                    }
+                    None => literal.push_str(&rest[..rest.len() - after.len()]),
+                }
+                rest = after;
+                continue;
+            }
+            literal.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+        if !literal.is_empty() || parts.is_empty() {
+            parts.push(format!("\"{}\"", escape_lua(&literal)));
+        }
+        parts.join(" .. ")
+    }
+}
+
+impl Profile {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Reading profile {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("Parsing profile {}", path.display()))
+    }
+
+    /// Converts the reactions to commands for `server`.
+    pub fn import(&self, server: &GameServer, enabled: bool) -> ImportReport {
+        let mut report = ImportReport {
+            profile: self.name.clone(),
+            ..Default::default()
+        };
+        for reaction in &self.reactions {
+            let Some((label, kinds)) = self.kinds(reaction, &mut report) else {
+                continue;
+            };
+            for (index, filtered_action) in reaction.filtered_actions.iter().enumerate() {
+                let name = command_name(&format!("{} {label} {}", self.name, index + 1));
+                if let Some(command) =
+                    self.command(&name, &kinds, filtered_action, server, enabled, &mut report)
+                {
+                    report.commands.push(command);
+                }
+            }
+        }
+        report.unmapped = report.unmapped.into_iter().unique().collect();
+        report
+    }
+
+    /// The label and events of the reaction that RCON2.0 has triggers for.
+    fn kinds(
+        &self,
+        reaction: &Reaction,
+        report: &mut ImportReport,
+    ) -> Option<(String, Vec<EventKind>)> {
+        let (label, kinds) = match (
+            &reaction.platform,
+            &reaction.event_type,
+            &reaction.value_type,
+        ) {
+            (Some(platform), Some(event_type), _) => {
+                let kinds = EVENTS
+                    .iter()
+                    .filter(|(p, t, _)| p == platform && t == event_type)
+                    .map(|(p, t, _)| (*p, *t))
+                    .collect_vec();
+                (format!("{platform}-{event_type}"), kinds)
+            }
+            (_, _, Some(value_type)) => {
+                // Only the events without their own reaction, and one of the gift events.
+                let gift = match self.options.gift_mode.as_deref() {
+                    Some("receiver") => "subMysteryGift",
+                    _ => "subscriptionGift",
+                };
+                let kinds = EVENTS
+                    .iter()
+                    .filter(|(p, t, v)| {
+                        v == value_type
+                            && *t != gift
+                            && !self.reactions.iter().any(|r| {
+                                r.platform.as_deref() == Some(p)
+                                    && r.event_type.as_deref() == Some(t)
+                            })
+                    })
+                    .map(|(p, t, _)| (*p, *t))
+                    .collect_vec();
+                (value_type.clone(), kinds)
+            }
+            _ => {
+                report
+                    .unmapped
+                    .push("A reaction has neither a platform and type nor a valueType".to_string());
+                return None;
+            }
+        };
+        let (kinds, missing): (Vec<_>, Vec<_>) =
+            kinds.into_iter().partition(|kind| trigger(kind).is_some());
+        if !missing.is_empty() {
+            report.unmapped.push(format!(
+                "{label}: {} have no RCON2.0 event",
+                missing.iter().map(event_label).join(", ")
+            ));
+        }
+        if kinds.is_empty() {
+            report
+                .unmapped
+                .push(format!("{label}: skipped, no events to trigger on"));
+            return None;
+        }
+        Some((label, kinds))
+    }
+
+    fn command(
+        &self,
+        name: &str,
+        kinds: &[EventKind],
+        filtered_action: &FilteredAction,
+        server: &GameServer,
+        enabled: bool,
+        report: &mut ImportReport,
+    ) -> Option<Command> {
+        let action = filtered_action.action.trim();
+        let action = match action
+            .strip_prefix("[ACTION_")
+            .and_then(|a| a.strip_suffix(']'))
+        {
+            Some(action_name) => match self.actions.iter().find(|a| a.name == action_name) {
+                Some(a) => a.effect.trim(),
+                None => {
+                    report
+                        .unmapped
+                        .push(format!("{name}: no action named {action_name}"));
+                    return None;
+                }
+            },
+            None => action,
+        };
+        if action == "[NOTHING]" || action.is_empty() {
+            return None;
+        }
+
+        let mut placeholders = Placeholders::new(kinds);
+        let manipulator = filtered_action.manipulator.trim();
+        let calc_value = if manipulator.is_empty() {
+            None
+        } else if manipulator.contains('\n') {
+            placeholders.unmapped.push(format!(
+                "the manipulator is a Python script, rewrite it in Lua: {manipulator:?}"
+            ));
+            None
+        } else {
+            Some(placeholders.code(manipulator, true))
+        };
+        placeholders.calc_value = calc_value.is_some();
+        let condition = match filtered_action.condition.trim() {
+            "" | "[ALL]" => None,
+            condition => Some(placeholders.code(condition, true)),
+        };
+
+        let lua = match action.split_once(' ') {
+            Some(("/sc" | "/silent-command", lua)) => {
+                Some((Prefix::SC, placeholders.code(lua, false)))
+            }
+            Some(("/c" | "/command", lua)) => Some((Prefix::C, placeholders.code(lua, false))),
+            Some(("/measured-command", lua)) => Some((Prefix::MC, placeholders.code(lua, false))),
+            _ if action.starts_with('/') => None,
+            // Text sent over RCON is printed to the game's chat.
+            _ => Some((
+                Prefix::SC,
+                format!("game.print({})", placeholders.text(action)),
+            )),
+        };
+        let (prefix, inline) = match lua {
+            Some((prefix, lua)) => {
+                let mut lines = Vec::new();
+                if let Some(calc_value) = calc_value {
+                    lines.push(format!("local CALCVALUE = {calc_value}"));
+                }
+                lines.push(lua);
+                let lua = match condition {
+                    Some(condition) => format!("if {condition} then\n{}\nend", lines.join("\n")),
+                    None => lines.join("\n"),
+                };
+                (prefix, lua)
+            }
+            None => {
+                // Console commands can't read Lua variables or check a condition.
+                let mut rest = action;
+                while let Some(start) = rest.find('[') {
+                    if let Some((name, after)) = placeholder(&rest[start..]) {
+                        placeholders.unmapped.push(format!(
+                            "[{name}] is left as it is, console commands can't use variables"
+                        ));
+                        rest = after;
+                    } else {
+                        rest = &rest[start + 1..];
+                    }
+                }
+                if condition.is_some() || calc_value.is_some() {
+                    placeholders.unmapped.push(
+                        "the condition and manipulator are ignored for a console command"
+                            .to_string(),
+                    );
+                }
+                placeholders.variables.clear();
+                (Prefix::Custom(String::new()), action.to_string())
+            }
+        };
+
+        report.unmapped.extend(
+            placeholders
+                .unmapped
+                .iter()
+                .map(|line| format!("{name}: {line}")),
+        );
+        let variables = placeholders
+            .variables
+            .iter()
+            .filter_map(|v| Variable::from_str(&declaration(v)).ok())
+            .collect_vec();
+        let rcon_lua = RconCommand {
+            prefix,
+            lua_command: RconCommandLua::Inline(inline),
+            variables: (!variables.is_empty()).then_some(variables),
+        };
+        let server_triggers = kinds
+            .iter()
+            .filter_map(trigger)
+            .unique()
+            .map(|trigger| {
+                let mut server_trigger = GameServerTrigger::new(server.clone(), trigger);
+                server_trigger.set_enabled(enabled);
+                server_trigger
+            })
+            .collect_vec();
+        Some(Command::from_config(name, rcon_lua, server_triggers))
+    }
+}
+
+/// Lowercase words joined by `-`, as config keys can't hold spaces or dots.
+fn command_name(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+        .join("-")
+}
+
+/// Reads the profile and saves its commands to the scripts config, unless `dry_run`.
+pub fn import_profile(
+    path: &Path,
+    server_name: &str,
+    enabled: bool,
+    dry_run: bool,
+) -> Result<ImportReport> {
+    let profile = Profile::from_file(path)?;
+    let Some(server) = GameServer::try_get(server_name) else {
+        bail!("No server with name '{server_name}' found.");
+    };
+    let report = profile.import(&server, enabled);
+    if !dry_run {
+        ScriptSettings::new().set_commands(&report.commands)?;
+        info!(
+            "Imported {} commands from \"{}\"",
+            report.commands.len(),
+            profile.name
+        );
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+#[instrument(level = "trace")]
+pub async fn import_streamlabs_profile(
+    path: PathBuf,
+    server_name: String,
+    enabled: bool,
+    dry_run: bool,
+) -> Result<ImportReport, String> {
+    import_profile(&path, &server_name, enabled, dry_run).map_err(|e| format!("{e:#}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use rstest::{fixture, rstest};
+
+    use super::*;
+    use crate::servers::Game;
+
+    const PROFILES: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../example/Streamlabs_Rcon_Integration_-_Alpha_-_2022-12-02/Profiles"
+    );
+
+    #[fixture]
+    fn server() -> GameServer {
+        GameServer::new(
+            "local",
+            "localhost",
+            27015,
+            "password",
+            Game::Factorio,
+            None,
+            <Option<SocketAddr>>::None,
+            None,
+        )
+    }
+
+    fn import(file: &str, server: &GameServer) -> ImportReport {
+        Profile::from_file(&Path::new(PROFILES).join(file))
+            .unwrap()
+            .import(server, false)
+    }
+
+    fn find<'a>(report: &'a ImportReport, name: &str) -> &'a Command {
+        report
+            .commands
+            .iter()
+            .find(|c| c.name == name)
+            .unwrap_or_else(|| panic!("{name} not in {:?}", report.commands))
+    }
+
+    fn lua(command: &Command) -> &str {
+        match &command.rcon_lua.lua_command {
+            RconCommandLua::Inline(lua) => lua,
+            lua => panic!("{lua:?}"),
+        }
+    }
+
+    #[rstest]
+    #[case("[BESTNAME] >= 5", true, "USERNAME >= 5")]
+    #[case("[VALUE] >= 5 and [VALUE] != 10", true, "VALUE >= 5 and VALUE ~= 10")]
+    #[case("int([VALUE]/10)", true, "math.floor(VALUE/10)")]
+    #[case("math.floor([VALUE])", true, "math.floor(VALUE)")]
+    #[case("'[gifter]'", true, "'[gifter]'")]
+    #[case("'[name]'", true, "'' .. USERNAME .. ''")]
+    #[case(
+        r#"game.print('it\'s [name]' .. "[message]")"#,
+        false,
+        r#"game.print('it\'s ' .. USERNAME .. '' .. "" .. MESSAGE .. "")"#
+    )]
+    #[case("t[i] = int", false, "t[i] = int")]
+    fn translate_code(#[case] code: &str, #[case] python: bool, #[case] expected: &str) {
+        let kinds = [("streamlabs", "donation")];
+        let mut placeholders = Placeholders::new(&kinds);
+        assert_eq!(placeholders.code(code, python), expected);
+    }
+
+    #[rstest]
+    #[case("[BESTNAME] followed", r#"USERNAME .. " followed""#)]
+    #[case(r#"Say "[message]""#, r#""Say \"" .. MESSAGE .. "\"""#)]
+    #[case("[sub_plan] months", r#""[sub_plan] months""#)]
+    #[case("", r#""""#)]
+    fn translate_text(#[case] text: &str, #[case] expected: &str) {
+        let kinds = [("streamlabs", "donation")];
+        let mut placeholders = Placeholders::new(&kinds);
+        assert_eq!(placeholders.text(text), expected);
+    }
+
+    #[rstest]
+    fn print_all(server: GameServer) {
+        let report = import("Factorio - Print All.json", &server);
+        assert_eq!(report.commands.len(), 3);
+        let money = find(&report, "factorio-print-all-money-1");
+        assert_eq!(
+            lua(money),
+            r#"game.print(USERNAME .. " supported with $" .. VALUE .. ". They said, " .. MESSAGE)"#
+        );
+        assert_eq!(money.rcon_lua.prefix, Prefix::SC);
+        assert_eq!(
+            money
+                .rcon_lua
+                .variables
+                .iter()
+                .flatten()
+                .map(|v| v.to_string())
+                .collect_vec(),
+            ["MESSAGE", "USERNAME", "VALUE:float=0"]
+        );
+        assert_eq!(
+            money
+                .server_triggers
+                .iter()
+                .map(|st| st.trigger.name())
+                .collect_vec(),
+            [
+                "Donation",
+                "Pledge",
+                "Subscription",
+                "GiftSub",
+                "Bits",
+                "Membership",
+                "SuperChat"
+            ]
+        );
+        assert!(money.server_triggers.iter().all(|st| st.disabled()));
+        // Every money event has a value.
+        assert!(!report
+            .unmapped
+            .iter()
+            .any(|line| line.contains("twitch_account-bits doesn't set")));
+        assert!(report.unmapped.contains(
+            &"viewer: twitch_account-host, mixer_account-host have no RCON2.0 event".to_string()
+        ));
+        let viewer = find(&report, "factorio-print-all-viewer-1");
+        assert_eq!(
+            lua(viewer),
+            r#"game.print(USERNAME .. " hosted with " .. VIEWERS .. " viewers")"#
+        );
+    }
+
+    #[rstest]
+    fn print_most_fancy(server: GameServer) {
+        let report = import("Factorio - Print Most Fancy.json", &server);
+        let donation = find(&report, "factorio-print-most-fancy-streamlabs-donation-1");
+        assert_eq!(
+            lua(donation),
+            "if VALUE >= 5 then\nlocal CALCVALUE = VALUE * 1.5\ngame.print(\"Thanks for the $\" .. VALUE .. \" donation \" .. USERNAME .. \", its worth $\" .. CALCVALUE .. \" to me\")\nend"
+        );
+        let small = find(&report, "factorio-print-most-fancy-streamlabs-donation-2");
+        assert_eq!(
+            lua(small),
+            "if VALUE < 5 then\ngame.print(\"Ta for $\" .. VALUE .. \" \" .. USERNAME)\nend"
+        );
+        // Donations have their own reaction.
+        let money = find(&report, "factorio-print-most-fancy-money-1");
+        assert!(!money
+            .server_triggers
+            .iter()
+            .any(|st| st.trigger.name() == "Donation"));
+        // [NOTHING]
+        assert!(!report
+            .commands
+            .iter()
+            .any(|c| c.name.starts_with("factorio-print-most-fancy-follow")));
+        assert!(report.unmapped.contains(
+            &"factorio-print-most-fancy-twitch_account-subscription-1: [months] has no RCON2.0 variable".to_string()
+        ));
+    }
+
+    #[rstest]
+    fn advanced_usage(server: GameServer) {
+        let report = import("Factorio - Advanced Usage Example.json", &server);
+        let console = find(&report, "factorio-advanced-usage-example-money-1");
+        assert_eq!(console.rcon_lua.prefix, Prefix::Custom(String::new()));
+        assert_eq!(lua(console), "/a_custom_command [CALCVALUE] '[BESTNAME]'");
+        assert!(report.unmapped.iter().any(|line| line.starts_with(
+            "factorio-advanced-usage-example-money-3: the manipulator is a Python script"
+        )));
+        let viewer = find(&report, "factorio-advanced-usage-example-viewer-1");
+        assert_eq!(
+            lua(viewer),
+            "if VIEWERS >= 0 then\nlocal CALCVALUE = 'bo\\'b'\ngame.print('' .. CALCVALUE .. '\\'s great' .. ' and ' .. \"\" .. USERNAME .. \"'s awesome\")\nend"
+        );
+    }
+
+    #[rstest]
+    #[case("Factorio - Print All money 1", "factorio-print-all-money-1")]
+    #[case("v1.2 twitch_account-bits 3", "v1-2-twitch_account-bits-3")]
+    fn normalise_name(#[case] name: &str, #[case] expected: &str) {
+        assert_eq!(command_name(name), expected);
+    }
+
+    #[rstest]
+    fn save_to_scripts_config(server: GameServer) {
+        let folder = std::env::temp_dir().join(format!("rcon2-profile-{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        let mut settings = ScriptSettings {
+            config_builder: config::Config::builder(),
+            scripts_folder: folder.clone(),
+            config_filename: "config.toml".to_string(),
+            config_fileformat: config::FileFormat::Toml,
+        };
+        let report = import("Factorio - Print All.json", &server);
+        settings.set_commands(&report.commands).unwrap();
+
+        let saved = std::fs::read_to_string(settings.config_filepath()).unwrap();
+        std::fs::remove_dir_all(&folder).unwrap();
+        let saved: toml::Table = toml::from_str(&saved).unwrap();
+        for command in &report.commands {
+            assert!(
+                saved.contains_key(&command.name),
+                "{} not saved",
+                command.name
+            );
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! Just enough of a Socket.IO v2 client (Engine.IO v3) over a websocket to
+//! receive events, which is all the Streamlabs Socket API needs.
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::time::{sleep_until, Instant};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, warn};
+use url::Url;
+
+type WebsocketStream =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Handshake {
+    pub sid: String,
+    /// Milliseconds between the client's pings.
+    pub ping_interval: u64,
+    pub ping_timeout: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Packet {
+    Open(Handshake),
+    Close,
+    Ping,
+    Pong,
+    Noop,
+    Connect,
+    Disconnect,
+    Event { name: String, data: Value },
+    Error(String),
+    Unknown(String),
+}
+
+impl Packet {
+    /// Parses an Engine.IO packet, and the Socket.IO packet it carries.
+    pub fn parse(text: &str) -> Self {
+        let mut chars = text.chars();
+        match chars.next() {
+            Some('0') => match serde_json::from_str(chars.as_str()) {
+                Ok(handshake) => Self::Open(handshake),
+                Err(_) => Self::Unknown(text.to_string()),
+            },
+            Some('1') => Self::Close,
+            Some('2') => Self::Ping,
+            Some('3') => Self::Pong,
+            Some('6') => Self::Noop,
+            Some('4') => Self::parse_message(chars.as_str()),
+            _ => Self::Unknown(text.to_string()),
+        }
+    }
+
+    fn parse_message(text: &str) -> Self {
+        let mut chars = text.chars();
+        match chars.next() {
+            Some('0') => Self::Connect,
+            Some('1') => Self::Disconnect,
+            Some('2') => {
+                // A namespace or ack id may come before the array.
+                let body = chars.as_str();
+                let body = &body[body.find('[').unwrap_or(0)..];
+                match serde_json::from_str::<Vec<Value>>(body) {
+                    Ok(array) => match array.as_slice() {
+                        [Value::String(name), data, ..] => Self::Event {
+                            name: name.clone(),
+                            data: data.clone(),
+                        },
+                        [Value::String(name)] => Self::Event {
+                            name: name.clone(),
+                            data: Value::Null,
+                        },
+                        _ => Self::Unknown(text.to_string()),
+                    },
+                    Err(_) => Self::Unknown(text.to_string()),
+                }
+            }
+            Some('4') => Self::Error(chars.as_str().to_string()),
+            _ => Self::Unknown(text.to_string()),
+        }
+    }
+}
+
+/// A connected socket, pinging the server as the handshake asks.
+pub struct SocketIo {
+    stream: WebsocketStream,
+    ping_interval: Duration,
+    next_ping: Instant,
+}
+
+impl SocketIo {
+    /// Connects and waits for the handshake.
+    pub async fn connect(url: &Url) -> Result<Self> {
+        let (mut stream, _response) = tokio_tungstenite::connect_async(url.as_str()).await?;
+        let handshake = loop {
+            match stream.next().await {
+                Some(Ok(Message::Text(text))) => match Packet::parse(&text) {
+                    Packet::Open(handshake) => break handshake,
+                    packet => debug!("Before handshake: {packet:?}"),
+                },
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => bail!(e),
+                None => bail!("Socket closed before the handshake"),
+            }
+        };
+        debug!("Socket.IO session {}", handshake.sid);
+        let ping_interval = Duration::from_millis(handshake.ping_interval);
+        Ok(Self {
+            stream,
+            ping_interval,
+            next_ping: Instant::now() + ping_interval,
+        })
+    }
+
+    /// The next event, [`None`] once the server closes the connection.
+    pub async fn next_event(&mut self) -> Result<Option<(String, Value)>> {
+        loop {
+            let message = tokio::select! {
+                message = self.stream.next() => message,
+                _ = sleep_until(self.next_ping) => {
+                    self.stream.send(Message::text("2")).await?;
+                    self.next_ping = Instant::now() + self.ping_interval;
+                    continue;
+                }
+            };
+            let text = match message {
+                Some(Ok(Message::Text(text))) => text,
+                Some(Ok(Message::Close(_))) | None => return Ok(None),
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => bail!(e),
+            };
+            match Packet::parse(&text) {
+                Packet::Event { name, data } => return Ok(Some((name, data))),
+                // Engine.IO v4 servers ping the client instead.
+                Packet::Ping => self.stream.send(Message::text("3")).await?,
+                Packet::Close | Packet::Disconnect => return Ok(None),
+                Packet::Error(e) => bail!("Socket.IO error: {e}"),
+                Packet::Unknown(text) => warn!("Unknown Socket.IO packet: {text}"),
+                Packet::Open(_) | Packet::Pong | Packet::Noop | Packet::Connect => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde_json::json;
+
+    #[rstest]
+    #[case(
+        r#"0{"sid":"abc","upgrades":[],"pingInterval":25000,"pingTimeout":5000}"#,
+        Packet::Open(Handshake { sid: "abc".into(), ping_interval: 25000, ping_timeout: 5000 })
+    )]
+    #[case("2", Packet::Ping)]
+    #[case("3", Packet::Pong)]
+    #[case("40", Packet::Connect)]
+    #[case(r#"42["event",{"type":"donation"}]"#, Packet::Event { name: "event".into(), data: json!({"type": "donation"}) })]
+    #[case(r#"42/ns,["event",1]"#, Packet::Event { name: "event".into(), data: json!(1) })]
+    #[case(r#"44"Not authorized""#, Packet::Error(r#""Not authorized""#.into()))]
+    #[case("x", Packet::Unknown("x".into()))]
+    fn parse(#[case] text: &str, #[case] expected: Packet) {
+        assert_eq!(Packet::parse(text), expected);
+    }
+}
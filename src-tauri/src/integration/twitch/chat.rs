@@ -0,0 +1,239 @@
+//! Chat replies sent through Helix Send Chat Message, see
+//! [`Feedback::Reply`](crate::integration::feedback::Feedback::Reply).
+use std::{
+    collections::VecDeque,
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Result};
+use config::Config;
+use tokio::{sync::broadcast::error::RecvError, time::sleep};
+use tracing::{debug, info, warn};
+
+use super::TwitchApiConnection;
+use crate::{
+    integration::{
+        feedback::{Feedback, FEEDBACK},
+        Platform,
+    },
+    localhost::TwitchState,
+};
+
+/// Longest chat message Twitch accepts.
+pub const MAX_MESSAGE_LENGTH: usize = 500;
+
+/// Twitch counts chat messages over a rolling 30 seconds.
+pub const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(30);
+
+/// Most sent message ids remembered, the oldest are forgotten first.
+pub const SENT_CAPACITY: usize = 100;
+
+/// Replies are sent as the broadcaster, so Twitch delivers them back as
+/// `channel.chat.message` where they could trigger the command again.
+pub static SENT_MESSAGES: LazyLock<Mutex<SentMessages>> =
+    LazyLock::new(|| Mutex::new(SentMessages::new(SENT_CAPACITY)));
+
+/// Returns `true` if the chat message is a reply sent by [`start`], which must
+/// not reach the runner.
+pub fn is_own_message(id: &str) -> bool {
+    let mut sent = SENT_MESSAGES.lock().unwrap_or_else(|e| e.into_inner());
+    if sent.remove(id) {
+        debug!("Dropped own chat message: {id}");
+        true
+    } else {
+        false
+    }
+}
+
+/// A bounded set of chat message ids.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentMessages {
+    ids: VecDeque<String>,
+    capacity: usize,
+}
+
+impl SentMessages {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            ids: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn insert(&mut self, id: String) {
+        if self.ids.len() == self.capacity {
+            self.ids.pop_front();
+        }
+        self.ids.push_back(id);
+    }
+
+    /// Each message is delivered once, so the id is forgotten when it matches.
+    pub fn remove(&mut self, id: &str) -> bool {
+        match self.ids.iter().position(|sent| sent == id) {
+            Some(index) => {
+                self.ids.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Allows at most `limit` messages in any `window`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimiter {
+    limit: usize,
+    window: Duration,
+    sent: VecDeque<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: usize, window: Duration) -> Self {
+        Self {
+            limit: limit.max(1),
+            window,
+            sent: VecDeque::new(),
+        }
+    }
+
+    /// Reads `auth.twitch.chat_rate_limit`, the messages allowed per 30 seconds.
+    /// Twitch allows 20, or 100 if the account is a moderator or the broadcaster.
+    pub fn from_config(config: &Config) -> Self {
+        let limit = config
+            .get_int("auth.twitch.chat_rate_limit")
+            .unwrap_or(20)
+            .max(1) as usize;
+        Self::new(limit, RATE_LIMIT_WINDOW)
+    }
+
+    /// Records a message sent at `now`, or returns how long to wait if the limit
+    /// has been reached.
+    pub fn try_acquire(&mut self, now: Instant) -> Result<(), Duration> {
+        while let Some(oldest) = self.sent.front() {
+            if now.duration_since(*oldest) >= self.window {
+                self.sent.pop_front();
+            } else {
+                break;
+            }
+        }
+        if self.sent.len() < self.limit {
+            self.sent.push_back(now);
+            Ok(())
+        } else {
+            Err(self.window - now.duration_since(self.sent[0]))
+        }
+    }
+
+    /// Waits until a message may be sent.
+    pub async fn acquire(&mut self) {
+        while let Err(wait) = self.try_acquire(Instant::now()) {
+            sleep(wait).await;
+        }
+    }
+}
+
+/// Cuts the message to [`MAX_MESSAGE_LENGTH`] characters.
+pub fn truncate_message(message: &str) -> &str {
+    match message.char_indices().nth(MAX_MESSAGE_LENGTH) {
+        Some((end, _)) => &message[..end],
+        None => message,
+    }
+}
+
+impl TwitchApiConnection {
+    /// Sends the message to the broadcaster's chat as the authenticated user and
+    /// remembers its id in [`SENT_MESSAGES`].
+    pub async fn send_chat_message(&mut self, message: &str) -> Result<()> {
+        let token = match self.check_token().await {
+            Ok(t) => t,
+            Err(e) => bail!("Not authenticated: {:?}", e),
+        };
+        let response = self
+            .client
+            .helix
+            .send_chat_message(
+                &token.user_id,
+                &token.user_id,
+                truncate_message(message),
+                &token,
+            )
+            .await?;
+        if !response.is_sent {
+            bail!("Message dropped by Twitch: {:?}", response.drop_reason);
+        }
+        if let Some(id) = response.message_id {
+            SENT_MESSAGES
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(id.take());
+        }
+        info!("Sent chat message: {message}");
+        Ok(())
+    }
+}
+
+/// Sends [`Feedback::Reply`]s to chat in the background, no faster than the limiter allows.
+pub fn start(twitch: TwitchState, mut limiter: RateLimiter) {
+    let mut rx = FEEDBACK.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(Feedback::Reply { event, message })
+                    if event.platform() == Some(Platform::Twitch) =>
+                {
+                    limiter.acquire().await;
+                    if let Err(e) = twitch.lock().await.send_chat_message(&message).await {
+                        warn!("Failed to send chat message: {:?}", e);
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => warn!("Dropped {skipped} chat replies."),
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn rate_limited() {
+        let mut limiter = RateLimiter::new(2, RATE_LIMIT_WINDOW);
+        let start = Instant::now();
+        assert!(limiter.try_acquire(start).is_ok());
+        assert!(limiter.try_acquire(start + Duration::from_secs(10)).is_ok());
+        assert_eq!(
+            limiter.try_acquire(start + Duration::from_secs(20)),
+            Err(Duration::from_secs(10))
+        );
+        assert!(limiter.try_acquire(start + Duration::from_secs(30)).is_ok());
+        assert_eq!(
+            limiter.try_acquire(start + Duration::from_secs(31)),
+            Err(Duration::from_secs(9))
+        );
+    }
+
+    #[rstest]
+    fn own_messages_forgotten() {
+        let mut sent = SentMessages::new(2);
+        sent.insert("a".to_string());
+        sent.insert("b".to_string());
+        sent.insert("c".to_string());
+        assert!(!sent.remove("a"));
+        assert!(sent.remove("b"));
+        assert!(!sent.remove("b"));
+        assert!(sent.remove("c"));
+        assert!(!sent.remove("unknown"));
+    }
+
+    #[rstest]
+    #[case("short", 5)]
+    #[case(&"é".repeat(600), MAX_MESSAGE_LENGTH)]
+    fn truncated(#[case] message: &str, #[case] length: usize) {
+        assert_eq!(truncate_message(message).chars().count(), length);
+    }
+}
@@ -0,0 +1,274 @@
+//! EventSub conduits. Subscriptions bind to the conduit rather than a websocket
+//! session, so they survive restarts, and each shard of the conduit is either
+//! the websocket or the webhook. A shard Twitch disables, e.g. when the
+//! websocket drops, is assigned again on the `conduit.shard.disabled` event and
+//! on every websocket welcome.
+use anyhow::{bail, Result};
+use config::Config;
+use serde_json::json;
+use tracing::{info, warn};
+use twitch_api::{
+    client::ClientDefault,
+    eventsub::{self, conduit::ConduitShardDisabledV1, EventType, Shard, Transport},
+    HelixClient,
+};
+use twitch_oauth2::{AppAccessToken, ClientId, ClientSecret, TwitchToken};
+
+use super::{generic::create_subscription, webhook::WebhookSettings};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShardTransport {
+    Websocket,
+    Webhook,
+}
+
+impl TryFrom<String> for ShardTransport {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "websocket" => Ok(Self::Websocket),
+            "webhook" => Ok(Self::Webhook),
+            _ => bail!(
+                "Invalid conduit shard: {}, expected Websocket or Webhook",
+                value
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConduitSettings {
+    /// Conduit to attach to, otherwise the first one owned by the client id or a new one.
+    pub conduit_id: Option<String>,
+    /// Transport of each shard, the index is the shard id.
+    pub shards: Vec<ShardTransport>,
+}
+
+impl ConduitSettings {
+    /// Reads `auth.twitch.conduit`, `auth.twitch.conduit_id` and
+    /// `auth.twitch.conduit_shards`. [`None`] if conduits are not enabled.
+    pub fn from_config(config: &Config) -> Result<Option<Self>> {
+        if !config.get_bool("auth.twitch.conduit").unwrap_or(false) {
+            return Ok(None);
+        }
+        let conduit_id = config
+            .get_string("auth.twitch.conduit_id")
+            .ok()
+            .filter(|id| !id.is_empty());
+        let shards = config
+            .get::<Vec<String>>("auth.twitch.conduit_shards")
+            .unwrap_or_else(|_| vec!["Websocket".to_string()])
+            .into_iter()
+            .map(ShardTransport::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        if shards.is_empty() {
+            bail!("auth.twitch.conduit_shards needs at least one shard");
+        }
+        if shards
+            .iter()
+            .filter(|s| **s == ShardTransport::Websocket)
+            .count()
+            > 1
+        {
+            bail!("auth.twitch.conduit_shards can only have one Websocket shard");
+        }
+        Ok(Some(Self { conduit_id, shards }))
+    }
+
+    pub fn has_shard(&self, transport: ShardTransport) -> bool {
+        self.shards.contains(&transport)
+    }
+}
+
+/// Transport of each shard, the websocket shard is left out until it has a session.
+pub fn shards(
+    transports: &[ShardTransport],
+    session_id: Option<&str>,
+    webhook: Option<&WebhookSettings>,
+) -> Result<Vec<Shard>> {
+    let mut shards = Vec::new();
+    for (id, transport) in transports.iter().enumerate() {
+        let transport = match (transport, session_id, webhook) {
+            (ShardTransport::Websocket, Some(session_id), _) => Transport::websocket(session_id),
+            (ShardTransport::Websocket, None, _) => continue,
+            (ShardTransport::Webhook, _, Some(webhook)) => {
+                Transport::webhook(&webhook.callback, webhook.secret.clone())
+            }
+            (ShardTransport::Webhook, _, None) => {
+                bail!("A Webhook shard needs auth.twitch.webhook_callback and webhook_secret")
+            }
+        };
+        shards.push(Shard::new(id.to_string(), transport));
+    }
+    Ok(shards)
+}
+
+#[derive(Clone)]
+pub struct Conduit {
+    pub id: String,
+    settings: ConduitSettings,
+    webhook: Option<WebhookSettings>,
+    client: HelixClient<'static, reqwest::Client>,
+    token: AppAccessToken,
+}
+
+impl std::fmt::Debug for Conduit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Conduit")
+            .field("id", &self.id)
+            .field("settings", &self.settings)
+            .finish()
+    }
+}
+
+impl Conduit {
+    /// Attaches to the configured or an existing conduit, or creates one, and
+    /// resizes it to the configured shards.
+    pub async fn attach(
+        client_id: String,
+        client_secret: String,
+        settings: ConduitSettings,
+        webhook: Option<WebhookSettings>,
+    ) -> Result<Self> {
+        let client: HelixClient<'static, reqwest::Client> = HelixClient::with_client(
+            <reqwest::Client>::default_client_with_name(Some("twitch-rs/conduit".parse()?))?,
+        );
+        let token = AppAccessToken::get_app_access_token(
+            &client.clone_client(),
+            ClientId::new(client_id),
+            ClientSecret::new(client_secret),
+            vec![],
+        )
+        .await?;
+        let conduits = client.get_conduits(&token).await?;
+        let existing = match &settings.conduit_id {
+            Some(id) => match conduits.into_iter().find(|c| c.id.as_str() == id) {
+                Some(conduit) => Some(conduit),
+                None => bail!("Conduit {id} not found"),
+            },
+            None => conduits.into_iter().next(),
+        };
+        let shard_count = settings.shards.len();
+        let conduit = match existing {
+            Some(conduit) if conduit.shard_count == shard_count => conduit,
+            Some(conduit) => {
+                client
+                    .update_conduit(conduit.id, shard_count, &token)
+                    .await?
+            }
+            None => client.create_conduit(shard_count, &token).await?,
+        };
+        info!("Using conduit {} with {} shards", conduit.id, shard_count);
+        Ok(Self {
+            id: conduit.id.take(),
+            settings,
+            webhook,
+            client,
+            token,
+        })
+    }
+
+    /// Points the shards at this app, the websocket shard needs the session.
+    pub async fn assign(&self, session_id: Option<&str>) -> Result<()> {
+        let shards = shards(&self.settings.shards, session_id, self.webhook.as_ref())?;
+        if shards.is_empty() {
+            return Ok(());
+        }
+        let response = self
+            .client
+            .update_conduit_shards(self.id.as_str(), shards, &self.token)
+            .await?;
+        if !response.errors.is_empty() {
+            let errors: Vec<String> = response
+                .errors
+                .iter()
+                .map(|e| format!("shard {}: {}", e.id, e.message))
+                .collect();
+            bail!("Failed to assign conduit shards: {}", errors.join(", "));
+        }
+        info!("Assigned {} conduit shards", response.shards.len());
+        Ok(())
+    }
+
+    /// Subscribes the conduit, returns the subscriptions which failed.
+    pub async fn subscribe(&self, subscriptions: &[EventType], user_id: &str) -> Vec<String> {
+        let mut failures = Vec::new();
+        let transport = json!({ "method": "conduit", "conduit_id": self.id });
+        for subscription in subscriptions {
+            match create_subscription(
+                &self.client.clone_client(),
+                &self.token,
+                *subscription,
+                user_id,
+                transport.clone(),
+            )
+            .await
+            {
+                Ok(()) => info!("Subscribed to {} on conduit", subscription),
+                Err(e) => {
+                    warn!("Failed to subscribe to {} on conduit: {}", subscription, e);
+                    failures.push(subscription.to_string());
+                }
+            }
+        }
+        let shard_disabled = ConduitShardDisabledV1::client_id(self.token.client_id().as_str())
+            .conduit_id(self.id.as_str());
+        if let Err(e) = self
+            .client
+            .create_eventsub_subscription(
+                shard_disabled,
+                eventsub::Transport::conduit(&self.id),
+                &self.token,
+            )
+            .await
+        {
+            // The websocket shard is assigned on every welcome regardless.
+            warn!(
+                "Failed to subscribe to {}: {}",
+                EventType::ConduitShardDisabled,
+                e
+            );
+        }
+        failures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn webhook() -> WebhookSettings {
+        WebhookSettings {
+            callback: "https://rcon.example.com/webhook/twitch".parse().unwrap(),
+            secret: "s3cRe7s3cRe7".to_string(),
+        }
+    }
+
+    #[rstest]
+    fn websocket_shard_waits_for_session() {
+        let transports = [ShardTransport::Websocket, ShardTransport::Webhook];
+        let connecting = shards(&transports, None, Some(&webhook())).unwrap();
+        assert_eq!(connecting.len(), 1);
+        assert_eq!(connecting[0].id.as_str(), "1");
+        assert!(connecting[0].transport.is_webhook());
+
+        let connected = shards(&transports, Some("session"), Some(&webhook())).unwrap();
+        assert_eq!(connected.len(), 2);
+        assert_eq!(connected[0].transport, Transport::websocket("session"));
+    }
+
+    #[rstest]
+    fn webhook_shard_needs_settings() {
+        assert!(shards(&[ShardTransport::Webhook], None, None).is_err());
+    }
+
+    #[rstest]
+    #[case("websocket", Some(ShardTransport::Websocket))]
+    #[case("Webhook", Some(ShardTransport::Webhook))]
+    #[case("conduit", None)]
+    fn shard_transport(#[case] value: &str, #[case] expected: Option<ShardTransport>) {
+        assert_eq!(ShardTransport::try_from(value.to_string()).ok(), expected);
+    }
+}
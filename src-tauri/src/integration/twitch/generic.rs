@@ -0,0 +1,236 @@
+//! EventSub types without their own handler, subscribed from the `websocket_subscription`
+//! config and delivered as [`IntegrationEvent::Generic`]. Webhook subscriptions are
+//! created here for every type, see [`super::webhook`].
+use anyhow::{bail, Result};
+use serde_json::{json, Value};
+use twitch_api::eventsub::EventType;
+use twitch_oauth2::TwitchToken;
+
+use super::websocket::WebsocketClient;
+use crate::integration::IntegrationEvent;
+
+/// Which user ids the subscription condition needs, all of them are the authenticated user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Condition {
+    Broadcaster,
+    BroadcasterModerator,
+    BroadcasterUser,
+    ToBroadcaster,
+    User,
+}
+
+/// Version and condition of the event type, [`None`] if unknown.
+pub fn subscription(event_type: EventType, user_id: &str) -> Option<(&'static str, Value)> {
+    use Condition::*;
+    use EventType::*;
+    let (version, condition) = match event_type {
+        AutomodMessageHold | AutomodMessageUpdate => ("2", BroadcasterModerator),
+        AutomodSettingsUpdate | AutomodTermsUpdate => ("1", BroadcasterModerator),
+        ChannelChatMessage => ("1", BroadcasterUser),
+        ChannelPointsCustomRewardRedemptionAdd | ChannelPointsCustomRewardRedemptionUpdate => {
+            ("1", Broadcaster)
+        }
+        ChannelSubscribe | ChannelSubscriptionMessage | ChannelSubscriptionGift => {
+            ("1", Broadcaster)
+        }
+        ChannelBitsUse | ChannelCheer => ("1", Broadcaster),
+        ChannelFollow => ("2", BroadcasterModerator),
+        ChannelRaid => ("1", ToBroadcaster),
+        ChannelHypeTrainBegin | ChannelHypeTrainProgress | ChannelHypeTrainEnd => {
+            ("1", Broadcaster)
+        }
+        ChannelPollBegin | ChannelPollProgress | ChannelPollEnd => ("1", Broadcaster),
+        ChannelPredictionBegin | ChannelPredictionProgress | ChannelPredictionEnd => {
+            ("1", Broadcaster)
+        }
+        ChannelUpdate => ("2", Broadcaster),
+        ChannelAdBreakBegin => ("1", Broadcaster),
+        ChannelChatClear
+        | ChannelChatClearUserMessages
+        | ChannelChatMessageDelete
+        | ChannelChatNotification
+        | ChannelChatSettingsUpdate
+        | ChannelChatUserMessageHold
+        | ChannelChatUserMessageUpdate => ("1", BroadcasterUser),
+        ChannelSharedChatBegin | ChannelSharedChatUpdate | ChannelSharedChatEnd => {
+            ("1", Broadcaster)
+        }
+        ChannelSubscriptionEnd => ("1", Broadcaster),
+        ChannelBan | ChannelUnban => ("1", Broadcaster),
+        ChannelUnbanRequestCreate | ChannelUnbanRequestResolve => ("1", BroadcasterModerator),
+        ChannelModerate => ("2", BroadcasterModerator),
+        ChannelModeratorAdd | ChannelModeratorRemove => ("1", Broadcaster),
+        ChannelPointsAutomaticRewardRedemptionAdd => ("2", Broadcaster),
+        ChannelPointsCustomRewardAdd
+        | ChannelPointsCustomRewardUpdate
+        | ChannelPointsCustomRewardRemove => ("1", Broadcaster),
+        ChannelPredictionLock => ("1", Broadcaster),
+        ChannelCharityCampaignDonate
+        | ChannelCharityCampaignStart
+        | ChannelCharityCampaignProgress
+        | ChannelCharityCampaignStop => ("1", Broadcaster),
+        ChannelGoalBegin | ChannelGoalProgress | ChannelGoalEnd => ("1", Broadcaster),
+        ChannelShieldModeBegin | ChannelShieldModeEnd => ("1", BroadcasterModerator),
+        ChannelShoutoutCreate | ChannelShoutoutReceive => ("1", BroadcasterModerator),
+        ChannelSuspiciousUserMessage | ChannelSuspiciousUserUpdate => ("1", BroadcasterModerator),
+        ChannelVipAdd | ChannelVipRemove => ("1", Broadcaster),
+        ChannelWarningAcknowledge | ChannelWarningSend => ("1", BroadcasterModerator),
+        StreamOnline | StreamOffline => ("1", Broadcaster),
+        UserUpdate | UserWhisperMessage => ("1", User),
+        _ => return None,
+    };
+    let condition = match condition {
+        Broadcaster => json!({ "broadcaster_user_id": user_id }),
+        BroadcasterModerator => json!({
+            "broadcaster_user_id": user_id,
+            "moderator_user_id": user_id,
+        }),
+        BroadcasterUser => json!({ "broadcaster_user_id": user_id, "user_id": user_id }),
+        ToBroadcaster => json!({ "to_broadcaster_user_id": user_id }),
+        User => json!({ "user_id": user_id }),
+    };
+    Some((version, condition))
+}
+
+/// The `message_id` of a raw websocket notification, the same when Twitch resends it.
+pub fn message_id(text: &str) -> Option<String> {
+    let message: Value = serde_json::from_str(text).ok()?;
+    if message["metadata"]["message_type"] != "notification" {
+        return None;
+    }
+    message["metadata"]["message_id"]
+        .as_str()
+        .map(str::to_string)
+}
+
+/// The event of a raw websocket notification.
+pub fn event_from_notification(text: &str) -> Option<IntegrationEvent> {
+    let message: Value = serde_json::from_str(text).ok()?;
+    if message["metadata"]["message_type"] != "notification" {
+        return None;
+    }
+    Some(IntegrationEvent::Generic {
+        event_type: message["metadata"]["subscription_type"]
+            .as_str()?
+            .to_string(),
+        payload: message["payload"]["event"].clone(),
+    })
+}
+
+/// The event of a webhook notification body.
+pub fn event_from_webhook(body: &[u8]) -> Option<IntegrationEvent> {
+    let notification: Value = serde_json::from_slice(body).ok()?;
+    Some(IntegrationEvent::Generic {
+        event_type: notification["subscription"]["type"].as_str()?.to_string(),
+        payload: notification["event"].clone(),
+    })
+}
+
+/// Creates the subscription through Helix without a typed request, `transport`
+/// is the transport object of the request body.
+pub async fn create_subscription(
+    client: &reqwest::Client,
+    token: &impl TwitchToken,
+    event_type: EventType,
+    user_id: &str,
+    transport: Value,
+) -> Result<()> {
+    let Some((version, condition)) = subscription(event_type, user_id) else {
+        bail!("Unknown condition for {event_type}");
+    };
+    let body = json!({
+        "type": event_type.to_str(),
+        "version": version,
+        "condition": condition,
+        "transport": transport,
+    });
+    let url = twitch_api::TWITCH_HELIX_URL.join("eventsub/subscriptions")?;
+    let response = client
+        .post(url)
+        .header("Client-Id", token.client_id().as_str())
+        .bearer_auth(token.token().secret())
+        .json(&body)
+        .send()
+        .await?;
+    // Conflict means the subscription already exists, e.g. a webhook after a restart.
+    if !response.status().is_success() && response.status() != reqwest::StatusCode::CONFLICT {
+        bail!("{}: {}", response.status(), response.text().await?);
+    }
+    Ok(())
+}
+
+impl WebsocketClient {
+    pub async fn create_generic_subscription(
+        &self,
+        event_type: EventType,
+        session_id: &str,
+    ) -> Result<()> {
+        create_subscription(
+            &self.client.clone_client(),
+            &self.token,
+            event_type,
+            self.user_id.as_str(),
+            json!({ "method": "websocket", "session_id": session_id }),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(EventType::ChannelAdBreakBegin, Some(("1", json!({"broadcaster_user_id": "1337"}))))]
+    #[case(EventType::ChannelShoutoutCreate, Some(("1", json!({"broadcaster_user_id": "1337", "moderator_user_id": "1337"}))))]
+    #[case(EventType::ChannelRaid, Some(("1", json!({"to_broadcaster_user_id": "1337"}))))]
+    #[case(EventType::ChannelGuestStarSessionBegin, None)]
+    fn subscription_condition(
+        #[case] event_type: EventType,
+        #[case] expected: Option<(&str, Value)>,
+    ) {
+        assert_eq!(subscription(event_type, "1337"), expected);
+    }
+
+    #[rstest]
+    fn ad_break_notification() {
+        let text = r#"{
+            "metadata": {
+                "message_id": "befa7b53-d79d-478f-86b9-120f112b044e",
+                "message_type": "notification",
+                "message_timestamp": "2022-11-16T10:11:12.464757833Z",
+                "subscription_type": "channel.ad_break.begin",
+                "subscription_version": "1"
+            },
+            "payload": {
+                "subscription": {},
+                "event": {
+                    "duration_seconds": "60",
+                    "started_at": "2019-11-16T10:11:12.634234626Z",
+                    "is_automatic": "false",
+                    "broadcaster_user_id": "1337"
+                }
+            }
+        }"#;
+        let Some(IntegrationEvent::Generic {
+            event_type,
+            payload,
+        }) = event_from_notification(text)
+        else {
+            panic!("Not a generic event");
+        };
+        assert_eq!(event_type, "channel.ad_break.begin");
+        assert_eq!(payload["duration_seconds"], "60");
+        assert_eq!(
+            message_id(text).as_deref(),
+            Some("befa7b53-d79d-478f-86b9-120f112b044e")
+        );
+    }
+
+    #[rstest]
+    fn keepalive_ignored() {
+        let text = r#"{"metadata": {"message_type": "session_keepalive"}, "payload": {}}"#;
+        assert_eq!(event_from_notification(text), None);
+    }
+}
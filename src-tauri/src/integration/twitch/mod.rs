@@ -1,6 +1,6 @@
 use item_information::{jd_channel_points, CustomChannelPointRewardInfo};
 use oauth::refresh_token;
-use permissions::get_eventsub_consolidated_scopes;
+use permissions::{get_eventsub_consolidated_scopes, get_helix_scopes};
 use std::{
     str::FromStr,
     sync::{
@@ -15,33 +15,45 @@ use tokio::sync::Mutex;
 use tracing::{debug, error, info, instrument};
 
 use crate::{
-    command::Runner,
+    command::{pause::PauseSettings, Runner},
     integration::websocket::{WebsocketController, WebsocketState},
 };
 
 use super::{
     status::{IntegrationError, IntegrationStatus},
-    APIConnectionConfig, Api, IntegrationChannels, IntegrationCommand, IntegrationControl,
-    IntegrationEvent, PlatformConnection, TokenError, Transmitter,
+    APIConnectionConfig, Api, CustomRewardEvent, IntegrationChannels, IntegrationCommand,
+    IntegrationControl, IntegrationEvent, PlatformConnection, TokenError, Transmitter,
 };
 use anyhow::{bail, Result};
 use config::Config;
 use reqwest::Client as ReqwestClient;
 use twitch_api::{
     eventsub::EventType,
-    helix::points::{CustomReward, GetCustomRewardRequest},
+    helix::points::{
+        CustomReward, CustomRewardRedemptionStatus, GetCustomRewardRequest,
+        UpdateRedemptionStatusBody, UpdateRedemptionStatusRequest,
+    },
     types::UserName,
     TwitchClient,
 };
 use twitch_oauth2::UserToken;
 use twitch_oauth2::{Scope, TwitchToken};
 
+pub mod chat;
+pub mod conduit;
+pub mod generic;
 pub mod item_information;
 pub mod oauth;
 pub mod permissions;
+pub mod polls;
+pub mod rewards;
+pub mod subscriptions;
+pub mod webhook;
 pub mod websocket;
 
+use conduit::{Conduit, ConduitSettings, ShardTransport};
 use twitch_types::UserId;
+use webhook::WebhookSettings;
 use websocket::WebsocketClient;
 
 pub struct TwitchApiConnection {
@@ -54,6 +66,10 @@ pub struct TwitchApiConnection {
     pub redirect_url: String,
     pub client: TwitchClient<'static, ReqwestClient>,
     pub websocket: Option<WebsocketClient>,
+    /// Set when using the webhook transport, see [`webhook`].
+    pub webhook: Option<WebhookSettings>,
+    /// Set when `auth.twitch.conduit` is enabled, see [`conduit`].
+    pub conduit: Option<Conduit>,
     pub websocket_joinhandle: Option<tauri::async_runtime::JoinHandle<()>>,
     pub session_id: Option<String>,
     pub scope: Vec<Scope>,
@@ -124,7 +140,12 @@ impl TwitchApiConnection {
                 },
             )
             .collect();
-        let scope = get_eventsub_consolidated_scopes(websocket_subs);
+        let mut scope = get_eventsub_consolidated_scopes(websocket_subs);
+        for helix_scope in get_helix_scopes() {
+            if !scope.contains(&helix_scope) {
+                scope.push(helix_scope);
+            }
+        }
         // get_all_required_scopes(scope);
         debug!("Scope: {:?}", scope);
         Self {
@@ -139,6 +160,8 @@ impl TwitchApiConnection {
             command_joinhandle: Default::default(),
             client: twitch_api::TwitchClient::new(),
             websocket: Default::default(),
+            webhook: None,
+            conduit: None,
             websocket_joinhandle: Default::default(),
             session_id: Default::default(),
             runner: Runner::new(),
@@ -156,7 +179,10 @@ impl TwitchApiConnection {
 
 impl TwitchApiConnection {
     pub async fn run(&mut self, config: Config, force: bool) {
-        self.new_websocket(config, force).await;
+        self.runner
+            .set_pause_settings(PauseSettings::from_config(&config));
+        super::currency::set_value_rates(super::currency::ValueRates::from_config(&config));
+        self.new_transport(config, force).await;
         match self.runner.run() {
             Ok(_) => {}
             Err(e) => {
@@ -165,8 +191,75 @@ impl TwitchApiConnection {
         }
     }
 
+    /// Starts the websocket, or the webhook if `auth.twitch.transport` is Webhook.
+    /// With `auth.twitch.conduit` enabled the conduit's shards are used instead.
+    pub async fn new_transport(&mut self, config: Config, force: bool) {
+        self.conduit = None;
+        match ConduitSettings::from_config(&config) {
+            Ok(Some(settings)) => match self.new_conduit(config.clone(), settings, force).await {
+                Ok(()) => return,
+                Err(e) => error!("Failed to use the conduit: {e}"),
+            },
+            Ok(None) => (),
+            Err(e) => error!("{e}"),
+        }
+        self.conduit = None;
+        match WebhookSettings::from_config(&config) {
+            Ok(Some(webhook)) => self.new_webhook(config, webhook).await,
+            Ok(None) => self.new_websocket(config, force).await,
+            Err(e) => {
+                error!("{e}, using the websocket instead");
+                self.new_websocket(config, force).await
+            }
+        }
+    }
+
+    /// Attaches the conduit, starts the transports of its shards and subscribes it.
+    pub async fn new_conduit(
+        &mut self,
+        config: Config,
+        settings: ConduitSettings,
+        force: bool,
+    ) -> Result<()> {
+        let webhook = if settings.has_shard(ShardTransport::Webhook) {
+            Some(WebhookSettings::read(&config)?)
+        } else {
+            None
+        };
+        let conduit = Conduit::attach(
+            self.client_id.clone(),
+            self.client_secret.clone(),
+            settings.clone(),
+            webhook.clone(),
+        )
+        .await?;
+        self.conduit = Some(conduit.clone());
+        if settings.has_shard(ShardTransport::Websocket) {
+            // The websocket assigns every shard once welcomed.
+            self.new_websocket(config, force).await;
+            self.webhook = webhook;
+        } else if let Some(webhook) = webhook {
+            self.new_webhook(config, webhook).await;
+            conduit.assign(None).await?;
+        }
+        let Some(websocket) = self.websocket.clone() else {
+            bail!("No transport started for the conduit");
+        };
+        let user_id = self.user_id().await.expect("Token is checked.");
+        tauri::async_runtime::spawn(async move {
+            for failure in conduit
+                .subscribe(&websocket.subscriptions, user_id.as_str())
+                .await
+            {
+                websocket.push_subscription_failure(failure);
+            }
+        });
+        Ok(())
+    }
+
     pub async fn new_websocket(&mut self, config: Config, _force: bool) {
         self.connecting = true;
+        self.webhook = None;
         debug!("new websocket");
         if let Some(joinhandle) = self.websocket_joinhandle.take() {
             joinhandle.abort();
@@ -181,10 +274,12 @@ impl TwitchApiConnection {
         let channel_points_enabled = subscriptions_string
             .iter()
             .any(|s| s.contains("channel_points_custom_reward"));
-        let subscriptions: Vec<EventType> = subscriptions_string
-            .iter()
-            .map(|s| EventType::from_str(s).unwrap())
-            .collect();
+        let subscriptions = subscriptions::without_duplicate_cheers(
+            subscriptions_string
+                .iter()
+                .map(|s| EventType::from_str(s).unwrap())
+                .collect(),
+        );
         info!("Websocket Subscriptions: {:?}", subscriptions);
         info!("Channel point rewards: {}", channel_points_enabled);
         if channel_points_enabled {
@@ -193,14 +288,16 @@ impl TwitchApiConnection {
             CustomChannelPointRewardInfo::list_valid_trigger(channel_point_custom_reward, true);
         }
 
-        self.websocket = Some(WebsocketClient::new(
+        let mut websocket = WebsocketClient::new(
             self.session_id.clone(),
             token,
             self.user_id().await.expect("Token is checked."),
             subscriptions,
             self.runner.tx(),
             self.websocker_controller.clone(),
-        ));
+        );
+        websocket.conduit = self.conduit.clone();
+        self.websocket = Some(websocket);
         let websocket = self.websocket.clone().unwrap();
         self.websocket_joinhandle = Some(tauri::async_runtime::spawn(async move {
             use websocket::WebsocketError::*;
@@ -348,7 +445,9 @@ impl TwitchApiConnection {
         if self.connecting {
             return Ok(IntegrationStatus::Connecting(api));
         };
-        if let Some(ws_state) = self.websocket_state().await {
+        if self.webhook.is_some() {
+            // No connection to check, Twitch posts to the callback.
+        } else if let Some(ws_state) = self.websocket_state().await {
             // if !ws_state.is_alive() {
             //     tracing::info!("{:?} state: {:?}", api, ws_state);
             // };
@@ -365,6 +464,15 @@ impl TwitchApiConnection {
         } else {
             return Ok(IntegrationStatus::Disconnected(api));
         }
+        if let Some(websocket) = &self.websocket {
+            let failures = websocket.subscription_failures();
+            if !failures.is_empty() {
+                return Ok(IntegrationStatus::Error {
+                    api,
+                    error: IntegrationError::Subscriptions(failures),
+                });
+            }
+        }
         let token: UserToken = match self.token().await {
             Some(t) => t,
             None => return Ok(IntegrationStatus::Disconnected(api)),
@@ -435,6 +543,31 @@ impl TwitchApiConnection {
             }
         }
     }
+
+    /// Marks the redemption as fulfilled, or cancels it refunding the viewer's points.
+    ///
+    /// Only works for rewards created by this application's client id.
+    pub async fn update_redemption_status(
+        &mut self,
+        redemption: &CustomRewardEvent,
+        status: CustomRewardRedemptionStatus,
+    ) -> Result<()> {
+        let token = match self.check_token().await {
+            Ok(t) => t,
+            Err(e) => bail!("Not authenticated: {:?}", e),
+        };
+        let request = UpdateRedemptionStatusRequest::new(
+            &token.user_id,
+            redemption.id.as_str(),
+            redemption.event_id.as_str(),
+        );
+        self.client
+            .helix
+            .req_patch(request, UpdateRedemptionStatusBody::status(status), &token)
+            .await?;
+        info!("Set redemption {} to {:?}", redemption.event_id, status);
+        Ok(())
+    }
 }
 
 impl Transmitter for TwitchApiConnection {
@@ -579,7 +712,7 @@ pub async fn refresh_twitch_websocket(
     let mut twitch = twitch_mutex.lock().await;
     let config = config.lock().await.clone();
     info!("Refreshing websocket");
-    twitch.new_websocket(config, true).await;
+    twitch.new_transport(config, true).await;
     info!("Websocket refreshed");
     Ok(())
 }
@@ -4,7 +4,7 @@ use anyhow::{bail, Context, Result};
 use cached::{stores::DiskCacheBuilder, DiskCache, IOCached};
 use http::{Response, StatusCode};
 use reqwest::Client as ReqwestClient;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufStream};
 use tokio::net::TcpListener;
 use tracing::{debug, error, info, instrument, warn};
@@ -60,7 +60,7 @@ impl From<UserToken> for SerializableUserToken {
     }
 }
 
-pub fn token_cache(key: String) -> DiskCache<String, SerializableUserToken> {
+pub fn token_cache<V: Serialize + DeserializeOwned>(key: String) -> DiskCache<String, V> {
     let config = sled::Config::new().flush_every_ms(None);
     let folder = if cfg!(dev) { "RCON2.0-dev" } else { "RCON2.0" };
     debug!("Cache folder: {}", folder);
@@ -90,7 +90,7 @@ pub async fn oauth(
     redirect_url: String,
     use_cache: bool,
 ) -> anyhow::Result<UserToken> {
-    let cache = token_cache("TWITCH_OAUTH".to_string());
+    let cache: DiskCache<String, SerializableUserToken> = token_cache("TWITCH_OAUTH".to_string());
     let cache_key = client_id.clone() + &oauth_scope_to_string(&scopes);
     let mut cached_token_option = match cache.cache_get(&cache_key) {
         Ok(value) => value,
@@ -207,7 +207,8 @@ pub async fn oauth(
     Ok(token)
 }
 
-async fn response_uri(port: u16) -> anyhow::Result<String> {
+/// Waits for the OAuth redirect on `localhost:port`, returns the url it was sent to.
+pub(crate) async fn response_uri(port: u16) -> anyhow::Result<String> {
     let host = "localhost";
     let listener_address = format!("{}:{}", host, port);
     let jh = tauri::async_runtime::spawn(async move {
@@ -28,7 +28,9 @@ pub fn get_eventsub_eventtype_scopes(event_type: EventType) -> Option<Vec<Scope>
         EventType::ChannelPointsCustomRewardAdd => Some(vec![ChannelReadRedemptions]),
         EventType::ChannelPointsCustomRewardUpdate => Some(vec![ChannelReadRedemptions]),
         EventType::ChannelPointsCustomRewardRemove => Some(vec![ChannelReadRedemptions]),
-        EventType::ChannelPointsCustomRewardRedemptionAdd => Some(vec![ChannelReadRedemptions]),
+        EventType::ChannelPointsCustomRewardRedemptionAdd => {
+            Some(vec![ChannelReadRedemptions, ChannelManageRedemptions])
+        }
         EventType::ChannelPointsCustomRewardRedemptionUpdate => Some(vec![ChannelReadRedemptions]),
         EventType::ChannelPollBegin => Some(vec![ChannelReadPolls]),
         EventType::ChannelPollProgress => Some(vec![ChannelReadPolls]),
@@ -154,6 +156,12 @@ pub fn get_eventsub_consolidated_scopes(subscriptions: Vec<EventType>) -> Vec<Sc
     scopes
 }
 
+/// Scopes for the Helix requests made on behalf of commands: managing rewards,
+/// updating redemptions and replying in chat.
+pub fn get_helix_scopes() -> Vec<Scope> {
+    vec![ChannelManageRedemptions, UserWriteChat, ChannelManagePolls]
+}
+
 #[cfg(test)]
 mod tests {
     use tracing::subscriber;
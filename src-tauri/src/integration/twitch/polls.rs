@@ -0,0 +1,111 @@
+//! Polls and predictions, ending as [`IntegrationEvent::PollEnd`](crate::integration::IntegrationEvent::PollEnd)
+//! so viewers can vote on what happens in game.
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use tauri::State;
+use tracing::{info, instrument};
+use twitch_api::helix::polls::{CreatePollBody, CreatePollRequest, NewPollChoice, Poll};
+
+use super::TwitchApiConnection;
+
+/// Twitch allows 2 to 5 choices.
+pub const POLL_CHOICES: std::ops::RangeInclusive<usize> = 2..=5;
+
+/// Twitch allows polls to run for 15 to 1800 seconds.
+pub const POLL_DURATION: std::ops::RangeInclusive<i64> = 15..=1800;
+
+/// The choice with the most votes, the first listed wins a tie.
+pub fn winning_choice<'a, I>(choices: I) -> Option<(String, u64)>
+where
+    I: IntoIterator<Item = (&'a str, u64)>,
+{
+    choices
+        .into_iter()
+        .fold(
+            None,
+            |winner: Option<(&str, u64)>, (title, votes)| match winner {
+                Some((_, most)) if most >= votes => winner,
+                _ => Some((title, votes)),
+            },
+        )
+        .map(|(title, votes)| (title.to_string(), votes))
+}
+
+impl TwitchApiConnection {
+    /// Starts a poll in the broadcaster's channel, duration in seconds.
+    pub async fn create_poll(
+        &mut self,
+        title: &str,
+        choices: &[String],
+        duration: i64,
+    ) -> Result<Poll> {
+        if !POLL_CHOICES.contains(&choices.len()) {
+            bail!(
+                "A poll needs {} to {} choices, recieved {}.",
+                POLL_CHOICES.start(),
+                POLL_CHOICES.end(),
+                choices.len()
+            );
+        }
+        if !POLL_DURATION.contains(&duration) {
+            bail!(
+                "A poll lasts {} to {} seconds, recieved {}.",
+                POLL_DURATION.start(),
+                POLL_DURATION.end(),
+                duration
+            );
+        }
+        let token = match self.check_token().await {
+            Ok(t) => t,
+            Err(e) => bail!("Not authenticated: {:?}", e),
+        };
+        let choices: Vec<NewPollChoice> = choices
+            .iter()
+            .map(|c| NewPollChoice::new(c.as_str()))
+            .collect();
+        let body = CreatePollBody::new(&token.user_id, title, duration, choices);
+        let poll = self
+            .client
+            .helix
+            .req_post(CreatePollRequest::new(), body, &token)
+            .await?
+            .data;
+        info!("Started poll \"{}\": {}", poll.title, poll.id);
+        Ok(poll)
+    }
+}
+
+/// Starts a poll, its winner is matched by the `PollResult` trigger when it ends.
+#[tauri::command]
+#[instrument(skip(twitch_mutex))]
+pub async fn create_poll(
+    title: String,
+    choices: Vec<String>,
+    duration: i64,
+    twitch_mutex: State<'_, Arc<futures::lock::Mutex<TwitchApiConnection>>>,
+) -> Result<String, String> {
+    let mut twitch = twitch_mutex.lock().await;
+    twitch
+        .create_poll(&title, &choices, duration)
+        .await
+        .map(|poll| poll.id.take())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(vec![("Biters", 3), ("Meteor", 7), ("Nothing", 2)], Some(("Meteor", 7)))]
+    #[case(vec![("Biters", 4), ("Meteor", 4)], Some(("Biters", 4)))]
+    #[case(vec![], None)]
+    fn winner(#[case] choices: Vec<(&str, u64)>, #[case] expected: Option<(&str, u64)>) {
+        assert_eq!(
+            winning_choice(choices),
+            expected.map(|(t, v)| (t.to_string(), v))
+        );
+    }
+}
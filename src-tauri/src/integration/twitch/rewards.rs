@@ -0,0 +1,237 @@
+//! Creating, updating and deleting custom channel point rewards through Helix.
+//!
+//! Twitch only allows changing rewards created with the same client id.
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use tauri::State;
+use tracing::{info, instrument, warn};
+use twitch_api::helix::points::{
+    update_custom_reward::UpdateCustomReward, CreateCustomRewardBody, CreateCustomRewardRequest,
+    CustomReward, DeleteCustomRewardRequest, UpdateCustomRewardBody, UpdateCustomRewardRequest,
+};
+use twitch_oauth2::UserToken;
+
+use super::{item_information::CustomChannelPointRewardInfo, TwitchApiConnection};
+use crate::{
+    command::{settings::ScriptSettings, Command, Reward},
+    servers::GameServer,
+};
+
+impl TwitchApiConnection {
+    async fn reward_token(&mut self) -> Result<UserToken> {
+        match self.check_token().await {
+            Ok(t) => Ok(t),
+            Err(e) => bail!("Not authenticated: {:?}", e),
+        }
+    }
+
+    pub async fn create_custom_reward(&mut self, reward: &Reward) -> Result<CustomReward> {
+        let token = self.reward_token().await?;
+        let request = CreateCustomRewardRequest::broadcaster_id(&token.user_id);
+        let mut body = CreateCustomRewardBody::new(reward.title.as_str(), reward.cost);
+        body.prompt = reward.prompt.as_deref().map(Into::into);
+        body.is_user_input_required = Some(reward.is_user_input_required);
+        body.is_global_cooldown_enabled = Some(reward.global_cooldown.is_some());
+        body.global_cooldown_seconds = reward.global_cooldown;
+        let created = self
+            .client
+            .helix
+            .req_post(request, body, &token)
+            .await?
+            .data;
+        info!(
+            "Created channel point reward {}: {}",
+            created.title, created.id
+        );
+        Ok(created)
+    }
+
+    pub async fn update_custom_reward(
+        &mut self,
+        id: &str,
+        body: UpdateCustomRewardBody<'_>,
+    ) -> Result<CustomReward> {
+        let token = self.reward_token().await?;
+        let request = UpdateCustomRewardRequest::new(&token.user_id, id);
+        match self
+            .client
+            .helix
+            .req_patch(request, body, &token)
+            .await?
+            .data
+        {
+            UpdateCustomReward::Success(reward) => Ok(reward),
+            other => bail!("Unexpected response updating reward {id}: {other:?}"),
+        }
+    }
+
+    pub async fn delete_custom_reward(&mut self, id: &str) -> Result<()> {
+        let token = self.reward_token().await?;
+        let request = DeleteCustomRewardRequest::new(&token.user_id, id);
+        self.client.helix.req_delete(request, &token).await?;
+        info!("Deleted channel point reward {id}");
+        Ok(())
+    }
+
+    pub async fn set_custom_reward_paused(&mut self, id: &str, paused: bool) -> Result<()> {
+        let mut body = UpdateCustomRewardBody::default();
+        body.is_paused = Some(paused);
+        self.update_custom_reward(id, body).await?;
+        Ok(())
+    }
+
+    pub async fn set_custom_reward_enabled(&mut self, id: &str, enabled: bool) -> Result<()> {
+        let mut body = UpdateCustomRewardBody::default();
+        body.is_enabled = Some(enabled);
+        self.update_custom_reward(id, body).await?;
+        Ok(())
+    }
+
+    /// Updates the reward on Twitch, creating it if it has no id.
+    pub async fn sync_reward(&mut self, reward: &Reward) -> Result<CustomReward> {
+        let Some(id) = &reward.id else {
+            return self.create_custom_reward(reward).await;
+        };
+        let mut body = UpdateCustomRewardBody::default();
+        body.title = Some(reward.title.as_str().into());
+        body.cost = Some(reward.cost);
+        body.prompt = reward.prompt.as_deref().map(Into::into);
+        body.is_user_input_required = Some(reward.is_user_input_required);
+        body.is_global_cooldown_enabled = Some(reward.global_cooldown.is_some());
+        body.global_cooldown_seconds = reward.global_cooldown;
+        self.update_custom_reward(id, body).await
+    }
+
+    /// Pauses, or resumes, the rewards of every command sending to the server.
+    pub async fn set_server_rewards_paused(&mut self, server: &GameServer, paused: bool) {
+        for command in ScriptSettings::get_commands() {
+            let Some(id) = command.reward.as_ref().and_then(|r| r.id.as_ref()) else {
+                continue;
+            };
+            if !command
+                .server_triggers
+                .iter()
+                .any(|st| &st.server == server)
+            {
+                continue;
+            }
+            match self.set_custom_reward_paused(id, paused).await {
+                Ok(()) => info!(
+                    "{} \"{}\" reward, \"{}\" server is {}",
+                    if paused { "Paused" } else { "Resumed" },
+                    command.name,
+                    server.name,
+                    if paused { "disconnected" } else { "connected" }
+                ),
+                Err(e) => warn!("Failed to update \"{}\" reward: {:?}", command.name, e),
+            }
+        }
+    }
+}
+
+fn command(name: &str) -> Result<Command, String> {
+    Command::get(name).ok_or(format!("Command \"{name}\" not found."))
+}
+
+/// Creates or updates the command's reward on Twitch and saves its id.
+#[tauri::command]
+#[instrument(skip(twitch_mutex))]
+pub async fn sync_command_reward(
+    command_name: String,
+    twitch_mutex: State<'_, Arc<futures::lock::Mutex<TwitchApiConnection>>>,
+) -> Result<Command, String> {
+    let mut command = command(&command_name)?;
+    let Some(reward) = command.reward.clone() else {
+        return Err(format!("\"{command_name}\" has no reward."));
+    };
+    let mut twitch = twitch_mutex.lock().await;
+    let synced = twitch
+        .sync_reward(&reward)
+        .await
+        .map_err(|e| e.to_string())?;
+    command.set_reward_id(synced.id.as_str());
+    command.update_config();
+    command.add_to_commands();
+    Ok(command)
+}
+
+/// Sets or removes the command's reward, without syncing it.
+#[tauri::command]
+#[instrument]
+pub fn set_command_reward(command_name: String, reward: Option<Reward>) -> Result<Command, String> {
+    let command = command(&command_name)?;
+    let reward = reward.map(|r| r.replacing(command.reward.as_ref()));
+    let command = command.with_reward(reward);
+    command.update_config();
+    command.add_to_commands();
+    Ok(command)
+}
+
+#[tauri::command]
+#[instrument(skip(twitch_mutex))]
+pub async fn create_channel_point_reward(
+    reward: Reward,
+    twitch_mutex: State<'_, Arc<futures::lock::Mutex<TwitchApiConnection>>>,
+) -> Result<CustomChannelPointRewardInfo, String> {
+    let mut twitch = twitch_mutex.lock().await;
+    twitch
+        .create_custom_reward(&reward)
+        .await
+        .map(CustomChannelPointRewardInfo::from)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[instrument(skip(twitch_mutex))]
+pub async fn set_channel_point_reward_paused(
+    id: String,
+    paused: bool,
+    twitch_mutex: State<'_, Arc<futures::lock::Mutex<TwitchApiConnection>>>,
+) -> Result<(), String> {
+    let mut twitch = twitch_mutex.lock().await;
+    twitch
+        .set_custom_reward_paused(&id, paused)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[instrument(skip(twitch_mutex))]
+pub async fn set_channel_point_reward_enabled(
+    id: String,
+    enabled: bool,
+    twitch_mutex: State<'_, Arc<futures::lock::Mutex<TwitchApiConnection>>>,
+) -> Result<(), String> {
+    let mut twitch = twitch_mutex.lock().await;
+    twitch
+        .set_custom_reward_enabled(&id, enabled)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Deletes the reward on Twitch and forgets its id on the owning command.
+#[tauri::command]
+#[instrument(skip(twitch_mutex))]
+pub async fn delete_channel_point_reward(
+    id: String,
+    twitch_mutex: State<'_, Arc<futures::lock::Mutex<TwitchApiConnection>>>,
+) -> Result<(), String> {
+    let mut twitch = twitch_mutex.lock().await;
+    twitch
+        .delete_custom_reward(&id)
+        .await
+        .map_err(|e| e.to_string())?;
+    for mut command in ScriptSettings::get_commands() {
+        if let Some(reward) = command
+            .reward
+            .as_mut()
+            .filter(|r| r.id.as_deref() == Some(id.as_str()))
+        {
+            reward.id = None;
+            command.update_config();
+            command.add_to_commands();
+        }
+    }
+    Ok(())
+}
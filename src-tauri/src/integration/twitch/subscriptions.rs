@@ -0,0 +1,136 @@
+//! Comparing the EventSub subscriptions Twitch has with the configured `websocket_subscription`.
+use tracing::warn;
+use twitch_api::eventsub::{EventSubSubscription, EventType, Status, TransportResponse};
+
+/// The parts of an [`EventSubSubscription`] needed to reconcile it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionInfo {
+    pub id: String,
+    pub event_type: EventType,
+    pub enabled: bool,
+    /// Websocket session the subscription is bound to, [`None`] for other transports.
+    pub session_id: Option<String>,
+}
+
+impl From<&EventSubSubscription> for SubscriptionInfo {
+    fn from(subscription: &EventSubSubscription) -> Self {
+        let session_id = match &subscription.transport {
+            TransportResponse::Websocket(websocket) => Some(websocket.session_id.clone()),
+            _ => None,
+        };
+        Self {
+            id: subscription.id.to_string(),
+            event_type: subscription.type_,
+            enabled: subscription.status == Status::Enabled,
+            session_id,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Reconciliation {
+    /// Ids of subscriptions left behind by old websocket sessions, these count
+    /// towards Twitch's limits until deleted.
+    pub stale: Vec<String>,
+    /// Configured subscriptions that are not enabled on the current session.
+    pub missing: Vec<EventType>,
+}
+
+/// Subscriptions of other sessions are only stale once disabled, an enabled one
+/// belongs to another running client.
+pub fn reconcile(
+    existing: &[SubscriptionInfo],
+    session_id: &str,
+    configured: &[EventType],
+) -> Reconciliation {
+    let stale = existing
+        .iter()
+        .filter(|s| !s.enabled)
+        .filter(|s| s.session_id.as_deref().is_some_and(|id| id != session_id))
+        .map(|s| s.id.clone())
+        .collect();
+    let mut missing = Vec::new();
+    for event_type in configured {
+        let active = existing.iter().any(|s| {
+            s.enabled && &s.event_type == event_type && s.session_id.as_deref() == Some(session_id)
+        });
+        if !active && !missing.contains(event_type) {
+            missing.push(*event_type);
+        }
+    }
+    Reconciliation { stale, missing }
+}
+
+/// `channel.bits.use` includes cheers, so `channel.cheer` is left out when both
+/// are configured, otherwise every cheer would trigger **Bits** twice.
+pub fn without_duplicate_cheers(mut configured: Vec<EventType>) -> Vec<EventType> {
+    if configured.contains(&EventType::ChannelBitsUse)
+        && configured.contains(&EventType::ChannelCheer)
+    {
+        warn!("Not subscribing to channel.cheer, channel.bits.use already includes cheers.");
+        configured.retain(|event_type| event_type != &EventType::ChannelCheer);
+    }
+    configured
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn subscription(
+        id: &str,
+        event_type: EventType,
+        enabled: bool,
+        session_id: &str,
+    ) -> SubscriptionInfo {
+        SubscriptionInfo {
+            id: id.to_string(),
+            event_type,
+            enabled,
+            session_id: Some(session_id.to_string()),
+        }
+    }
+
+    #[rstest]
+    fn stale_and_missing() {
+        let existing = vec![
+            subscription("1", EventType::ChannelChatMessage, true, "current"),
+            subscription("2", EventType::ChannelChatMessage, false, "old"),
+            subscription("3", EventType::ChannelRaid, true, "other-client"),
+            subscription("4", EventType::ChannelFollow, false, "current"),
+        ];
+        let configured = [
+            EventType::ChannelChatMessage,
+            EventType::ChannelRaid,
+            EventType::ChannelFollow,
+        ];
+        assert_eq!(
+            reconcile(&existing, "current", &configured),
+            Reconciliation {
+                stale: vec!["2".to_string()],
+                missing: vec![EventType::ChannelRaid, EventType::ChannelFollow],
+            }
+        );
+    }
+
+    #[rstest]
+    fn reconciled() {
+        let existing = vec![subscription("1", EventType::ChannelCheer, true, "current")];
+        assert_eq!(
+            reconcile(&existing, "current", &[EventType::ChannelCheer]),
+            Reconciliation::default()
+        );
+    }
+
+    #[rstest]
+    #[case(
+        vec![EventType::ChannelCheer, EventType::ChannelBitsUse],
+        vec![EventType::ChannelBitsUse]
+    )]
+    #[case(vec![EventType::ChannelCheer], vec![EventType::ChannelCheer])]
+    #[case(vec![EventType::ChannelBitsUse], vec![EventType::ChannelBitsUse])]
+    fn duplicate_cheers(#[case] configured: Vec<EventType>, #[case] expected: Vec<EventType>) {
+        assert_eq!(without_duplicate_cheers(configured), expected);
+    }
+}
@@ -0,0 +1,417 @@
+//! EventSub webhook transport, an alternative to the websocket for headless
+//! servers. Twitch posts notifications to `auth.twitch.webhook_callback`, which
+//! has to forward to [`WEBHOOK_PATH`] on the localhost server. Notifications are
+//! handed to the same handlers as the websocket's, see
+//! [`WebsocketClient::handle_notification`].
+use std::{str::FromStr, time::Duration};
+
+use anyhow::{bail, Result};
+use config::Config;
+use crypto_hmac::{Hmac, Mac};
+use http::{Request, Response, StatusCode};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use tracing::{debug, error, info, warn};
+use twitch_api::eventsub::{Event, EventType};
+use twitch_oauth2::{AppAccessToken, ClientId, ClientSecret};
+
+use super::{
+    generic::{create_subscription, event_from_webhook},
+    subscriptions::without_duplicate_cheers,
+    websocket::WebsocketClient,
+    TwitchApiConnection,
+};
+use crate::{
+    integration::dedup,
+    localhost::{text_response, TwitchState},
+};
+
+/// Path on the localhost server receiving the notifications.
+pub const WEBHOOK_PATH: &str = "/webhook/twitch";
+
+/// Twitch recommends rejecting notifications older than 10 minutes as replays.
+pub const MAX_MESSAGE_AGE: Duration = Duration::from_secs(10 * 60);
+
+/// Twitch allows secrets of 10 to 100 characters.
+pub const SECRET_LENGTH: std::ops::RangeInclusive<usize> = 10..=100;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookSettings {
+    /// Public HTTPS url Twitch posts to.
+    pub callback: url::Url,
+    /// Shared secret for the `Twitch-Eventsub-Message-Signature`.
+    pub secret: String,
+}
+
+impl WebhookSettings {
+    /// Reads `auth.twitch.transport`, `auth.twitch.webhook_callback` and
+    /// `auth.twitch.webhook_secret`. [`None`] when using the websocket.
+    pub fn from_config(config: &Config) -> Result<Option<Self>> {
+        let transport = config
+            .get_string("auth.twitch.transport")
+            .unwrap_or_default();
+        match transport.to_lowercase().as_str() {
+            "" | "websocket" => Ok(None),
+            "webhook" => Self::read(config).map(Some),
+            t => bail!("Invalid twitch transport: {t}, expected Websocket or Webhook"),
+        }
+    }
+
+    /// Reads `auth.twitch.webhook_callback` and `auth.twitch.webhook_secret`
+    /// whichever the transport.
+    pub fn read(config: &Config) -> Result<Self> {
+        let callback = config
+            .get_string("auth.twitch.webhook_callback")
+            .unwrap_or_default();
+        let callback = match url::Url::parse(&callback) {
+            Ok(url) => url,
+            Err(e) => bail!("Invalid auth.twitch.webhook_callback \"{callback}\": {e}"),
+        };
+        let secret = config
+            .get_string("auth.twitch.webhook_secret")
+            .unwrap_or_default();
+        if !SECRET_LENGTH.contains(&secret.len()) {
+            bail!(
+                "auth.twitch.webhook_secret needs {} to {} characters, recieved {}.",
+                SECRET_LENGTH.start(),
+                SECRET_LENGTH.end(),
+                secret.len()
+            );
+        }
+        Ok(Self { callback, secret })
+    }
+}
+
+/// A verified request from Twitch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebhookMessage {
+    /// Sent when the subscription is created, answered with the challenge.
+    Verification {
+        challenge: String,
+    },
+    /// Twitch stopped sending the subscription, e.g. the user revoked access.
+    Revocation {
+        event_type: String,
+        status: String,
+    },
+    Notification {
+        message_id: String,
+    },
+}
+
+/// Whether the `Twitch-Eventsub-Message-Signature` is the HMAC-SHA256 of the id,
+/// timestamp and body.
+pub fn verify_signature(
+    secret: &[u8],
+    message_id: &str,
+    timestamp: &str,
+    body: &[u8],
+    signature: &str,
+) -> bool {
+    let Some(signature) = signature.strip_prefix("sha256=").and_then(hex_decode) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret) else {
+        return false;
+    };
+    mac.update(message_id.as_bytes());
+    mac.update(timestamp.as_bytes());
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// Decodes a hex signature, [`None`] if it isn't hex.
+pub fn hex_decode(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Verifies the signature and age of the request, the error is the status to
+/// respond with.
+pub fn check_request(
+    request: &Request<Vec<u8>>,
+    secret: &str,
+    now: OffsetDateTime,
+) -> Result<WebhookMessage, StatusCode> {
+    let header = |name: &str| {
+        request
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+    };
+    let message_id = header("Twitch-Eventsub-Message-Id");
+    let timestamp = header("Twitch-Eventsub-Message-Timestamp");
+    let signature = header("Twitch-Eventsub-Message-Signature");
+    if !verify_signature(
+        secret.as_bytes(),
+        message_id,
+        timestamp,
+        request.body(),
+        signature,
+    ) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    match OffsetDateTime::parse(timestamp, &Rfc3339) {
+        Ok(sent) if now - sent <= MAX_MESSAGE_AGE => (),
+        _ => return Err(StatusCode::FORBIDDEN),
+    }
+    let body: Value =
+        serde_json::from_slice(request.body()).map_err(|_| StatusCode::BAD_REQUEST)?;
+    match header("Twitch-Eventsub-Message-Type") {
+        "webhook_callback_verification" => match body["challenge"].as_str() {
+            Some(challenge) => Ok(WebhookMessage::Verification {
+                challenge: challenge.to_string(),
+            }),
+            None => Err(StatusCode::BAD_REQUEST),
+        },
+        "revocation" => Ok(WebhookMessage::Revocation {
+            event_type: body["subscription"]["type"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            status: body["subscription"]["status"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+        }),
+        "notification" => Ok(WebhookMessage::Notification {
+            message_id: message_id.to_string(),
+        }),
+        _ => Err(StatusCode::BAD_REQUEST),
+    }
+}
+
+/// Handles a request to [`WEBHOOK_PATH`].
+pub async fn route(request: Request<Vec<u8>>, twitch: TwitchState) -> Response<Vec<u8>> {
+    let mut twitch = twitch.lock().await;
+    let (Some(webhook), Some(websocket)) = (twitch.webhook.clone(), twitch.websocket.as_mut())
+    else {
+        return text_response(StatusCode::NOT_FOUND, "Not Found");
+    };
+    let message = match check_request(&request, &webhook.secret, OffsetDateTime::now_utc()) {
+        Ok(message) => message,
+        Err(status) => {
+            warn!("Rejected twitch webhook request: {status}");
+            return text_response(status, status.to_string());
+        }
+    };
+    match message {
+        WebhookMessage::Verification { challenge } => {
+            info!("Twitch webhook subscription verified");
+            text_response(StatusCode::OK, challenge)
+        }
+        WebhookMessage::Revocation { event_type, status } => {
+            error!("Twitch revoked the {event_type} subscription: {status}");
+            websocket.push_subscription_failure(format!("{event_type}: {status}"));
+            no_content()
+        }
+        WebhookMessage::Notification { message_id } => {
+            let id = format!("message:{message_id}");
+            if dedup::is_seen(&id) {
+                return no_content();
+            }
+            let body = request.body().clone();
+            match Event::parse_http(&request) {
+                Ok(event) => {
+                    websocket
+                        .handle_notification(event, || event_from_webhook(&body))
+                        .await
+                }
+                // Event types newer than twitch_api.
+                Err(e) => match event_from_webhook(&body) {
+                    Some(event) => {
+                        let _ = websocket.event_tx.send(event).await;
+                    }
+                    None => error!("Failed to parse twitch webhook notification: {e}"),
+                },
+            }
+            // Twitch retries notifications that were not acknowledged.
+            if websocket.event_tx.is_closed() {
+                return text_response(StatusCode::SERVICE_UNAVAILABLE, "Not running");
+            }
+            dedup::mark_seen(&id);
+            no_content()
+        }
+    }
+}
+
+fn no_content() -> Response<Vec<u8>> {
+    Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Vec::new())
+        .unwrap()
+}
+
+impl TwitchApiConnection {
+    /// Uses the webhook transport instead of the websocket. Subscriptions are
+    /// created with an app access token as Twitch requires for webhooks.
+    pub async fn new_webhook(&mut self, config: Config, webhook: WebhookSettings) {
+        self.connecting = true;
+        debug!("new webhook");
+        if let Some(joinhandle) = self.websocket_joinhandle.take() {
+            joinhandle.abort();
+        }
+        let token = self.check_token().await.unwrap();
+        let subscriptions = without_duplicate_cheers(
+            config
+                .get::<Vec<String>>("auth.twitch.websocket_subscription")
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|s| EventType::from_str(s).ok())
+                .collect(),
+        );
+        info!("Webhook Subscriptions: {:?}", subscriptions);
+        let user_id = self.user_id().await.expect("Token is checked.");
+        // Only used for its handlers, it is never connected.
+        let websocket = WebsocketClient::new(
+            None,
+            token,
+            user_id.clone(),
+            subscriptions.clone(),
+            self.runner.tx(),
+            self.websocker_controller.clone(),
+        );
+        self.websocket = Some(websocket.clone());
+        self.webhook = Some(webhook.clone());
+        if self.conduit.is_some() {
+            // The conduit holds the subscriptions.
+            self.connecting = false;
+            return;
+        }
+        let client_id = ClientId::new(self.client_id.clone());
+        let client_secret = ClientSecret::new(self.client_secret.clone());
+        self.websocket_joinhandle = Some(tauri::async_runtime::spawn(async move {
+            let client = reqwest::Client::new();
+            let app_token = match AppAccessToken::get_app_access_token(
+                &client,
+                client_id,
+                client_secret,
+                vec![],
+            )
+            .await
+            {
+                Ok(token) => token,
+                Err(e) => {
+                    error!("Failed to get an app access token: {e}");
+                    websocket.push_subscription_failure(format!("App access token: {e}"));
+                    return;
+                }
+            };
+            let transport = json!({
+                "method": "webhook",
+                "callback": webhook.callback.as_str(),
+                "secret": webhook.secret,
+            });
+            for subscription in subscriptions {
+                match create_subscription(
+                    &client,
+                    &app_token,
+                    subscription,
+                    user_id.as_str(),
+                    transport.clone(),
+                )
+                .await
+                {
+                    Ok(()) => info!("Subscribed to {} by webhook", subscription),
+                    Err(e) => {
+                        error!("Failed to subscribe to {} by webhook: {}", subscription, e);
+                        websocket.push_subscription_failure(subscription.to_string());
+                    }
+                }
+            }
+        }));
+        self.connecting = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const SECRET: &str = "s3cRe7s3cRe7";
+    const MESSAGE_ID: &str = "e76c6bd4-55c9-4987-8304-da1588d8988b";
+    const TIMESTAMP: &str = "2019-11-16T10:11:12.634234626Z";
+    const BODY: &str = r#"{"challenge":"pogchamp-kappa-360noscope-vohiyo","subscription":{"type":"channel.follow"}}"#;
+    const SIGNATURE: &str =
+        "sha256=e4175b5e1ea08bf741f8d61529088117694aaf4fddb132fa6b0159a71602e239";
+
+    fn request(message_type: &str, signature: &str) -> Request<Vec<u8>> {
+        Request::builder()
+            .method("POST")
+            .uri(WEBHOOK_PATH)
+            .header("Twitch-Eventsub-Message-Id", MESSAGE_ID)
+            .header("Twitch-Eventsub-Message-Timestamp", TIMESTAMP)
+            .header("Twitch-Eventsub-Message-Signature", signature)
+            .header("Twitch-Eventsub-Message-Type", message_type)
+            .body(BODY.as_bytes().to_vec())
+            .unwrap()
+    }
+
+    fn sent_at() -> OffsetDateTime {
+        OffsetDateTime::parse(TIMESTAMP, &Rfc3339).unwrap()
+    }
+
+    #[rstest]
+    #[case(SECRET, SIGNATURE, true)]
+    #[case("wrong-secret", SIGNATURE, false)]
+    #[case(SECRET, "sha256=00", false)]
+    #[case(
+        SECRET,
+        "e4175b5e1ea08bf741f8d61529088117694aaf4fddb132fa6b0159a71602e239",
+        false
+    )]
+    fn signature_verified(#[case] secret: &str, #[case] signature: &str, #[case] expected: bool) {
+        assert_eq!(
+            verify_signature(
+                secret.as_bytes(),
+                MESSAGE_ID,
+                TIMESTAMP,
+                BODY.as_bytes(),
+                signature
+            ),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn challenge_answered() {
+        assert_eq!(
+            check_request(
+                &request("webhook_callback_verification", SIGNATURE),
+                SECRET,
+                sent_at()
+            ),
+            Ok(WebhookMessage::Verification {
+                challenge: "pogchamp-kappa-360noscope-vohiyo".to_string()
+            })
+        );
+    }
+
+    #[rstest]
+    #[case(request("notification", "sha256=00"), sent_at())]
+    #[case(request("notification", SIGNATURE), sent_at() + Duration::from_secs(11 * 60))]
+    fn rejected(#[case] request: Request<Vec<u8>>, #[case] now: OffsetDateTime) {
+        assert_eq!(
+            check_request(&request, SECRET, now),
+            Err(StatusCode::FORBIDDEN)
+        );
+    }
+
+    #[rstest]
+    fn notification_accepted() {
+        assert_eq!(
+            check_request(&request("notification", SIGNATURE), SECRET, sent_at()),
+            Ok(WebhookMessage::Notification {
+                message_id: MESSAGE_ID.to_string()
+            })
+        );
+    }
+}
@@ -1,8 +1,15 @@
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use crate::integration::event::{normalise_tier, CustomRewardVariant};
+use super::chat;
+use super::conduit::Conduit;
+use super::generic::{event_from_notification, message_id};
+use super::subscriptions::{reconcile, SubscriptionInfo};
+use crate::integration::event::{
+    normalise_tier, CustomRewardVariant, HypeTrainState, HYPE_TRAIN_LEVELS,
+};
 use crate::integration::websocket::{WebsocketCommand, WebsocketController, WebsocketState};
-use crate::integration::{self, CustomRewardEvent, IntegrationEvent};
+use crate::integration::{self, dedup, Badge, Chatter, CustomRewardEvent, IntegrationEvent};
 use anyhow::Result;
 use futures::stream::FusedStream;
 use futures::TryStreamExt;
@@ -11,7 +18,6 @@ use tokio::sync::mpsc::Sender;
 use tokio_tungstenite::tungstenite;
 use tracing::{debug, error, info, warn};
 use tracing::{trace, Instrument};
-use twitch_api::eventsub::EventType;
 use twitch_api::eventsub::channel::goal::progress;
 use twitch_api::{
     client::ClientDefault,
@@ -25,6 +31,12 @@ use twitch_api::{
 };
 use twitch_oauth2::{TwitchToken, UserToken};
 
+/// Time Twitch allows for connecting to the `reconnect_url` of a `session_reconnect`.
+pub const RECONNECT_GRACE: Duration = Duration::from_secs(30);
+
+type WebsocketStream =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
 #[derive(Debug, PartialEq, Eq, thiserror::Error, miette::Diagnostic)]
 pub enum WebsocketError {
     #[error("Token elapsed")]
@@ -58,12 +70,18 @@ pub struct WebsocketClient {
     pub client: HelixClient<'static, reqwest::Client>,
     pub user_id: types::UserId,
     pub connect_url: url::Url,
+    /// Url from the last `session_reconnect` message, see [`WebsocketClient::reconnect`].
+    pub reconnect_url: Option<url::Url>,
     pub subscriptions: Vec<eventsub::EventType>,
     pub subscribed: Vec<eventsub::EventType>,
     pub event_tx: Sender<integration::IntegrationEvent>,
     pub controller: WebsocketController,
     state: WebsocketState,
     keep_alive_seconds: Duration,
+    /// Configured subscriptions which are not enabled, shared with every clone.
+    subscription_failures: Arc<Mutex<Vec<String>>>,
+    /// When set the session is a shard of the conduit, which holds the subscriptions.
+    pub conduit: Option<Conduit>,
 }
 
 impl std::fmt::Debug for WebsocketClient {
@@ -101,34 +119,78 @@ impl WebsocketClient {
             client,
             user_id,
             connect_url: twitch_api::TWITCH_EVENTSUB_WEBSOCKET_URL.clone(),
+            reconnect_url: None,
             subscriptions,
             subscribed: Vec::new(),
             keep_alive_seconds: Duration::from_secs(10),
             event_tx,
             state: WebsocketState::Down,
             controller,
+            subscription_failures: Arc::new(Mutex::new(Vec::new())),
+            conduit: None,
         }
     }
 
     /// Connect to the websocket and return the stream
-    pub async fn connect(
-        &mut self,
-    ) -> Result<
-        tokio_tungstenite::WebSocketStream<
-            tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
-        >,
-        WebsocketError,
-    > {
+    pub async fn connect(&mut self) -> Result<WebsocketStream, WebsocketError> {
         tracing::debug!("Connecting to Twitch Websocket");
         self.subscribed.clear();
+        self.reconnect_url = None;
+        let url = self.connect_url.clone();
+        self.connect_to(&url).await
+    }
+
+    /// Follows a `session_reconnect` to its url, Twitch moves the subscriptions to the
+    /// new session so they are kept. Falls back to [`WebsocketClient::connect`] if the
+    /// welcome message isn't received within [`RECONNECT_GRACE`].
+    pub async fn reconnect(&mut self) -> Result<WebsocketStream, WebsocketError> {
+        let Some(url) = self.reconnect_url.take() else {
+            return self.connect().await;
+        };
+        tracing::debug!("Reconnecting to Twitch Websocket: {url}");
+        match async_std::future::timeout(RECONNECT_GRACE, self.connect_welcomed(&url)).await {
+            Ok(Ok(socket)) => {
+                info!("Reconnected to Twitch's Websocket");
+                Ok(socket)
+            }
+            Ok(Err(e)) => {
+                warn!("Failed to reconnect, connecting again: {e}");
+                self.connect().await
+            }
+            Err(_) => {
+                warn!(
+                    "Reconnect timed out after {:?}, connecting again",
+                    RECONNECT_GRACE
+                );
+                self.connect().await
+            }
+        }
+    }
+
+    /// Connects and processes the welcome message, the old connection must stay open until then.
+    async fn connect_welcomed(
+        &mut self,
+        url: &url::Url,
+    ) -> Result<WebsocketStream, WebsocketError> {
+        let mut socket = self.connect_to(url).await?;
+        match futures::StreamExt::next(&mut socket).await {
+            Some(Ok(msg)) => self.process_message(msg).await?,
+            Some(Err(e)) => return Err(WebsocketError::FailedToConnect(e.to_string())),
+            None => return Err(WebsocketError::Terminated),
+        }
+        match self.session_id {
+            Some(_) => Ok(socket),
+            None => Err(WebsocketError::FailedToConnect("No welcome message".into())),
+        }
+    }
+
+    async fn connect_to(&mut self, url: &url::Url) -> Result<WebsocketStream, WebsocketError> {
         let config = tungstenite::protocol::WebSocketConfig::default()
             .max_message_size(Some(64 << 20))
             .max_frame_size(Some(16 << 20))
             .accept_unmasked_frames(false);
 
-        match tokio_tungstenite::connect_async_with_config(&self.connect_url, Some(config), false)
-            .await
-        {
+        match tokio_tungstenite::connect_async_with_config(url, Some(config), false).await {
             Ok((socket, _response)) => {
                 self.state = WebsocketState::Alive;
                 Ok(socket)
@@ -260,10 +322,14 @@ impl WebsocketClient {
                                 Ok(m) => m,
                                 Err(e) => {
                                     if e == WebsocketError::Reconnect {
-                                        websocket_stream = WebsocketError::FailedToRun("When reconnecting to websocket".into()).map_err(self
-                                        .connect()
-                                        .await
-                                        ).unwrap(); // BUG: Not handling error.
+                                        websocket_stream = match self.reconnect().await {
+                                            Ok(s) => s,
+                                            Err(e) => {
+                                                self.state = WebsocketState::Down;
+                                                self.subscribed.clear();
+                                                return Err(e);
+                                            }
+                                        };
                                     continue
                                     } else {
                                     self.state = WebsocketState::Down;
@@ -304,7 +370,27 @@ impl WebsocketClient {
             tungstenite::Message::Text(s) => {
                 tracing::trace!("{s}");
                 // Parse the message into a [twitch_api::eventsub::EventsubWebsocketData]
-                match Event::parse_websocket(s.as_str()).unwrap() {
+                let data = match Event::parse_websocket(s.as_str()) {
+                    Ok(data) => data,
+                    Err(e) => {
+                        // Event types newer than twitch_api.
+                        let id = message_id(s.as_str()).map(|id| format!("message:{id}"));
+                        if id.as_deref().is_some_and(dedup::is_seen) {
+                            return Ok(());
+                        }
+                        match event_from_notification(s.as_str()) {
+                            Some(event) => {
+                                let _ = self.event_tx.send(event).await;
+                            }
+                            None => error!("Failed to parse websocket message: {e}"),
+                        }
+                        if let Some(id) = id {
+                            self.handled(&id);
+                        }
+                        return Ok(());
+                    }
+                };
+                match data {
                     EventsubWebsocketData::Welcome {
                         payload: WelcomePayload { session },
                         ..
@@ -314,11 +400,12 @@ impl WebsocketClient {
                         payload: ReconnectPayload { session },
                         ..
                     } => {
-                        self.session_id = Some(session.id.to_string());
+                        info!("Twitch requested a reconnect");
+                        self.session_id = None;
                         if let Some(url) = session.reconnect_url {
-                            self.connect_url = WebsocketError::InvalidURL(url.to_string())
-                                .map_err(url.parse())
-                                .unwrap();
+                            self.reconnect_url = Some(
+                                WebsocketError::InvalidURL(url.to_string()).map_err(url.parse())?,
+                            );
                         }
 
                         if self.token.is_elapsed() {
@@ -332,55 +419,14 @@ impl WebsocketClient {
                         Err(WebsocketError::Reconnect)
                     }
 
-                    EventsubWebsocketData::Notification {
-                        metadata: _,
-                        payload,
-                    } => {
-                        match payload {
-                            Event::ChannelChatMessageV1(eventsub::Payload { message, .. }) => {
-                                self.channel_chat_message(message).await
-                            }
-
-                            Event::ChannelPointsCustomRewardRedemptionAddV1(
-                                eventsub::Payload { message, .. },
-                            ) => {
-                                self.channel_points_custom_reward_redemption_add(message)
-                                    .await
-                            }
-
-                            Event::ChannelPointsCustomRewardRedemptionUpdateV1(
-                                eventsub::Payload { message, .. },
-                            ) => {
-                                self.channel_points_custom_reward_redemption_update(message)
-                                    .await
-                            }
-
-                            Event::ChannelSubscribeV1(eventsub::Payload { message, .. }) => {
-                                self.channel_subscribe(message).await
-                            }
-
-                            Event::ChannelSubscriptionMessageV1(eventsub::Payload {
-                                message,
-                                ..
-                            }) => self.channel_subscribe_message(message).await,
-                            Event::ChannelSubscriptionGiftV1(eventsub::Payload {
-                                message, ..
-                            }) => self.channel_subscription_gift(message).await,
-                            Event::ChannelBitsUseV1(eventsub::Payload { message, .. }) => {
-                                self.channel_bits_use(message).await
-                            }
-                            Event::ChannelHypeTrainBeginV1(eventsub::Payload{message, ..}) => {
-                                self.channel_hype_train_begin(message).await
-                            }
-                            m => {
-                                let message =
-                                    format!("Received an unimplemented websocket event: {:?}", m);
-                                error!(
-                                    target = "rcon2::integration::twitch::websocket::Event",
-                                    message
-                                );
-                            }
+                    EventsubWebsocketData::Notification { metadata, payload } => {
+                        let id = format!("message:{}", metadata.message_id);
+                        if dedup::is_seen(&id) {
+                            return Ok(());
                         }
+                        self.handle_notification(payload, || event_from_notification(s.as_str()))
+                            .await;
+                        self.handled(&id);
                         Ok(())
                     }
                     EventsubWebsocketData::Revocation {
@@ -526,17 +572,24 @@ impl WebsocketClient {
                 );
 
                 info!(target = "rcon2::integration::twitch::websocket::ChannelPointsCustomRewardRedemptionUpdate", message);
-                let _ = self
-                    .event_tx
-                    .send(IntegrationEvent::ChannelPoint(CustomRewardEvent {
-                        event_id: reward_payload.id.to_string(),
-                        id: reward_payload.reward.id.to_string(),
-                        title: reward_payload.reward.title,
-                        user_name: reward_payload.user_name.to_string(),
-                        variant: CustomRewardVariant::Update,
-                        message: reward_payload.user_input.to_string(),
-                    }))
-                    .await;
+                let event = IntegrationEvent::ChannelPoint(CustomRewardEvent {
+                    event_id: reward_payload.id.to_string(),
+                    id: reward_payload.reward.id.to_string(),
+                    title: reward_payload.reward.title,
+                    user_name: reward_payload.user_name.to_string(),
+                    variant: CustomRewardVariant::Update,
+                    message: reward_payload.user_input.to_string(),
+                });
+                // The same redemption can arrive in another message, e.g. over the webhook.
+                let id = event.event_id();
+                if id.as_deref().is_some_and(dedup::is_seen) {
+                    return;
+                }
+                if self.event_tx.send(event).await.is_ok() {
+                    if let Some(id) = id {
+                        dedup::mark_seen(&id);
+                    }
+                }
             }
             _ => {
                 error! {"Unhandled ChannelPointsCustomRewardRedemptionUpdateV1 Payload: {:?}", message}
@@ -558,17 +611,24 @@ impl WebsocketClient {
                     reward_payload.user_input
                 );
                 info!(target = "rcon2::integration::twitch::websocket::ChannelPointsCustomRewardRedemptionAdd", message);
-                let _ = self
-                    .event_tx
-                    .send(IntegrationEvent::ChannelPoint(CustomRewardEvent {
-                        event_id: reward_payload.id.to_string(),
-                        id: reward_payload.reward.id.to_string(),
-                        title: reward_payload.reward.title,
-                        user_name: reward_payload.user_name.to_string(),
-                        variant: CustomRewardVariant::New,
-                        message: reward_payload.user_input.to_string(),
-                    }))
-                    .await;
+                let event = IntegrationEvent::ChannelPoint(CustomRewardEvent {
+                    event_id: reward_payload.id.to_string(),
+                    id: reward_payload.reward.id.to_string(),
+                    title: reward_payload.reward.title,
+                    user_name: reward_payload.user_name.to_string(),
+                    variant: CustomRewardVariant::New,
+                    message: reward_payload.user_input.to_string(),
+                });
+                // The same redemption can arrive in another message, e.g. over the webhook.
+                let id = event.event_id();
+                if id.as_deref().is_some_and(dedup::is_seen) {
+                    return;
+                }
+                if self.event_tx.send(event).await.is_ok() {
+                    if let Some(id) = id {
+                        dedup::mark_seen(&id);
+                    }
+                }
             }
             _ => {
                 error! {"Unhandled ChannelPointsCustomRewardRedemptionAddV1 Payload: {:?}", message}
@@ -576,23 +636,172 @@ impl WebsocketClient {
         }
     }
 
+    /// Remembers the notification for [`dedup::is_seen`] unless the runner is
+    /// gone, so it is handled again if Twitch resends it.
+    fn handled(&self, message_id: &str) {
+        if !self.event_tx.is_closed() {
+            dedup::mark_seen(message_id);
+        }
+    }
+
+    /// Sends the [`IntegrationEvent`] for a notification from either transport,
+    /// `fallback` parses event types without a handler.
+    pub async fn handle_notification(
+        &mut self,
+        payload: Event,
+        fallback: impl FnOnce() -> Option<IntegrationEvent>,
+    ) {
+        match payload {
+            Event::ChannelChatMessageV1(eventsub::Payload { message, .. }) => {
+                self.channel_chat_message(message).await
+            }
+
+            Event::ChannelPointsCustomRewardRedemptionAddV1(eventsub::Payload {
+                message, ..
+            }) => {
+                self.channel_points_custom_reward_redemption_add(message)
+                    .await
+            }
+
+            Event::ChannelPointsCustomRewardRedemptionUpdateV1(eventsub::Payload {
+                message,
+                ..
+            }) => {
+                self.channel_points_custom_reward_redemption_update(message)
+                    .await
+            }
+
+            Event::ChannelSubscribeV1(eventsub::Payload { message, .. }) => {
+                self.channel_subscribe(message).await
+            }
+
+            Event::ChannelSubscriptionMessageV1(eventsub::Payload { message, .. }) => {
+                self.channel_subscribe_message(message).await
+            }
+            Event::ChannelSubscriptionGiftV1(eventsub::Payload { message, .. }) => {
+                self.channel_subscription_gift(message).await
+            }
+            Event::ChannelBitsUseV1(eventsub::Payload { message, .. }) => {
+                self.channel_bits_use(message).await
+            }
+            Event::ChannelHypeTrainBeginV1(eventsub::Payload { message, .. }) => {
+                self.channel_hype_train_begin(message).await
+            }
+            Event::ChannelHypeTrainProgressV1(eventsub::Payload { message, .. }) => {
+                self.channel_hype_train_progress(message).await
+            }
+            Event::ChannelHypeTrainEndV1(eventsub::Payload { message, .. }) => {
+                self.channel_hype_train_end(message).await
+            }
+            Event::ChannelPollBeginV1(eventsub::Payload { message, .. }) => {
+                self.channel_poll_begin(message).await
+            }
+            Event::ChannelPollProgressV1(eventsub::Payload { message, .. }) => {
+                self.channel_poll_progress(message).await
+            }
+            Event::ChannelPollEndV1(eventsub::Payload { message, .. }) => {
+                self.channel_poll_end(message).await
+            }
+            Event::ChannelPredictionBeginV1(eventsub::Payload { message, .. }) => {
+                self.channel_prediction_begin(message).await
+            }
+            Event::ChannelPredictionProgressV1(eventsub::Payload { message, .. }) => {
+                self.channel_prediction_progress(message).await
+            }
+            Event::ChannelPredictionEndV1(eventsub::Payload { message, .. }) => {
+                self.channel_prediction_end(message).await
+            }
+            Event::ChannelFollowV2(eventsub::Payload { message, .. }) => {
+                self.channel_follow(message).await
+            }
+            Event::ChannelRaidV1(eventsub::Payload { message, .. }) => {
+                self.channel_raid(message).await
+            }
+            Event::ChannelCheerV1(eventsub::Payload { message, .. }) => {
+                self.channel_cheer(message).await
+            }
+            Event::ConduitShardDisabledV1(eventsub::Payload { message, .. }) => {
+                self.conduit_shard_disabled(message).await
+            }
+            m => match fallback() {
+                Some(event) => {
+                    debug!(
+                        target = "rcon2::integration::twitch::websocket::Event",
+                        "Generic event: {:?}", event
+                    );
+                    let _ = self.event_tx.send(event).await;
+                }
+                None => {
+                    let message = format!("Received an unimplemented websocket event: {:?}", m);
+                    error!(
+                        target = "rcon2::integration::twitch::websocket::Event",
+                        message
+                    );
+                }
+            },
+        }
+    }
+
+    async fn conduit_shard_disabled(
+        &mut self,
+        message: eventsub::Message<eventsub::conduit::ConduitShardDisabledV1>,
+    ) {
+        match message.clone() {
+            eventsub::Message::Notification(payload) => {
+                let message = format!(
+                    "Conduit shard {} disabled: {:?}",
+                    payload.shard_id, payload.status
+                );
+                warn!(
+                    target = "rcon2::integration::twitch::websocket::conduit",
+                    message
+                );
+                if let Some(conduit) = &self.conduit {
+                    match conduit.assign(self.session_id.as_deref()).await {
+                        Ok(()) => info!("Reassigned conduit shards"),
+                        Err(e) => error!("{e}"),
+                    }
+                }
+            }
+            _ => error! {"Unexpected message: {:?}", message},
+        }
+    }
+
     async fn channel_chat_message(
         &mut self,
         message: eventsub::Message<eventsub::channel::ChannelChatMessageV1>,
     ) {
         match message.clone() {
             eventsub::Message::Notification(chat_payload) => {
+                if chat::is_own_message(chat_payload.message_id.as_str()) {
+                    return;
+                }
                 let message =
                     chat_payload.chatter_user_name.to_string() + " - " + &chat_payload.message.text;
                 info!(
                     target = "rcon2::integration::twitch::websocket::ChannelChatMessage",
                     message
                 );
+                let badges = chat_payload
+                    .badges
+                    .iter()
+                    .map(|badge| Badge {
+                        set_id: badge.set_id.to_string(),
+                        id: badge.id.to_string(),
+                        info: badge.info.clone(),
+                    })
+                    .collect();
+                let chatter = Chatter::from_badges(
+                    chat_payload.chatter_user_id.as_str(),
+                    chat_payload.message_id.as_str(),
+                    badges,
+                );
                 let _ = self
                     .event_tx
                     .send(IntegrationEvent::Chat {
                         msg: chat_payload.message.text.to_string(),
                         author: chat_payload.chatter_user_name.to_string(),
+                        chatter,
                     })
                     .await;
             }
@@ -629,36 +838,422 @@ impl WebsocketClient {
         }
     }
     
-    /// Channel Hype Train begin docs: [dev.twitch.tv](https://dev.twitch.tv/docs/eventsub/eventsub-subscription-types/#channelbitsuse)
+    /// Channel Hype Train begin docs: [dev.twitch.tv](https://dev.twitch.tv/docs/eventsub/eventsub-subscription-types/#channelhype_trainbegin)
     async fn channel_hype_train_begin(
         &mut self,
         message: eventsub::Message<eventsub::channel::ChannelHypeTrainBeginV1>,
     ) {
         match message.clone() {
-            eventsub::Message::Notification(reward_payload) => {
-                let hypetrain_id = reward_payload.id.clone().take();
-                let level = reward_payload.level.clone();
-                let progress = reward_payload.progress.clone();
-                let message = format!("Hype train level {}", level);
+            eventsub::Message::Notification(hype_train_payload) => {
+                let hypetrain_id = hype_train_payload.id.clone().take();
+                let level = hype_train_payload.level;
+                let message = format!("Hype train began at level {}", level);
                 info!(
                     target = "rcon2::integration::twitch::websocket::ChannelHypeTrainBeginV1",
                     message
                 );
+                let events = HYPE_TRAIN_LEVELS
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .events(&hypetrain_id, level);
+                for (level, level_increased) in events {
+                    let _ = self
+                        .event_tx
+                        .send(IntegrationEvent::HypeTrain {
+                            state: HypeTrainState::Begin,
+                            id: hypetrain_id.clone(),
+                            level,
+                            progress: hype_train_payload.progress,
+                            goal: hype_train_payload.goal,
+                            total: hype_train_payload.total,
+                            expires_at: hype_train_payload.expires_at.clone(),
+                            level_increased,
+                        })
+                        .await;
+                }
+            }
+            _ => {
+                error! {"Unhandled ChannelHypeTrainBegin Payload: {:?}", message}
+            }
+        }
+    }
+
+    /// Channel Hype Train progress docs: [dev.twitch.tv](https://dev.twitch.tv/docs/eventsub/eventsub-subscription-types/#channelhype_trainprogress)
+    async fn channel_hype_train_progress(
+        &mut self,
+        message: eventsub::Message<eventsub::channel::ChannelHypeTrainProgressV1>,
+    ) {
+        match message.clone() {
+            eventsub::Message::Notification(hype_train_payload) => {
+                let hypetrain_id = hype_train_payload.id.clone().take();
+                let level = hype_train_payload.level;
+                let message = format!(
+                    "Hype train level {} at {}/{}",
+                    level, hype_train_payload.progress, hype_train_payload.goal
+                );
+                info!(
+                    target = "rcon2::integration::twitch::websocket::ChannelHypeTrainProgressV1",
+                    message
+                );
+                let events = HYPE_TRAIN_LEVELS
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .events(&hypetrain_id, level);
+                for (level, level_increased) in events {
+                    let _ = self
+                        .event_tx
+                        .send(IntegrationEvent::HypeTrain {
+                            state: HypeTrainState::Progress,
+                            id: hypetrain_id.clone(),
+                            level,
+                            progress: hype_train_payload.progress,
+                            goal: hype_train_payload.goal,
+                            total: hype_train_payload.total,
+                            expires_at: hype_train_payload.expires_at.clone(),
+                            level_increased,
+                        })
+                        .await;
+                }
+            }
+            _ => {
+                error! {"Unhandled ChannelHypeTrainProgress Payload: {:?}", message}
+            }
+        }
+    }
+
+    /// Channel Hype Train end docs: [dev.twitch.tv](https://dev.twitch.tv/docs/eventsub/eventsub-subscription-types/#channelhype_trainend)
+    ///
+    /// `expires_at` is when the hype train ended.
+    async fn channel_hype_train_end(
+        &mut self,
+        message: eventsub::Message<eventsub::channel::ChannelHypeTrainEndV1>,
+    ) {
+        match message.clone() {
+            eventsub::Message::Notification(hype_train_payload) => {
+                let level = hype_train_payload.level;
+                let message = format!("Hype train ended at level {}", level);
+                info!(
+                    target = "rcon2::integration::twitch::websocket::ChannelHypeTrainEndV1",
+                    message
+                );
+                HYPE_TRAIN_LEVELS
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .end();
                 let _ = self
                     .event_tx
                     .send(IntegrationEvent::HypeTrain {
-                        state: integration::event::HypeTrainState::Begin, // TODO: Work out actual value.
-                        id: hypetrain_id,
-                        level: level,
-                        progress,
-                        goal: reward_payload.goal,
-                        total: reward_payload.total,
-                        expires_at: reward_payload.expires_at,
+                        state: HypeTrainState::End,
+                        id: hype_train_payload.id.clone().take(),
+                        level,
+                        progress: 0,
+                        goal: 0,
+                        total: hype_train_payload.total,
+                        expires_at: hype_train_payload.ended_at,
+                        level_increased: false,
                     })
                     .await;
             }
             _ => {
-                error! {"Unhandled ChannelBitsUse Payload: {:?}", message}
+                error! {"Unhandled ChannelHypeTrainEnd Payload: {:?}", message}
+            }
+        }
+    }
+
+    /// Channel Follow docs: [dev.twitch.tv](https://dev.twitch.tv/docs/eventsub/eventsub-subscription-types/#channelfollow)
+    async fn channel_follow(
+        &mut self,
+        message: eventsub::Message<eventsub::channel::ChannelFollowV2>,
+    ) {
+        match message.clone() {
+            eventsub::Message::Notification(follow_payload) => {
+                let user_name = follow_payload.user_name.clone().take();
+                let message = format!("{} followed", user_name);
+                info!(
+                    target = "rcon2::integration::twitch::websocket::ChannelFollow",
+                    message
+                );
+                let _ = self
+                    .event_tx
+                    .send(IntegrationEvent::Follow { user_name })
+                    .await;
+            }
+            _ => {
+                error! {"Unhandled ChannelFollow Payload: {:?}", message}
+            }
+        }
+    }
+
+    /// Channel Raid docs: [dev.twitch.tv](https://dev.twitch.tv/docs/eventsub/eventsub-subscription-types/#channelraid)
+    async fn channel_raid(&mut self, message: eventsub::Message<eventsub::channel::ChannelRaidV1>) {
+        match message.clone() {
+            eventsub::Message::Notification(raid_payload) => {
+                let from = raid_payload.from_broadcaster_user_name.clone().take();
+                let message = format!("{} raided with {} viewers", from, raid_payload.viewers);
+                info!(
+                    target = "rcon2::integration::twitch::websocket::ChannelRaid",
+                    message
+                );
+                let _ = self
+                    .event_tx
+                    .send(IntegrationEvent::Raid {
+                        from,
+                        viewers: u64::try_from(raid_payload.viewers).unwrap_or_default(),
+                    })
+                    .await;
+            }
+            _ => {
+                error! {"Unhandled ChannelRaid Payload: {:?}", message}
+            }
+        }
+    }
+
+    /// Channel Cheer docs: [dev.twitch.tv](https://dev.twitch.tv/docs/eventsub/eventsub-subscription-types/#channelcheer)
+    ///
+    /// Sent as [`IntegrationEvent::Bits`] unless `channel.bits.use` is subscribed to,
+    /// which already includes cheers.
+    async fn channel_cheer(
+        &mut self,
+        message: eventsub::Message<eventsub::channel::ChannelCheerV1>,
+    ) {
+        match message.clone() {
+            eventsub::Message::Notification(_)
+                if self
+                    .subscriptions
+                    .contains(&eventsub::EventType::ChannelBitsUse) =>
+            {
+                debug!("Cheer already sent by channel.bits.use");
+            }
+            eventsub::Message::Notification(cheer_payload) => {
+                let user_name = match &cheer_payload.user_name {
+                    Some(name) if !cheer_payload.is_anonymous => name.to_string(),
+                    _ => "Anonymous".to_string(),
+                };
+                let message = format!("{} bits cheered from {}", cheer_payload.bits, user_name);
+                info!(
+                    target = "rcon2::integration::twitch::websocket::ChannelCheer",
+                    message
+                );
+                let _ = self
+                    .event_tx
+                    .send(IntegrationEvent::Bits {
+                        user_name,
+                        bits: u64::try_from(cheer_payload.bits).unwrap_or_default(),
+                    })
+                    .await;
+            }
+            _ => {
+                error! {"Unhandled ChannelCheer Payload: {:?}", message}
+            }
+        }
+    }
+
+    /// Channel Poll begin docs: [dev.twitch.tv](https://dev.twitch.tv/docs/eventsub/eventsub-subscription-types/#channelpollbegin)
+    async fn channel_poll_begin(
+        &mut self,
+        message: eventsub::Message<eventsub::channel::ChannelPollBeginV1>,
+    ) {
+        match message.clone() {
+            eventsub::Message::Notification(poll_payload) => {
+                let message = format!(
+                    "Poll \"{}\" began with choices: {}",
+                    poll_payload.title,
+                    poll_payload
+                        .choices
+                        .iter()
+                        .map(|c| c.title.as_str())
+                        .join(", ")
+                );
+                info!(
+                    target = "rcon2::integration::twitch::websocket::ChannelPollBegin",
+                    message
+                );
+            }
+            _ => {
+                error! {"Unhandled ChannelPollBegin Payload: {:?}", message}
+            }
+        }
+    }
+
+    /// Channel Poll progress docs: [dev.twitch.tv](https://dev.twitch.tv/docs/eventsub/eventsub-subscription-types/#channelpollprogress)
+    async fn channel_poll_progress(
+        &mut self,
+        message: eventsub::Message<eventsub::channel::ChannelPollProgressV1>,
+    ) {
+        match message.clone() {
+            eventsub::Message::Notification(poll_payload) => {
+                let message = format!(
+                    "Poll \"{}\" votes: {}",
+                    poll_payload.title,
+                    poll_payload
+                        .choices
+                        .iter()
+                        .map(|c| format!("{} {}", c.title, c.votes.unwrap_or_default()))
+                        .join(", ")
+                );
+                debug!(
+                    target = "rcon2::integration::twitch::websocket::ChannelPollProgress",
+                    message
+                );
+            }
+            _ => {
+                error! {"Unhandled ChannelPollProgress Payload: {:?}", message}
+            }
+        }
+    }
+
+    /// Channel Poll end docs: [dev.twitch.tv](https://dev.twitch.tv/docs/eventsub/eventsub-subscription-types/#channelpollend)
+    ///
+    /// Only completed or terminated polls have a winner, archived polls were already sent.
+    async fn channel_poll_end(
+        &mut self,
+        message: eventsub::Message<eventsub::channel::ChannelPollEndV1>,
+    ) {
+        match message.clone() {
+            eventsub::Message::Notification(poll_payload) => {
+                if !matches!(
+                    poll_payload.status,
+                    types::PollStatus::Completed | types::PollStatus::Terminated
+                ) {
+                    debug!("Poll \"{}\" {:?}", poll_payload.title, poll_payload.status);
+                    return;
+                }
+                let Some((winning_choice, votes)) = integration::twitch::polls::winning_choice(
+                    poll_payload.choices.iter().map(|c| {
+                        (
+                            c.title.as_str(),
+                            u64::try_from(c.votes.unwrap_or_default()).unwrap_or_default(),
+                        )
+                    }),
+                ) else {
+                    warn!("Poll \"{}\" ended without choices", poll_payload.title);
+                    return;
+                };
+                let message = format!(
+                    "Poll \"{}\" won by \"{}\" with {} votes",
+                    poll_payload.title, winning_choice, votes
+                );
+                info!(
+                    target = "rcon2::integration::twitch::websocket::ChannelPollEnd",
+                    message
+                );
+                let _ = self
+                    .event_tx
+                    .send(IntegrationEvent::PollEnd {
+                        title: poll_payload.title,
+                        winning_choice,
+                        votes,
+                    })
+                    .await;
+            }
+            _ => {
+                error! {"Unhandled ChannelPollEnd Payload: {:?}", message}
+            }
+        }
+    }
+
+    /// Channel Prediction begin docs: [dev.twitch.tv](https://dev.twitch.tv/docs/eventsub/eventsub-subscription-types/#channelpredictionbegin)
+    async fn channel_prediction_begin(
+        &mut self,
+        message: eventsub::Message<eventsub::channel::ChannelPredictionBeginV1>,
+    ) {
+        match message.clone() {
+            eventsub::Message::Notification(prediction_payload) => {
+                let message = format!(
+                    "Prediction \"{}\" began with outcomes: {}",
+                    prediction_payload.title,
+                    prediction_payload
+                        .outcomes
+                        .iter()
+                        .map(|o| o.title.as_str())
+                        .join(", ")
+                );
+                info!(
+                    target = "rcon2::integration::twitch::websocket::ChannelPredictionBegin",
+                    message
+                );
+            }
+            _ => {
+                error! {"Unhandled ChannelPredictionBegin Payload: {:?}", message}
+            }
+        }
+    }
+
+    /// Channel Prediction progress docs: [dev.twitch.tv](https://dev.twitch.tv/docs/eventsub/eventsub-subscription-types/#channelpredictionprogress)
+    async fn channel_prediction_progress(
+        &mut self,
+        message: eventsub::Message<eventsub::channel::ChannelPredictionProgressV1>,
+    ) {
+        match message.clone() {
+            eventsub::Message::Notification(prediction_payload) => {
+                let message = format!(
+                    "Prediction \"{}\" users: {}",
+                    prediction_payload.title,
+                    prediction_payload
+                        .outcomes
+                        .iter()
+                        .map(|o| format!("{} {}", o.title, o.users.unwrap_or_default()))
+                        .join(", ")
+                );
+                debug!(
+                    target = "rcon2::integration::twitch::websocket::ChannelPredictionProgress",
+                    message
+                );
+            }
+            _ => {
+                error! {"Unhandled ChannelPredictionProgress Payload: {:?}", message}
+            }
+        }
+    }
+
+    /// Channel Prediction end docs: [dev.twitch.tv](https://dev.twitch.tv/docs/eventsub/eventsub-subscription-types/#channelpredictionend)
+    ///
+    /// Sent as [`IntegrationEvent::PollEnd`] with the number of users who predicted the
+    /// winning outcome as the votes. Canceled predictions are ignored.
+    async fn channel_prediction_end(
+        &mut self,
+        message: eventsub::Message<eventsub::channel::ChannelPredictionEndV1>,
+    ) {
+        match message.clone() {
+            eventsub::Message::Notification(prediction_payload) => {
+                if prediction_payload.status != types::PredictionStatus::Resolved {
+                    debug!(
+                        "Prediction \"{}\" {:?}",
+                        prediction_payload.title, prediction_payload.status
+                    );
+                    return;
+                }
+                let Some(outcome) = prediction_payload
+                    .outcomes
+                    .iter()
+                    .find(|o| o.id == prediction_payload.winning_outcome_id.as_str())
+                else {
+                    warn!(
+                        "Prediction \"{}\" resolved without a winning outcome",
+                        prediction_payload.title
+                    );
+                    return;
+                };
+                let votes = u64::try_from(outcome.users.unwrap_or_default()).unwrap_or_default();
+                let message = format!(
+                    "Prediction \"{}\" resolved to \"{}\" predicted by {} users",
+                    prediction_payload.title, outcome.title, votes
+                );
+                info!(
+                    target = "rcon2::integration::twitch::websocket::ChannelPredictionEnd",
+                    message
+                );
+                let _ = self
+                    .event_tx
+                    .send(IntegrationEvent::PollEnd {
+                        title: prediction_payload.title.clone(),
+                        winning_choice: outcome.title.clone(),
+                        votes,
+                    })
+                    .await;
+            }
+            _ => {
+                error! {"Unhandled ChannelPredictionEnd Payload: {:?}", message}
             }
         }
     }
@@ -668,11 +1263,6 @@ impl WebsocketClient {
         data: SessionData<'_>,
     ) -> Result<(), WebsocketError> {
         self.session_id = Some(data.id.to_string());
-        if let Some(url) = data.reconnect_url {
-            self.connect_url = WebsocketError::InvalidURL(url.to_string())
-                .map_err(url.parse())
-                .unwrap();
-        }
 
         if self.token.is_elapsed() {
             match self.refresh_token().await {
@@ -687,6 +1277,13 @@ impl WebsocketClient {
             self.keep_alive_seconds =
                 Duration::from_secs(keep_alive as u64) + Duration::from_millis(200);
         };
+        if let Some(conduit) = &self.conduit {
+            if let Err(e) = conduit.assign(Some(&data.id)).await {
+                error!("{e}");
+                self.push_subscription_failure(e.to_string());
+            }
+            return Ok(());
+        }
         let transport = eventsub::Transport::websocket(data.id.clone());
 
         for subscription in self.subscriptions.clone() {
@@ -805,33 +1402,275 @@ impl WebsocketClient {
 
                     }
                 }
+                ChannelFollow => {
+                    match self.client
+                        .create_eventsub_subscription(
+                            eventsub::channel::ChannelFollowV2::new(
+                                self.user_id.clone(),
+                                self.user_id.clone(),
+                            ),
+                            transport.clone(),
+                            &self.token
+                        ).await
+                    {
+                        Ok(sub)  =>  {
+                            self.subscribed.push(subscription);
+                            info!("Subscribed to {}", subscription);
+                            debug!{"Subscription: {:?}", sub};
+                        },
+                        Err(e) => {warn!("Failed to subscribe to {}: {}", subscription, e)},
+                    }
+                }
+                ChannelRaid => {
+                    match self.client
+                        .create_eventsub_subscription(
+                            eventsub::channel::ChannelRaidV1::to_broadcaster_user_id(self.user_id.clone()),
+                            transport.clone(),
+                            &self.token
+                        ).await
+                    {
+                        Ok(sub)  =>  {
+                            self.subscribed.push(subscription);
+                            info!("Subscribed to {}", subscription);
+                            debug!{"Subscription: {:?}", sub};
+                        },
+                        Err(e) => {warn!("Failed to subscribe to {}: {}", subscription, e)},
+                    }
+                }
+                ChannelCheer => {
+                    match self.client
+                        .create_eventsub_subscription(
+                            eventsub::channel::ChannelCheerV1::broadcaster_user_id(self.user_id.clone()),
+                            transport.clone(),
+                            &self.token
+                        ).await
+                    {
+                        Ok(sub)  =>  {
+                            self.subscribed.push(subscription);
+                            info!("Subscribed to {}", subscription);
+                            debug!{"Subscription: {:?}", sub};
+                        },
+                        Err(e) => {warn!("Failed to subscribe to {}: {}", subscription, e)},
+                    }
+                }
+                ChannelPollBegin => {
+                    match self.client
+                        .create_eventsub_subscription(
+                            eventsub::channel::ChannelPollBeginV1::broadcaster_user_id(self.user_id.clone()),
+                            transport.clone(),
+                            &self.token
+                        ).await
+                    {
+                        Ok(sub)  =>  {
+                            self.subscribed.push(subscription);
+                            info!("Subscribed to {}", subscription);
+                            debug!{"Subscription: {:?}", sub};
+                        },
+                        Err(e) => {warn!("Failed to subscribe to {}: {}", subscription, e)},
+                    }
+                }
+                ChannelPollProgress => {
+                    match self.client
+                        .create_eventsub_subscription(
+                            eventsub::channel::ChannelPollProgressV1::broadcaster_user_id(self.user_id.clone()),
+                            transport.clone(),
+                            &self.token
+                        ).await
+                    {
+                        Ok(sub)  =>  {
+                            self.subscribed.push(subscription);
+                            info!("Subscribed to {}", subscription);
+                            debug!{"Subscription: {:?}", sub};
+                        },
+                        Err(e) => {warn!("Failed to subscribe to {}: {}", subscription, e)},
+                    }
+                }
+                ChannelPollEnd => {
+                    match self.client
+                        .create_eventsub_subscription(
+                            eventsub::channel::ChannelPollEndV1::broadcaster_user_id(self.user_id.clone()),
+                            transport.clone(),
+                            &self.token
+                        ).await
+                    {
+                        Ok(sub)  =>  {
+                            self.subscribed.push(subscription);
+                            info!("Subscribed to {}", subscription);
+                            debug!{"Subscription: {:?}", sub};
+                        },
+                        Err(e) => {warn!("Failed to subscribe to {}: {}", subscription, e)},
+                    }
+                }
+                ChannelPredictionBegin => {
+                    match self.client
+                        .create_eventsub_subscription(
+                            eventsub::channel::ChannelPredictionBeginV1::broadcaster_user_id(self.user_id.clone()),
+                            transport.clone(),
+                            &self.token
+                        ).await
+                    {
+                        Ok(sub)  =>  {
+                            self.subscribed.push(subscription);
+                            info!("Subscribed to {}", subscription);
+                            debug!{"Subscription: {:?}", sub};
+                        },
+                        Err(e) => {warn!("Failed to subscribe to {}: {}", subscription, e)},
+                    }
+                }
+                ChannelPredictionProgress => {
+                    match self.client
+                        .create_eventsub_subscription(
+                            eventsub::channel::ChannelPredictionProgressV1::broadcaster_user_id(self.user_id.clone()),
+                            transport.clone(),
+                            &self.token
+                        ).await
+                    {
+                        Ok(sub)  =>  {
+                            self.subscribed.push(subscription);
+                            info!("Subscribed to {}", subscription);
+                            debug!{"Subscription: {:?}", sub};
+                        },
+                        Err(e) => {warn!("Failed to subscribe to {}: {}", subscription, e)},
+                    }
+                }
+                ChannelPredictionEnd => {
+                    match self.client
+                        .create_eventsub_subscription(
+                            eventsub::channel::ChannelPredictionEndV1::broadcaster_user_id(self.user_id.clone()),
+                            transport.clone(),
+                            &self.token
+                        ).await
+                    {
+                        Ok(sub)  =>  {
+                            self.subscribed.push(subscription);
+                            info!("Subscribed to {}", subscription);
+                            debug!{"Subscription: {:?}", sub};
+                        },
+                        Err(e) => {warn!("Failed to subscribe to {}: {}", subscription, e)},
+                    }
+                }
+                ChannelHypeTrainBegin => {
+                    match self.client
+                        .create_eventsub_subscription(
+                            eventsub::channel::ChannelHypeTrainBeginV1::broadcaster_user_id(self.user_id.clone()),
+                            transport.clone(),
+                            &self.token
+                        ).await
+                    {
+                        Ok(sub)  =>  {
+                            self.subscribed.push(subscription);
+                            info!("Subscribed to {}", subscription);
+                            debug!{"Subscription: {:?}", sub};
+                        },
+                        Err(e) => {warn!("Failed to subscribe to {}: {}", subscription, e)},
+                    }
+                }
+                ChannelHypeTrainProgress => {
+                    match self.client
+                        .create_eventsub_subscription(
+                            eventsub::channel::ChannelHypeTrainProgressV1::broadcaster_user_id(self.user_id.clone()),
+                            transport.clone(),
+                            &self.token
+                        ).await
+                    {
+                        Ok(sub)  =>  {
+                            self.subscribed.push(subscription);
+                            info!("Subscribed to {}", subscription);
+                            debug!{"Subscription: {:?}", sub};
+                        },
+                        Err(e) => {warn!("Failed to subscribe to {}: {}", subscription, e)},
+                    }
+                }
+                ChannelHypeTrainEnd => {
+                    match self.client
+                        .create_eventsub_subscription(
+                            eventsub::channel::ChannelHypeTrainEndV1::broadcaster_user_id(self.user_id.clone()),
+                            transport.clone(),
+                            &self.token
+                        ).await
+                    {
+                        Ok(sub)  =>  {
+                            self.subscribed.push(subscription);
+                            info!("Subscribed to {}", subscription);
+                            debug!{"Subscription: {:?}", sub};
+                        },
+                        Err(e) => {warn!("Failed to subscribe to {}: {}", subscription, e)},
+                    }
+                }
                 _ => {
-                    error!(target:"rcon2::integration::twitch::websocket::subscription", "Tried to subscribe to unimplemented subscription: {}", subscription)
+                    match self.create_generic_subscription(subscription, &data.id).await {
+                        Ok(()) => {
+                            self.subscribed.push(subscription);
+                            info!("Subscribed to {}", subscription);
+                        },
+                        Err(e) => {
+                            error!(target:"rcon2::integration::twitch::websocket::subscription", "Failed to subscribe to {}: {}", subscription, e)
+                        },
+                    }
                 }
             }
         }
-        self.current_subsciptions().await;
+        self.reconcile_subscriptions().await;
         Ok(())
     }
 
-    /// TODO: Make return result and compare to current subscribed
-    async fn current_subsciptions(&mut self) -> Vec<EventType> {
-        let mut subscribed: Vec<EventType> = Vec::new();
-        let _ = self
+    /// Deletes subscriptions left by old sessions and checks every configured
+    /// subscription is enabled on this one, see [`WebsocketClient::subscription_failures`].
+    async fn reconcile_subscriptions(&mut self) {
+        let Some(session_id) = self.session_id.clone() else {
+            return;
+        };
+        let existing: Vec<SubscriptionInfo> = match self
             .client
             .get_eventsub_subscriptions(None, None, None, &self.token)
             .map_ok(|r| {
-                let mut subs = r.subscriptions.into_iter().map(|s| s.type_).collect_vec();
-                subscribed.append(&mut subs);
+                r.subscriptions
+                    .iter()
+                    .map(SubscriptionInfo::from)
+                    .collect_vec()
             })
-            .try_collect::<Vec<_>>()
-            .await;
-
-        subscribed = subscribed
-            .into_iter()
-            .unique_by(|s| s.to_str())
+            .try_concat()
+            .await
+        {
+            Ok(existing) => existing,
+            Err(e) => {
+                warn!("Failed to list subscriptions: {e}");
+                return;
+            }
+        };
+        let reconciliation = reconcile(&existing, &session_id, &self.subscriptions);
+        for id in reconciliation.stale {
+            match self
+                .client
+                .delete_eventsub_subscription(id.as_str(), &self.token)
+                .await
+            {
+                Ok(_) => debug!("Deleted stale subscription: {id}"),
+                Err(e) => warn!("Failed to delete stale subscription {id}: {e}"),
+            }
+        }
+        // Subscribed again on the next welcome.
+        self.subscribed
+            .retain(|s| !reconciliation.missing.contains(s));
+        let failures = reconciliation
+            .missing
+            .iter()
+            .map(|s| s.to_string())
             .collect_vec();
-        subscribed
+        if !failures.is_empty() {
+            error!("Subscriptions not enabled: {}", failures.join(", "));
+        }
+        *self.subscription_failures.lock().unwrap() = failures;
+    }
+
+    /// Configured subscriptions which were not enabled when last checked.
+    pub fn subscription_failures(&self) -> Vec<String> {
+        self.subscription_failures.lock().unwrap().clone()
+    }
+
+    /// Records a subscription which failed outside of [`WebsocketClient::reconcile_subscriptions`].
+    pub fn push_subscription_failure(&self, failure: String) {
+        self.subscription_failures.lock().unwrap().push(failure);
     }
 
     pub fn state(&self) -> WebsocketState {
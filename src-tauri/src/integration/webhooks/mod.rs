@@ -0,0 +1,481 @@
+//! Webhooks from tipping services without their own integration, e.g. Ko-fi or
+//! Humble. Each table in `webhooks` is a route on the localhost server at
+//! `/webhook/<source>`, verified with a shared token or HMAC signature and mapped
+//! to an [`IntegrationEvent::Webhook`] with JSONPaths. A [`preset`] fills in all
+//! but the token for known services.
+use anyhow::{bail, Result};
+use config::{Config, Map, Value as ConfigValue};
+use crypto_hmac::{Hmac, Mac};
+use http::{Request, Response, StatusCode};
+use serde_json::Value;
+use sha2::Sha256;
+use tracing::{debug, error, warn};
+
+pub mod preset;
+
+use super::{
+    currency::{self, CurrencyRates},
+    dedup, runner_tx,
+    twitch::webhook::hex_decode,
+    IntegrationEvent,
+};
+use crate::{
+    command::trigger::json_path,
+    localhost::{text_response, TwitchState},
+    settings::Settings,
+};
+
+/// Routes are `/webhook/<source>`, Twitch and Patreon have their own.
+pub const PATH_PREFIX: &str = "/webhook/";
+
+/// How a request is shown to come from the service.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Verification {
+    /// Anyone who can reach the route can post, only meant for testing.
+    #[default]
+    None,
+    /// A shared token in a header, or in a field of the body.
+    Token {
+        token: String,
+        header: Option<String>,
+        /// JSONPath of the field.
+        path: Option<String>,
+    },
+    /// The hex HMAC-SHA256 of the body in a header, after a prefix such as
+    /// `sha256=`.
+    Hmac {
+        secret: String,
+        header: String,
+        prefix: String,
+    },
+}
+
+impl Verification {
+    pub fn verify(&self, request: &Request<Vec<u8>>, body: &Value) -> bool {
+        match self {
+            Verification::None => true,
+            Verification::Token {
+                token,
+                header,
+                path,
+            } => {
+                let found = match (header, path) {
+                    (Some(header), _) => header_value(request, header),
+                    (None, Some(path)) => json_path::select(body, path).and_then(Value::as_str),
+                    (None, None) => None,
+                };
+                !token.is_empty() && found == Some(token.as_str())
+            }
+            Verification::Hmac {
+                secret,
+                header,
+                prefix,
+            } => {
+                let Some(signature) = header_value(request, header)
+                    .and_then(|s| s.strip_prefix(prefix.as_str()))
+                    .and_then(hex_decode)
+                else {
+                    return false;
+                };
+                let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret.as_bytes()) else {
+                    return false;
+                };
+                mac.update(request.body());
+                mac.verify_slice(&signature).is_ok()
+            }
+        }
+    }
+}
+
+fn header_value<'a>(request: &'a Request<Vec<u8>>, name: &str) -> Option<&'a str> {
+    request.headers().get(name).and_then(|v| v.to_str().ok())
+}
+
+/// JSONPaths of the fields of the body, [`None`] leaves the field empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldMapping {
+    pub amount: Option<String>,
+    pub currency: Option<String>,
+    pub user: Option<String>,
+    pub message: Option<String>,
+    /// A boolean, the message is left out when it is `false`.
+    pub public: Option<String>,
+    /// Identifies the payment so a retried webhook only triggers once.
+    pub id: Option<String>,
+}
+
+impl FieldMapping {
+    fn set(&mut self, field: &str, path: String) -> Result<()> {
+        json_path::validate(&path)?;
+        let field = match field {
+            "amount" => &mut self.amount,
+            "currency" => &mut self.currency,
+            "user" => &mut self.user,
+            "message" => &mut self.message,
+            "public" => &mut self.public,
+            "id" => &mut self.id,
+            field => bail!(
+                "Unknown webhook field {field}, expected amount, currency, user, message, public or id"
+            ),
+        };
+        *field = Some(path);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookRoute {
+    /// Name of the table, the route and the `source` of the events.
+    pub source: String,
+    pub verification: Verification,
+    /// The body is a form with the JSON in this field, rather than JSON.
+    pub form_field: Option<String>,
+    pub fields: FieldMapping,
+    /// Currency of the amount when the body doesn't have one.
+    pub currency: String,
+}
+
+impl WebhookRoute {
+    pub fn new(source: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            verification: Verification::None,
+            form_field: None,
+            fields: FieldMapping::default(),
+            currency: "USD".to_string(),
+        }
+    }
+
+    /// The route for `webhooks.<source>`, [`None`] if there isn't one.
+    pub fn from_source(config: &Config, source: &str) -> Result<Option<Self>> {
+        if source.is_empty()
+            || !source
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Ok(None);
+        }
+        match config.get_table(&format!("webhooks.{source}")) {
+            Ok(table) => Self::from_config(source, &table).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Reads a table of `webhooks`, anything set overrides the preset.
+    pub fn from_config(source: &str, table: &Map<String, ConfigValue>) -> Result<Self> {
+        let get = |key: &str| {
+            table
+                .get(key)
+                .and_then(|v| v.clone().into_string().ok())
+                .filter(|s| !s.is_empty())
+        };
+        let mut route = match get("preset") {
+            Some(name) => preset::preset(&name, source)?,
+            None => Self::new(source),
+        };
+        if let Some(form_field) = get("form_field") {
+            route.form_field = Some(form_field);
+        }
+        if let Some(currency) = get("currency") {
+            route.currency = currency.to_uppercase();
+        }
+        if let Some(fields) = table.get("fields") {
+            for (field, path) in fields.clone().into_table()? {
+                route.fields.set(&field, path.into_string()?)?;
+            }
+        }
+        // Only a preset has a verification already.
+        let kind = match (get("verification"), &route.verification) {
+            (Some(kind), _) => kind.to_lowercase(),
+            (None, Verification::Token { .. }) => "token".to_string(),
+            (None, Verification::Hmac { .. }) => "hmac".to_string(),
+            (None, Verification::None) => {
+                bail!("webhooks.{source}.verification is required, Token, Hmac or None")
+            }
+        };
+        route.verification = match (kind.as_str(), route.verification) {
+            ("none", _) => {
+                warn!("Anyone can post to the {source} webhook, verification is None");
+                Verification::None
+            }
+            ("token", preset) => {
+                let (header, path) = match preset {
+                    Verification::Token { header, path, .. } => (header, path),
+                    _ => (None, None),
+                };
+                let header = get("token_header").or(header);
+                let path = get("token_path").or(path);
+                if header.is_none() && path.is_none() {
+                    bail!("webhooks.{source} needs a token_header or token_path");
+                }
+                if let Some(path) = &path {
+                    json_path::validate(path)?;
+                }
+                match get("token") {
+                    Some(token) => Verification::Token {
+                        token,
+                        header,
+                        path,
+                    },
+                    None => bail!("webhooks.{source}.token is required"),
+                }
+            }
+            ("hmac", preset) => {
+                let (header, prefix) = match preset {
+                    Verification::Hmac { header, prefix, .. } => (Some(header), prefix),
+                    _ => (None, String::new()),
+                };
+                let Some(header) = get("signature_header").or(header) else {
+                    bail!("webhooks.{source}.signature_header is required");
+                };
+                match get("secret") {
+                    Some(secret) => Verification::Hmac {
+                        secret,
+                        header,
+                        prefix: get("signature_prefix").unwrap_or(prefix),
+                    },
+                    None => bail!("webhooks.{source}.secret is required"),
+                }
+            }
+            (kind, _) => {
+                bail!("Invalid webhook verification: {kind}, expected Token, Hmac or None")
+            }
+        };
+        Ok(route)
+    }
+
+    /// The JSON of the body, from the form field if there is one.
+    fn body(&self, request: &Request<Vec<u8>>) -> Result<Value, StatusCode> {
+        match &self.form_field {
+            Some(field) => {
+                let data = url::form_urlencoded::parse(request.body())
+                    .find(|(key, _)| key == field)
+                    .ok_or(StatusCode::BAD_REQUEST)?
+                    .1;
+                serde_json::from_str(&data).map_err(|_| StatusCode::BAD_REQUEST)
+            }
+            None => serde_json::from_slice(request.body()).map_err(|_| StatusCode::BAD_REQUEST),
+        }
+    }
+
+    /// The verified body, the error is the status to respond with.
+    pub fn check_request(&self, request: &Request<Vec<u8>>) -> Result<Value, StatusCode> {
+        let body = self.body(request)?;
+        if !self.verification.verify(request, &body) {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        Ok(body)
+    }
+
+    fn select<'a>(&self, payload: &'a Value, path: &Option<String>) -> Option<&'a Value> {
+        json_path::select(payload, path.as_deref()?)
+    }
+
+    fn text(&self, payload: &Value, path: &Option<String>) -> String {
+        match self.select(payload, path) {
+            Some(Value::String(text)) => text.clone(),
+            Some(Value::Null) | None => String::new(),
+            Some(value) => value.to_string(),
+        }
+    }
+
+    /// Maps the body with the fields.
+    pub fn event(&self, payload: Value, rates: &CurrencyRates) -> IntegrationEvent {
+        let amount = match self.select(&payload, &self.fields.amount) {
+            Some(Value::Number(n)) => currency::parse_cents(&n.to_string()),
+            Some(Value::String(s)) => currency::parse_cents(s),
+            _ => None,
+        };
+        let currency = match self.text(&payload, &self.fields.currency) {
+            currency if currency.is_empty() => self.currency.clone(),
+            currency => currency.to_uppercase(),
+        };
+        let public = self
+            .select(&payload, &self.fields.public)
+            .and_then(Value::as_bool)
+            .unwrap_or(true);
+        IntegrationEvent::Webhook {
+            source: self.source.clone(),
+            amount: amount.unwrap_or_default(),
+            usd_cents: amount.and_then(|amount| rates.cents_to_usd_cents(amount, &currency)),
+            currency,
+            user: self.text(&payload, &self.fields.user),
+            message: match public {
+                true => self.text(&payload, &self.fields.message),
+                false => String::new(),
+            },
+            payload,
+        }
+    }
+
+    /// Key for [`dedup`], [`None`] without an id.
+    pub fn event_id(&self, payload: &Value) -> Option<String> {
+        let id = self.text(payload, &self.fields.id);
+        (!id.is_empty()).then(|| format!("webhook:{}:{id}", self.source))
+    }
+}
+
+/// Handles a request to a path starting with [`PATH_PREFIX`]. The routes are
+/// read from the config on each request, so they can change while running.
+pub async fn route(request: Request<Vec<u8>>, twitch: TwitchState) -> Response<Vec<u8>> {
+    let path = request.uri().path().trim_end_matches('/');
+    let source = path.strip_prefix(PATH_PREFIX).unwrap_or_default();
+    let config = Settings::current_config();
+    let route = match WebhookRoute::from_source(&config, source) {
+        Ok(Some(route)) => route,
+        Ok(None) => return text_response(StatusCode::NOT_FOUND, "Not Found"),
+        Err(e) => {
+            error!("Invalid webhooks.{source}: {e:#}");
+            return text_response(StatusCode::INTERNAL_SERVER_ERROR, "Invalid webhook config");
+        }
+    };
+    let payload = match route.check_request(&request) {
+        Ok(payload) => payload,
+        Err(status) => {
+            warn!("Rejected {source} webhook request: {status}");
+            return text_response(status, status.to_string());
+        }
+    };
+    let id = route.event_id(&payload);
+    if id.as_deref().is_some_and(dedup::is_seen) {
+        return text_response(StatusCode::OK, "OK");
+    }
+    let event = route.event(payload, &CurrencyRates::from_config(&config));
+    debug!("Webhook event: {event:?}");
+    let event_tx = runner_tx(&twitch, &config).await;
+    if let Err(e) = event_tx.send(event).await {
+        error!("Failed to pass on the {source} webhook: {e}");
+        return text_response(StatusCode::SERVICE_UNAVAILABLE, "Not running");
+    }
+    if let Some(id) = id {
+        dedup::mark_seen(&id);
+    }
+    text_response(StatusCode::OK, "OK")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn config(toml: &str) -> Config {
+        Config::builder()
+            .add_source(config::File::from_str(toml, config::FileFormat::Toml))
+            .build()
+            .unwrap()
+    }
+
+    fn request(header: (&str, &str), body: &str) -> Request<Vec<u8>> {
+        Request::builder()
+            .method("POST")
+            .uri("/webhook/humble")
+            .header(header.0, header.1)
+            .body(body.as_bytes().to_vec())
+            .unwrap()
+    }
+
+    const HUMBLE: &str = r#"
+        [webhooks.humble]
+        verification = "Hmac"
+        secret = "It's a Secret to Everybody"
+        signature_header = "X-Signature"
+        signature_prefix = "sha256="
+        [webhooks.humble.fields]
+        amount = "$.order.total"
+        user = "$.customer.name"
+        id = "$.order.id"
+    "#;
+    const BODY: &str = r#"{"order":{"id":"A1","total":12.5},"customer":{"name":"Cool User"}}"#;
+
+    #[rstest]
+    #[case("sha256=00")]
+    #[case("sha256=zz")]
+    #[case("")]
+    fn hmac_rejected(#[case] signature: &str) {
+        let route = WebhookRoute::from_source(&config(HUMBLE), "humble")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            route.check_request(&request(("X-Signature", signature), BODY)),
+            Err(StatusCode::FORBIDDEN)
+        );
+    }
+
+    #[rstest]
+    fn hmac_verified() {
+        let route = WebhookRoute::from_source(&config(HUMBLE), "humble")
+            .unwrap()
+            .unwrap();
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"It's a Secret to Everybody").unwrap();
+        mac.update(BODY.as_bytes());
+        let signature: String = mac
+            .finalize()
+            .into_bytes()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        let payload = route
+            .check_request(&request(
+                ("X-Signature", &format!("sha256={signature}")),
+                BODY,
+            ))
+            .unwrap();
+        assert_eq!(
+            route.event_id(&payload),
+            Some("webhook:humble:A1".to_string())
+        );
+        assert_eq!(
+            route.event(payload.clone(), &CurrencyRates::default()),
+            IntegrationEvent::Webhook {
+                source: "humble".to_string(),
+                amount: 1250,
+                currency: "USD".to_string(),
+                usd_cents: Some(1250),
+                user: "Cool User".to_string(),
+                message: String::new(),
+                payload,
+            }
+        );
+    }
+
+    #[rstest]
+    #[case("abc", true)]
+    #[case("abd", false)]
+    fn token_header(#[case] token: &str, #[case] expected: bool) {
+        let config = config(
+            r#"
+            [webhooks.tips]
+            verification = "Token"
+            token = "abc"
+            token_header = "X-Token"
+            "#,
+        );
+        let route = WebhookRoute::from_source(&config, "tips").unwrap().unwrap();
+        assert_eq!(
+            route
+                .check_request(&request(("X-Token", token), "{}"))
+                .is_ok(),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case("[webhooks.tips]\ntoken = \"abc\"")]
+    #[case("[webhooks.tips]\nverification = \"Token\"\ntoken_header = \"X-Token\"")]
+    #[case("[webhooks.tips]\nverification = \"Token\"\ntoken = \"abc\"")]
+    #[case("[webhooks.tips]\nverification = \"Hmac\"\nsecret = \"abc\"")]
+    #[case("[webhooks.tips]\nverification = \"None\"\n[webhooks.tips.fields]\nname = \"$.name\"")]
+    #[case("[webhooks.tips]\npreset = \"Tipeee\"")]
+    fn invalid_config(#[case] toml: &str) {
+        assert!(WebhookRoute::from_source(&config(toml), "tips").is_err());
+    }
+
+    #[rstest]
+    #[case("missing")]
+    #[case("../tips")]
+    #[case("")]
+    fn no_route(#[case] source: &str) {
+        let config = config("[webhooks.tips]\nverification = \"None\"");
+        assert_eq!(WebhookRoute::from_source(&config, source).unwrap(), None);
+    }
+}
@@ -0,0 +1,131 @@
+//! Routes for known services, only missing their token or secret.
+use anyhow::{bail, Result};
+
+use super::{FieldMapping, Verification, WebhookRoute};
+
+pub const PRESETS: [&str; 1] = ["Ko-fi"];
+
+/// The preset by name, ignoring case, spaces and dashes.
+pub fn preset(name: &str, source: &str) -> Result<WebhookRoute> {
+    match name.to_lowercase().replace([' ', '-', '_'], "").as_str() {
+        "kofi" => Ok(kofi(source)),
+        _ => bail!("Unknown webhook preset {name}, expected one of {PRESETS:?}"),
+    }
+}
+
+/// [Ko-fi](https://ko-fi.com/manage/webhooks) posts a form with the JSON in
+/// `data`, holding the verification token. Donations, subscriptions, commissions
+/// and shop orders all come this way, their `type` tells them apart.
+pub fn kofi(source: &str) -> WebhookRoute {
+    let path = |field: &str| Some(format!("$.{field}"));
+    WebhookRoute {
+        source: source.to_string(),
+        verification: Verification::Token {
+            token: String::new(),
+            header: None,
+            path: path("verification_token"),
+        },
+        form_field: Some("data".to_string()),
+        fields: FieldMapping {
+            amount: path("amount"),
+            currency: path("currency"),
+            user: path("from_name"),
+            message: path("message"),
+            public: path("is_public"),
+            id: path("kofi_transaction_id"),
+        },
+        currency: "USD".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::{currency::CurrencyRates, IntegrationEvent};
+    use config::Config;
+    use http::{Request, StatusCode};
+    use rstest::rstest;
+
+    const TOKEN: &str = "8c5bc1a6-9d4b-4ad2-a1b7-7d3f0a9b2c41";
+
+    /// Ko-fi's example webhook.
+    fn data(is_public: bool) -> String {
+        format!(
+            r#"{{"verification_token":"{TOKEN}","message_id":"3a1fac0c-f960-4506-a60e-824979a74e74","timestamp":"2017-08-21T13:04:30.7296166Z","type":"Donation","is_public":{is_public},"from_name":"Ko-fi Team","message":"Good luck with the integration!","amount":"3.00","url":"https://ko-fi.com/Home/CoffeeShop?txid=00000000-1111-2222-3333-444444444444","email":"someone@example.com","currency":"EUR","is_subscription_payment":false,"is_first_subscription_payment":false,"kofi_transaction_id":"00000000-1111-2222-3333-444444444444","shop_items":null,"tier_name":null,"shipping":null}}"#
+        )
+    }
+
+    fn request(data: &str) -> Request<Vec<u8>> {
+        let body = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("data", data)
+            .finish();
+        Request::builder()
+            .method("POST")
+            .uri("/webhook/kofi")
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(body.into_bytes())
+            .unwrap()
+    }
+
+    fn route(token: &str) -> WebhookRoute {
+        let config = Config::builder()
+            .set_override("webhooks.kofi.preset", "Ko-fi")
+            .unwrap()
+            .set_override("webhooks.kofi.token", token)
+            .unwrap()
+            .build()
+            .unwrap();
+        WebhookRoute::from_source(&config, "kofi").unwrap().unwrap()
+    }
+
+    #[rstest]
+    #[case(true, "Good luck with the integration!")]
+    #[case(false, "")]
+    fn kofi_donation(#[case] is_public: bool, #[case] message: &str) {
+        let route = route(TOKEN);
+        let payload = route.check_request(&request(&data(is_public))).unwrap();
+        assert_eq!(
+            route.event_id(&payload),
+            Some("webhook:kofi:00000000-1111-2222-3333-444444444444".to_string())
+        );
+        let IntegrationEvent::Webhook {
+            source,
+            amount,
+            currency,
+            usd_cents,
+            user,
+            message: event_message,
+            ..
+        } = route.event(payload, &CurrencyRates::default())
+        else {
+            panic!("Not a webhook event");
+        };
+        assert_eq!(
+            (source.as_str(), amount, currency.as_str(), user.as_str()),
+            ("kofi", 300, "EUR", "Ko-fi Team")
+        );
+        assert_eq!(
+            usd_cents,
+            CurrencyRates::default().to_usd_cents(3_000_000, "EUR")
+        );
+        assert_eq!(event_message, message);
+    }
+
+    #[rstest]
+    fn kofi_wrong_token() {
+        assert_eq!(
+            route("another-token").check_request(&request(&data(true))),
+            Err(StatusCode::FORBIDDEN)
+        );
+    }
+
+    #[rstest]
+    fn kofi_needs_token() {
+        let config = Config::builder()
+            .set_override("webhooks.kofi.preset", "kofi")
+            .unwrap()
+            .build()
+            .unwrap();
+        assert!(WebhookRoute::from_source(&config, "kofi").is_err());
+    }
+}
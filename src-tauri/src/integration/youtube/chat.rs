@@ -0,0 +1,535 @@
+//! Polling `liveChat/messages` of the active broadcast. YouTube has no push
+//! api for live chat, each response says how long to wait before the next
+//! request in `pollingIntervalMillis`.
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Deserializer};
+use tracing::debug;
+use url::Url;
+
+use crate::integration::{
+    currency::CurrencyRates, Chatter, IntegrationEvent, Platform, ViewerRole,
+};
+
+/// Used when a response has no polling interval.
+pub const DEFAULT_POLLING_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, thiserror::Error, miette::Diagnostic)]
+pub enum LiveChatError {
+    #[error("YouTube token expired or revoked")]
+    Unauthorized,
+    #[error("The live chat has ended: {0}")]
+    Ended(String),
+    #[error("YouTube api error {status}: {message}")]
+    Api {
+        status: reqwest::StatusCode,
+        message: String,
+    },
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveChatMessageList {
+    pub next_page_token: Option<String>,
+    pub polling_interval_millis: Option<u64>,
+    #[serde(default)]
+    pub items: Vec<LiveChatMessage>,
+}
+
+impl LiveChatMessageList {
+    pub fn polling_interval(&self) -> Duration {
+        self.polling_interval_millis
+            .map_or(DEFAULT_POLLING_INTERVAL, Duration::from_millis)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveChatMessage {
+    pub id: String,
+    pub snippet: Snippet,
+    pub author_details: AuthorDetails,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorDetails {
+    pub channel_id: String,
+    pub display_name: String,
+    #[serde(default)]
+    pub is_chat_owner: bool,
+    #[serde(default)]
+    pub is_chat_moderator: bool,
+    #[serde(default)]
+    pub is_chat_sponsor: bool,
+}
+
+impl AuthorDetails {
+    /// Members count as subscribers, YouTube has no badge for months.
+    pub fn chatter(&self, message_id: &str) -> Chatter {
+        let roles = [
+            (self.is_chat_owner, ViewerRole::Broadcaster),
+            (self.is_chat_moderator, ViewerRole::Moderator),
+            (self.is_chat_sponsor, ViewerRole::Subscriber),
+        ]
+        .into_iter()
+        .filter_map(|(has_role, role)| has_role.then_some(role))
+        .collect();
+        Chatter {
+            user_id: self.channel_id.clone(),
+            message_id: message_id.to_string(),
+            roles,
+            platform: Platform::YouTube,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snippet {
+    /// e.g. `textMessageEvent` or `superChatEvent`.
+    #[serde(rename = "type")]
+    pub message_type: String,
+    #[serde(default)]
+    pub display_message: String,
+    pub text_message_details: Option<TextMessageDetails>,
+    pub super_chat_details: Option<SuperChatDetails>,
+    pub super_sticker_details: Option<SuperStickerDetails>,
+    pub new_sponsor_details: Option<NewSponsorDetails>,
+    pub member_milestone_chat_details: Option<MemberMilestoneChatDetails>,
+    pub membership_gifting_details: Option<MembershipGiftingDetails>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextMessageDetails {
+    pub message_text: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuperChatDetails {
+    #[serde(deserialize_with = "number_or_string")]
+    pub amount_micros: u64,
+    pub currency: String,
+    #[serde(default)]
+    pub user_comment: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuperStickerDetails {
+    #[serde(deserialize_with = "number_or_string")]
+    pub amount_micros: u64,
+    pub currency: String,
+    pub super_sticker_metadata: SuperStickerMetadata,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuperStickerMetadata {
+    pub sticker_id: String,
+    #[serde(default)]
+    pub alt_text: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewSponsorDetails {
+    #[serde(default)]
+    pub member_level_name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberMilestoneChatDetails {
+    #[serde(default)]
+    pub member_level_name: String,
+    pub member_month: u64,
+    #[serde(default)]
+    pub user_comment: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MembershipGiftingDetails {
+    pub gift_memberships_count: u64,
+    #[serde(default)]
+    pub gift_memberships_level_name: String,
+}
+
+/// Google encodes 64 bit integers as strings.
+fn number_or_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString {
+        Number(u64),
+        String(String),
+    }
+    match NumberOrString::deserialize(deserializer)? {
+        NumberOrString::Number(n) => Ok(n),
+        NumberOrString::String(s) => s.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+/// Maps a chat message to an event, [`None`] for message types without one.
+pub fn event_from_message(
+    message: &LiveChatMessage,
+    rates: &CurrencyRates,
+) -> Option<IntegrationEvent> {
+    let snippet = &message.snippet;
+    let user_name = message.author_details.display_name.clone();
+    match snippet.message_type.as_str() {
+        "textMessageEvent" => Some(IntegrationEvent::Chat {
+            msg: snippet
+                .text_message_details
+                .as_ref()
+                .map_or(snippet.display_message.clone(), |t| t.message_text.clone()),
+            author: user_name,
+            chatter: message.author_details.chatter(&message.id),
+        }),
+        "superChatEvent" => {
+            let details = snippet.super_chat_details.as_ref()?;
+            Some(IntegrationEvent::SuperChat {
+                user_name,
+                amount_micros: details.amount_micros,
+                usd_cents: rates.to_usd_cents(details.amount_micros, &details.currency),
+                currency: details.currency.clone(),
+                message: details.user_comment.clone(),
+                sticker: None,
+            })
+        }
+        "superStickerEvent" => {
+            let details = snippet.super_sticker_details.as_ref()?;
+            Some(IntegrationEvent::SuperChat {
+                user_name,
+                amount_micros: details.amount_micros,
+                usd_cents: rates.to_usd_cents(details.amount_micros, &details.currency),
+                currency: details.currency.clone(),
+                message: String::new(),
+                sticker: Some(details.super_sticker_metadata.alt_text.clone()),
+            })
+        }
+        "newSponsorEvent" => {
+            let details = snippet.new_sponsor_details.as_ref()?;
+            Some(IntegrationEvent::Membership {
+                user_name,
+                level: details.member_level_name.clone(),
+                months: 0,
+                gifts: 0,
+            })
+        }
+        "memberMilestoneChatEvent" => {
+            let details = snippet.member_milestone_chat_details.as_ref()?;
+            Some(IntegrationEvent::Membership {
+                user_name,
+                level: details.member_level_name.clone(),
+                months: details.member_month,
+                gifts: 0,
+            })
+        }
+        "membershipGiftingEvent" => {
+            let details = snippet.membership_gifting_details.as_ref()?;
+            Some(IntegrationEvent::Membership {
+                user_name,
+                level: details.gift_memberships_level_name.clone(),
+                months: 0,
+                gifts: details.gift_memberships_count,
+            })
+        }
+        message_type => {
+            // giftMembershipReceivedEvent is left out, the gifter's event covers it.
+            debug!(
+                "Ignoring YouTube {message_type}: {}",
+                snippet.display_message
+            );
+            None
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LiveBroadcastList {
+    #[serde(default)]
+    items: Vec<LiveBroadcast>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LiveBroadcast {
+    id: String,
+    snippet: LiveBroadcastSnippet,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LiveBroadcastSnippet {
+    live_chat_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiErrorResponse {
+    error: ApiError,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiError {
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    errors: Vec<ApiErrorReason>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiErrorReason {
+    #[serde(default)]
+    reason: String,
+}
+
+/// Reads a page of the live chat at a time, remembering where it stopped.
+#[derive(Debug, Clone)]
+pub struct LiveChat {
+    client: reqwest::Client,
+    api: Url,
+    pub live_chat_id: String,
+    page_token: Option<String>,
+}
+
+impl LiveChat {
+    pub fn new(client: reqwest::Client, api: Url, live_chat_id: String) -> Self {
+        Self {
+            client,
+            api,
+            live_chat_id,
+            page_token: None,
+        }
+    }
+
+    /// Finds the live chat of the signed in channel's active broadcast.
+    pub async fn active(client: reqwest::Client, api: Url, access_token: &str) -> Result<Self> {
+        let url = endpoint(&api, "liveBroadcasts");
+        let response = client
+            .get(url)
+            .query(&[
+                ("part", "snippet"),
+                ("broadcastStatus", "active"),
+                ("broadcastType", "all"),
+            ])
+            .bearer_auth(access_token)
+            .send()
+            .await?;
+        let broadcasts: LiveBroadcastList = check(response).await?.json().await?;
+        let Some(broadcast) = broadcasts.items.into_iter().next() else {
+            bail!("No active YouTube broadcast, set auth.youtube.live_chat_id or go live first");
+        };
+        let Some(live_chat_id) = broadcast.snippet.live_chat_id else {
+            bail!("YouTube broadcast {} has no live chat", broadcast.id);
+        };
+        Ok(Self::new(client, api, live_chat_id))
+    }
+
+    /// Whether a page has been read, the first one holds the chat backlog.
+    pub fn started(&self) -> bool {
+        self.page_token.is_some()
+    }
+
+    /// Reads the messages since the last poll.
+    pub async fn poll(&mut self, access_token: &str) -> Result<LiveChatMessageList, LiveChatError> {
+        let url = endpoint(&self.api, "liveChat/messages");
+        let mut query = vec![
+            ("liveChatId", self.live_chat_id.as_str()),
+            ("part", "snippet,authorDetails"),
+            ("maxResults", "2000"),
+        ];
+        if let Some(page_token) = &self.page_token {
+            query.push(("pageToken", page_token));
+        }
+        let response = self
+            .client
+            .get(url)
+            .query(&query)
+            .bearer_auth(access_token)
+            .send()
+            .await?;
+        let list: LiveChatMessageList = check(response).await?.json().await?;
+        if list.next_page_token.is_some() {
+            self.page_token = list.next_page_token.clone();
+        }
+        Ok(list)
+    }
+}
+
+fn endpoint(api: &Url, path: &str) -> Url {
+    let mut url = api.clone();
+    url.path_segments_mut()
+        .expect("YouTube api url is a base")
+        .pop_if_empty()
+        .extend(path.split('/'));
+    url
+}
+
+async fn check(response: reqwest::Response) -> Result<reqwest::Response, LiveChatError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    if status == reqwest::StatusCode::UNAUTHORIZED {
+        return Err(LiveChatError::Unauthorized);
+    }
+    let error = response.json::<ApiErrorResponse>().await.ok();
+    let reason = error
+        .as_ref()
+        .and_then(|e| e.error.errors.first())
+        .map(|e| e.reason.clone())
+        .unwrap_or_default();
+    let message = error.map(|e| e.error.message).unwrap_or_default();
+    match reason.as_str() {
+        "liveChatEnded" | "liveChatNotFound" | "liveChatDisabled" => {
+            Err(LiveChatError::Ended(reason))
+        }
+        _ => Err(LiveChatError::Api { status, message }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::youtube::tests::{json, mock_server};
+    use http::StatusCode;
+    use rstest::rstest;
+
+    fn message(snippet: serde_json::Value) -> LiveChatMessage {
+        serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "snippet": snippet,
+            "authorDetails": { "channelId": "UC1", "displayName": "Cool_User", "isChatModerator": true }
+        }))
+        .unwrap()
+    }
+
+    #[rstest]
+    #[case(
+        serde_json::json!({
+            "type": "textMessageEvent",
+            "displayMessage": "hello",
+            "textMessageDetails": { "messageText": "hello" }
+        }),
+        Some(IntegrationEvent::Chat { msg: "hello".into(), author: "Cool_User".into(), chatter: Chatter { user_id: "UC1".into(), message_id: "1".into(), roles: vec![ViewerRole::Moderator], platform: Platform::YouTube, ..Default::default() } })
+    )]
+    #[case(
+        serde_json::json!({
+            "type": "superChatEvent",
+            "superChatDetails": { "amountMicros": "10000000", "currency": "EUR", "userComment": "boom", "tier": 2 }
+        }),
+        Some(IntegrationEvent::SuperChat { user_name: "Cool_User".into(), amount_micros: 10_000_000, currency: "EUR".into(), usd_cents: Some(1080), message: "boom".into(), sticker: None })
+    )]
+    #[case(
+        serde_json::json!({
+            "type": "superStickerEvent",
+            "superStickerDetails": { "amountMicros": 2000000, "currency": "USD", "superStickerMetadata": { "stickerId": "s1", "altText": "Dancing cat" } }
+        }),
+        Some(IntegrationEvent::SuperChat { user_name: "Cool_User".into(), amount_micros: 2_000_000, currency: "USD".into(), usd_cents: Some(200), message: String::new(), sticker: Some("Dancing cat".into()) })
+    )]
+    #[case(
+        serde_json::json!({
+            "type": "newSponsorEvent",
+            "newSponsorDetails": { "memberLevelName": "Engineer", "isUpgrade": false }
+        }),
+        Some(IntegrationEvent::Membership { user_name: "Cool_User".into(), level: "Engineer".into(), months: 0, gifts: 0 })
+    )]
+    #[case(
+        serde_json::json!({
+            "type": "memberMilestoneChatEvent",
+            "memberMilestoneChatDetails": { "memberLevelName": "Engineer", "memberMonth": 6 }
+        }),
+        Some(IntegrationEvent::Membership { user_name: "Cool_User".into(), level: "Engineer".into(), months: 6, gifts: 0 })
+    )]
+    #[case(
+        serde_json::json!({
+            "type": "membershipGiftingEvent",
+            "membershipGiftingDetails": { "giftMembershipsCount": 5, "giftMembershipsLevelName": "Engineer" }
+        }),
+        Some(IntegrationEvent::Membership { user_name: "Cool_User".into(), level: "Engineer".into(), months: 0, gifts: 5 })
+    )]
+    #[case(
+        serde_json::json!({
+            "type": "giftMembershipReceivedEvent",
+            "giftMembershipReceivedDetails": { "memberLevelName": "Engineer" }
+        }),
+        None
+    )]
+    fn map_message(#[case] snippet: serde_json::Value, #[case] expected: Option<IntegrationEvent>) {
+        assert_eq!(
+            event_from_message(&message(snippet), &CurrencyRates::default()),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn polls_with_page_token() {
+        let (url, requests) = mock_server(vec![
+            json(serde_json::json!({
+                "items": [{ "id": "broadcast", "snippet": { "liveChatId": "chat" } }]
+            })),
+            json(serde_json::json!({
+                "nextPageToken": "page2",
+                "pollingIntervalMillis": 1500,
+                "items": []
+            })),
+            json(serde_json::json!({
+                "nextPageToken": "page3",
+                "pollingIntervalMillis": 2000,
+                "items": [{
+                    "id": "m1",
+                    "snippet": { "type": "textMessageEvent", "displayMessage": "hi", "textMessageDetails": { "messageText": "hi" } },
+                    "authorDetails": { "channelId": "UC1", "displayName": "Cool_User" }
+                }]
+            })),
+        ])
+        .await;
+        let mut chat = LiveChat::active(reqwest::Client::new(), url, "token")
+            .await
+            .unwrap();
+        assert_eq!(chat.live_chat_id, "chat");
+        assert!(!chat.started());
+
+        let first = chat.poll("token").await.unwrap();
+        assert_eq!(first.polling_interval(), Duration::from_millis(1500));
+        assert!(chat.started());
+        let second = chat.poll("token").await.unwrap();
+        assert_eq!(second.polling_interval(), Duration::from_millis(2000));
+        assert_eq!(second.items[0].id, "m1");
+
+        let requests = requests.await.unwrap();
+        assert_eq!(requests[0].uri().path(), "/liveBroadcasts");
+        assert_eq!(requests[0].headers()["authorization"], "Bearer token");
+        assert_eq!(requests[1].uri().path(), "/liveChat/messages");
+        assert!(!requests[1].uri().query().unwrap().contains("pageToken"));
+        assert!(requests[2]
+            .uri()
+            .query()
+            .unwrap()
+            .contains("pageToken=page2"));
+    }
+
+    #[rstest]
+    #[case(StatusCode::UNAUTHORIZED, "", "Unauthorized")]
+    #[case(StatusCode::FORBIDDEN, "liveChatEnded", "Ended")]
+    #[case(StatusCode::FORBIDDEN, "rateLimitExceeded", "Api")]
+    #[tokio::test]
+    async fn poll_errors(#[case] status: StatusCode, #[case] reason: &str, #[case] expected: &str) {
+        let mut response = json(serde_json::json!({
+            "error": { "code": status.as_u16(), "message": "error", "errors": [{ "reason": reason }] }
+        }));
+        *response.status_mut() = status;
+        let (url, _requests) = mock_server(vec![response]).await;
+        let mut chat = LiveChat::new(reqwest::Client::new(), url, "chat".to_string());
+        let error = chat.poll("token").await.unwrap_err();
+        assert!(format!("{error:?}").starts_with(expected));
+    }
+}
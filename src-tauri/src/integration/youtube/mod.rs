@@ -0,0 +1,303 @@
+//! YouTube Live chat, Super Chats, Super Stickers and memberships.
+//!
+//! Signs in with [`oauth`] and polls the chat of the active broadcast with
+//! [`chat::LiveChat`], passing the events on to the [`crate::command::Runner`].
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use config::Config;
+use time::OffsetDateTime;
+use tokio::sync::mpsc::Sender;
+use tracing::{debug, error, info, instrument, warn};
+use url::Url;
+
+pub mod chat;
+pub mod oauth;
+
+use chat::{event_from_message, LiveChat, LiveChatError};
+use oauth::{refresh_token, YouTubeToken};
+
+use super::{
+    currency::CurrencyRates,
+    dedup,
+    status::{IntegrationError, IntegrationStatus},
+    Api, IntegrationEvent,
+};
+
+pub const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+pub const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+pub const API_URL: &str = "https://www.googleapis.com/youtube/v3";
+
+/// Where to reach Google, `YOUTUBE_API_URL` and `YOUTUBE_TOKEN_URL` point them
+/// at a mock server for testing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YouTubeEndpoints {
+    pub auth: Url,
+    pub token: Url,
+    pub api: Url,
+}
+
+impl Default for YouTubeEndpoints {
+    fn default() -> Self {
+        Self {
+            auth: Url::parse(AUTH_URL).unwrap(),
+            token: Url::parse(TOKEN_URL).unwrap(),
+            api: Url::parse(API_URL).unwrap(),
+        }
+    }
+}
+
+impl YouTubeEndpoints {
+    pub fn from_env() -> Self {
+        let mut endpoints = Self::default();
+        for (var, url) in [
+            ("YOUTUBE_TOKEN_URL", &mut endpoints.token),
+            ("YOUTUBE_API_URL", &mut endpoints.api),
+        ] {
+            if let Ok(value) = std::env::var(var) {
+                match Url::parse(&value) {
+                    Ok(value) => *url = value,
+                    Err(e) => error!("Invalid {var}: {e}"),
+                }
+            }
+        }
+        endpoints
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct YouTubeSettings {
+    pub client_id: String,
+    pub client_secret: String,
+    pub redirect_url: String,
+    /// Chat to read instead of the active broadcast's.
+    pub live_chat_id: Option<String>,
+}
+
+impl YouTubeSettings {
+    /// Reads `auth.youtube`.
+    pub fn from_config(config: &Config) -> Self {
+        let get = |key: &str| {
+            config
+                .get_string(&format!("auth.youtube.{key}"))
+                .unwrap_or_default()
+        };
+        Self {
+            client_id: get("client_id"),
+            client_secret: get("client_secret"),
+            redirect_url: get("redirect_url"),
+            live_chat_id: Some(get("live_chat_id")).filter(|id| !id.is_empty()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct YouTubeApiConnection {
+    settings: YouTubeSettings,
+    endpoints: YouTubeEndpoints,
+    client: reqwest::Client,
+    /// Shared with the chat task, which refreshes it.
+    token: Arc<Mutex<Option<YouTubeToken>>>,
+    chat_joinhandle: Option<tauri::async_runtime::JoinHandle<()>>,
+}
+
+impl YouTubeApiConnection {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            settings: YouTubeSettings::from_config(config),
+            endpoints: YouTubeEndpoints::from_env(),
+            client: reqwest::Client::new(),
+            token: Arc::new(Mutex::new(None)),
+            chat_joinhandle: None,
+        }
+    }
+
+    pub fn token(&self) -> Option<YouTubeToken> {
+        self.token.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub async fn authenticate(&mut self, use_cache: bool) -> Result<YouTubeToken> {
+        let token = oauth::oauth(&self.client, &self.endpoints, &self.settings, use_cache).await?;
+        *self.token.lock().unwrap_or_else(|e| e.into_inner()) = Some(token.clone());
+        Ok(token)
+    }
+
+    /// Signs in and starts polling the live chat, replacing any running poll.
+    #[instrument(skip_all)]
+    pub async fn run(
+        &mut self,
+        config: Config,
+        event_tx: Sender<IntegrationEvent>,
+    ) -> Result<YouTubeToken> {
+        self.settings = YouTubeSettings::from_config(&config);
+        let token = self.authenticate(true).await?;
+        if let Some(jh) = self.chat_joinhandle.take() {
+            jh.abort();
+        }
+        let task = ChatTask {
+            client: self.client.clone(),
+            endpoints: self.endpoints.clone(),
+            settings: self.settings.clone(),
+            token: Arc::clone(&self.token),
+            rates: CurrencyRates::from_config(&config),
+            event_tx,
+        };
+        self.chat_joinhandle = Some(tauri::async_runtime::spawn(async move {
+            if let Err(e) = task.run().await {
+                error!("YouTube live chat stopped: {e:?}");
+            }
+        }));
+        Ok(token)
+    }
+
+    pub fn check_status(&self) -> Result<IntegrationStatus, IntegrationError> {
+        let Some(token) = self.token() else {
+            return Ok(IntegrationStatus::NotStarted);
+        };
+        match &self.chat_joinhandle {
+            Some(jh) if !jh.inner().is_finished() => Ok(IntegrationStatus::Connected {
+                api: Api::YouTube,
+                expires_at: Some(IntegrationStatus::seconds_to(
+                    token.expires_in(OffsetDateTime::now_utc()),
+                )),
+            }),
+            _ => Ok(IntegrationStatus::Disconnected(Api::YouTube)),
+        }
+    }
+}
+
+struct ChatTask {
+    client: reqwest::Client,
+    endpoints: YouTubeEndpoints,
+    settings: YouTubeSettings,
+    token: Arc<Mutex<Option<YouTubeToken>>>,
+    rates: CurrencyRates,
+    event_tx: Sender<IntegrationEvent>,
+}
+
+impl ChatTask {
+    /// The current access token, refreshed first if it has expired.
+    async fn access_token(&self, force_refresh: bool) -> Result<String> {
+        let token = self
+            .token
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+            .context("Not signed in to YouTube")?;
+        if !force_refresh && !token.is_expired(OffsetDateTime::now_utc()) {
+            return Ok(token.access_token);
+        }
+        let token = refresh_token(&self.client, &self.endpoints, &self.settings, &token).await?;
+        *self.token.lock().unwrap_or_else(|e| e.into_inner()) = Some(token.clone());
+        Ok(token.access_token)
+    }
+
+    async fn run(self) -> Result<()> {
+        let access_token = self.access_token(false).await?;
+        let mut chat = match &self.settings.live_chat_id {
+            Some(id) => LiveChat::new(self.client.clone(), self.endpoints.api.clone(), id.clone()),
+            None => {
+                LiveChat::active(
+                    self.client.clone(),
+                    self.endpoints.api.clone(),
+                    &access_token,
+                )
+                .await?
+            }
+        };
+        info!("Reading YouTube live chat {}", chat.live_chat_id);
+        let _ = self.event_tx.send(IntegrationEvent::Connected).await;
+        let mut force_refresh = false;
+        loop {
+            let access_token = self.access_token(force_refresh).await?;
+            let refreshed = std::mem::take(&mut force_refresh);
+            // The first page is the chat before connecting.
+            let backlog = !chat.started();
+            let list = match chat.poll(&access_token).await {
+                Ok(list) => list,
+                Err(LiveChatError::Unauthorized) if !refreshed => {
+                    warn!("YouTube token rejected, refreshing");
+                    force_refresh = true;
+                    continue;
+                }
+                // Retrying can't help when consent was revoked or a scope is missing.
+                Err(e @ LiveChatError::Unauthorized) => {
+                    let _ = self.event_tx.send(IntegrationEvent::Disconnected).await;
+                    return Err(anyhow::Error::from(e)
+                        .context("YouTube rejected the refreshed token, sign in again"));
+                }
+                Err(e @ LiveChatError::Ended(_)) => {
+                    let _ = self.event_tx.send(IntegrationEvent::Disconnected).await;
+                    return Err(e.into());
+                }
+                Err(e) => {
+                    error!("{e}");
+                    tokio::time::sleep(chat::DEFAULT_POLLING_INTERVAL).await;
+                    continue;
+                }
+            };
+            if backlog {
+                debug!("Skipping {} earlier YouTube messages", list.items.len());
+            } else {
+                for message in &list.items {
+                    let id = format!("youtube:{}", message.id);
+                    if dedup::is_seen(&id) {
+                        continue;
+                    }
+                    if let Some(event) = event_from_message(message, &self.rates) {
+                        if self.event_tx.send(event).await.is_err() {
+                            return Ok(());
+                        }
+                    }
+                    dedup::mark_seen(&id);
+                }
+            }
+            tokio::time::sleep(list.polling_interval()).await;
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::localhost::{parse_request, write_response};
+    use http::{Request, Response, StatusCode};
+    use tokio::{io::BufStream, net::TcpListener, task::JoinHandle};
+
+    pub fn settings() -> YouTubeSettings {
+        YouTubeSettings {
+            client_id: "client".to_string(),
+            client_secret: "secret".to_string(),
+            redirect_url: "http://localhost:27935/youtube/register".to_string(),
+            live_chat_id: None,
+        }
+    }
+
+    pub fn json(body: serde_json::Value) -> Response<Vec<u8>> {
+        Response::builder()
+            .status(StatusCode::OK)
+            .header("content-type", "application/json")
+            .body(serde_json::to_vec(&body).unwrap())
+            .unwrap()
+    }
+
+    /// Answers one request per response in order, returns its url and the
+    /// requests it received.
+    pub async fn mock_server(
+        responses: Vec<Response<Vec<u8>>>,
+    ) -> (Url, JoinHandle<Vec<Request<Vec<u8>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let jh = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut stream = BufStream::new(stream);
+                requests.push(parse_request(&mut stream).await.unwrap());
+                write_response(&mut stream, response).await.unwrap();
+            }
+            requests
+        });
+        (url, jh)
+    }
+}
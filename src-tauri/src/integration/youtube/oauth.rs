@@ -0,0 +1,308 @@
+//! Google OAuth for installed apps. The user signs in through the browser and
+//! is redirected back to `auth.youtube.redirect_url` on localhost, the tokens
+//! are cached on disk like the Twitch tokens and refreshed when they expire.
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use cached::{DiskCache, IOCached};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tracing::{debug, error, info, instrument};
+use url::Url;
+
+use super::{YouTubeEndpoints, YouTubeSettings};
+use crate::integration::twitch::oauth::{response_uri, token_cache};
+
+/// Read only access is enough for live chat messages.
+pub const SCOPE: &str = "https://www.googleapis.com/auth/youtube.readonly";
+
+/// Tokens are refreshed this long before they expire.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct YouTubeToken {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    /// Unix timestamp in seconds.
+    pub expires_at: i64,
+}
+
+impl std::fmt::Debug for YouTubeToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("YouTubeToken")
+            .field("refresh_token", &self.refresh_token.is_some())
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
+
+impl YouTubeToken {
+    pub fn expires_in(&self, now: OffsetDateTime) -> Duration {
+        Duration::from_secs((self.expires_at - now.unix_timestamp()).max(0) as u64)
+    }
+
+    pub fn is_expired(&self, now: OffsetDateTime) -> bool {
+        self.expires_in(now) <= EXPIRY_MARGIN
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: i64,
+    refresh_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenErrorResponse {
+    error: String,
+    #[serde(default)]
+    error_description: String,
+}
+
+fn cache() -> DiskCache<String, YouTubeToken> {
+    token_cache("YOUTUBE_OAUTH".to_string())
+}
+
+fn cache_key(settings: &YouTubeSettings) -> String {
+    settings.client_id.clone() + SCOPE
+}
+
+fn store(settings: &YouTubeSettings, token: &YouTubeToken) {
+    match cache().cache_set(cache_key(settings), token.clone()) {
+        Ok(_) => debug!("Stored YouTube token in cache."),
+        Err(e) => error!("Disk Cache failure: {}", e),
+    }
+}
+
+/// The url the user visits to grant access.
+pub fn authorize_url(endpoints: &YouTubeEndpoints, settings: &YouTubeSettings, state: &str) -> Url {
+    let mut url = endpoints.auth.clone();
+    url.query_pairs_mut()
+        .append_pair("client_id", &settings.client_id)
+        .append_pair("redirect_uri", &settings.redirect_url)
+        .append_pair("response_type", "code")
+        .append_pair("scope", SCOPE)
+        .append_pair("access_type", "offline")
+        .append_pair("prompt", "consent")
+        .append_pair("state", state);
+    url
+}
+
+/// Returns the cached token, refreshing it if needed, otherwise signs in
+/// through the browser.
+#[instrument(skip(client, settings))]
+pub async fn oauth(
+    client: &reqwest::Client,
+    endpoints: &YouTubeEndpoints,
+    settings: &YouTubeSettings,
+    use_cache: bool,
+) -> Result<YouTubeToken> {
+    if settings.client_id.is_empty() || settings.client_secret.is_empty() {
+        bail!("auth.youtube.client_id and auth.youtube.client_secret are required");
+    }
+    let cached = if use_cache {
+        match cache().cache_get(&cache_key(settings)) {
+            Ok(token) => token,
+            Err(e) => {
+                error!("DiskCacheError: {:?}", e);
+                None
+            }
+        }
+    } else {
+        info!("Not using cache");
+        None
+    };
+    if let Some(token) = cached {
+        if !token.is_expired(OffsetDateTime::now_utc()) {
+            return Ok(token);
+        }
+        if token.refresh_token.is_some() {
+            match refresh_token(client, endpoints, settings, &token).await {
+                Ok(token) => return Ok(token),
+                Err(e) => error!("Failed to refresh YouTube token: {e}"),
+            }
+        }
+    }
+    debug!(
+        target = "YouTube OAuth",
+        "No cached token found, generating new token."
+    );
+
+    let redirect_url = Url::parse(&settings.redirect_url).context("auth.youtube.redirect_url")?;
+    let response_port = redirect_url.port().unwrap_or(27935);
+    let state = uuid::Uuid::new_v4().to_string();
+    let url = authorize_url(endpoints, settings, &state);
+    println!("Generated OAuth URL: {}", &url);
+    if let Err(e) = webbrowser::open(url.as_str()) {
+        error!("Failed to open the browser: {e}");
+    }
+    let response = Url::parse(&response_uri(response_port).await?)
+        .context("when parsing the input as a URL")?;
+    let map: std::collections::HashMap<_, _> = response.query_pairs().collect();
+    let code = match (map.get("state"), map.get("code"), map.get("error")) {
+        (Some(s), Some(code), _) if s.as_ref() == state => code.to_string(),
+        (_, Some(_), _) => bail!("YouTube OAuth state did not match"),
+        (_, _, Some(error)) => bail!("YouTube OAuth errored with error: {}", error),
+        _ => bail!("invalid url passed"),
+    };
+    let token = request_token(
+        client,
+        &endpoints.token,
+        &[
+            ("code", code.as_str()),
+            ("client_id", &settings.client_id),
+            ("client_secret", &settings.client_secret),
+            ("redirect_uri", &settings.redirect_url),
+            ("grant_type", "authorization_code"),
+        ],
+        None,
+    )
+    .await?;
+    store(settings, &token);
+    Ok(token)
+}
+
+/// Exchanges the refresh token for a new access token and caches it.
+#[instrument(skip(client, settings))]
+pub async fn refresh_token(
+    client: &reqwest::Client,
+    endpoints: &YouTubeEndpoints,
+    settings: &YouTubeSettings,
+    token: &YouTubeToken,
+) -> Result<YouTubeToken> {
+    info!("Refreshing YouTube OAuth Token.");
+    let Some(refresh_token) = &token.refresh_token else {
+        bail!("YouTube token has no refresh token");
+    };
+    let token = request_token(
+        client,
+        &endpoints.token,
+        &[
+            ("refresh_token", refresh_token.as_str()),
+            ("client_id", &settings.client_id),
+            ("client_secret", &settings.client_secret),
+            ("grant_type", "refresh_token"),
+        ],
+        Some(refresh_token),
+    )
+    .await?;
+    store(settings, &token);
+    Ok(token)
+}
+
+/// Google leaves out the refresh token when refreshing, so the previous one is kept.
+async fn request_token(
+    client: &reqwest::Client,
+    token_url: &Url,
+    params: &[(&str, &str)],
+    previous_refresh_token: Option<&String>,
+) -> Result<YouTubeToken> {
+    let body = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(params)
+        .finish();
+    let response = client
+        .post(token_url.clone())
+        .header("content-type", "application/x-www-form-urlencoded")
+        .body(body)
+        .send()
+        .await?;
+    let status = response.status();
+    let text = response.text().await?;
+    if !status.is_success() {
+        match serde_json::from_str::<TokenErrorResponse>(&text) {
+            Ok(e) => bail!(
+                "YouTube token request failed: {} - {}",
+                e.error,
+                e.error_description
+            ),
+            Err(_) => bail!("YouTube token request failed: {status}"),
+        }
+    }
+    let response: TokenResponse = serde_json::from_str(&text)?;
+    Ok(YouTubeToken {
+        access_token: response.access_token,
+        refresh_token: response
+            .refresh_token
+            .or_else(|| previous_refresh_token.cloned()),
+        expires_at: OffsetDateTime::now_utc().unix_timestamp() + response.expires_in,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::youtube::tests::{mock_server, settings};
+    use http::{Response, StatusCode};
+    use rstest::rstest;
+
+    #[rstest]
+    fn authorize_url_requests_offline_access() {
+        let url = authorize_url(&YouTubeEndpoints::default(), &settings(), "state");
+        let map: std::collections::HashMap<_, _> = url.query_pairs().collect();
+        assert_eq!(map["client_id"], "client");
+        assert_eq!(map["access_type"], "offline");
+        assert_eq!(map["scope"], SCOPE);
+        assert_eq!(map["state"], "state");
+    }
+
+    #[rstest]
+    fn token_expiry() {
+        let now = OffsetDateTime::now_utc();
+        let token = YouTubeToken {
+            access_token: String::new(),
+            refresh_token: None,
+            expires_at: now.unix_timestamp() + 3600,
+        };
+        assert!(!token.is_expired(now));
+        assert!(token.is_expired(now + Duration::from_secs(3550)));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn refresh_keeps_refresh_token() {
+        let (url, requests) = mock_server(vec![Response::builder()
+            .status(StatusCode::OK)
+            .body(br#"{"access_token":"new","expires_in":3599,"token_type":"Bearer"}"#.to_vec())
+            .unwrap()])
+        .await;
+        let token = YouTubeToken {
+            access_token: "old".to_string(),
+            refresh_token: Some("refresh".to_string()),
+            expires_at: 0,
+        };
+        let token = request_token(
+            &reqwest::Client::new(),
+            &url.join("token").unwrap(),
+            &[
+                ("refresh_token", "refresh"),
+                ("grant_type", "refresh_token"),
+            ],
+            token.refresh_token.as_ref(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(token.access_token, "new");
+        assert_eq!(token.refresh_token.as_deref(), Some("refresh"));
+        let requests = requests.await.unwrap();
+        assert_eq!(requests[0].uri().path(), "/token");
+        assert_eq!(
+            requests[0].body(),
+            b"refresh_token=refresh&grant_type=refresh_token"
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn token_error_is_reported() {
+        let (url, _requests) = mock_server(vec![Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(br#"{"error":"invalid_grant","error_description":"Bad Request"}"#.to_vec())
+            .unwrap()])
+        .await;
+        let error = request_token(&reqwest::Client::new(), &url, &[], None)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("invalid_grant"));
+    }
+}
@@ -2,8 +2,8 @@
 //!
 //! ## Todo
 //! - [x] Print Rcon connected to server.
-//! - [ ] Raid
-//! - [ ] Cheers
+//! - [x] Raid
+//! - [x] Cheers
 //! - [ ] Gift subs
 //!
 //! ## Classes
@@ -22,7 +22,7 @@
 //!               becomes valid command with default data.
 //! 1. [ ] Read from SteamLabs/streamelements Patreon (own api) and Humble
 //!        notifications and donations.
-//! 1. [ ] Read from YouTube for chat/subs/memberships/supers.
+//! 1. [x] Read from YouTube for chat/subs/memberships/supers.
 //! 1. [x] Have a Pause button or Api end point to pause for bio breaks.
 //!
 //! 1. [x] Have a RCON app/interface that takes in specific Factorio commands as well
@@ -30,7 +30,7 @@
 //! 1. [x] Rcon interface needs to take configurations for any rcon server.
 //! 1. [ ] Ensure that the amount of data is below the max per tick amount.
 //!
-//! 1. [ ] Provide visiual feedback through an OBS overlay (website) to give feedback
+//! 1. [x] Provide visiual feedback through an OBS overlay (website) to give feedback
 //!        on things like the boom factor.
 //!        ![Example of OBS overlay](./docs/Example_visual_feedback.png)
 //! 1. [ ] From twitch events read hype trains and be able to respond.
@@ -82,11 +82,40 @@
 //!
 //! ### YouTube
 //!
-//! Not yet implemented.
+//! 1. Create an OAuth client of type Desktop app with the YouTube Data API v3
+//!     enabled in the [Google Cloud console](https://console.cloud.google.com/apis/credentials).
+//! 2. Add the credentials to auth.youtube, the redirect url only needs a free
+//!     port e.g. `http://localhost:27935/youtube/register`.
+//! 3. Connect from the app, the live chat of the active broadcast is polled for
+//!     chat messages, Super Chats, Super Stickers and memberships.
+//!
+//! ### Streamlabs
+//!
+//! 1. Add the Socket API token from Streamlabs' API Settings to
+//!     auth.streamlabs.socket_token.
+//! 2. Connect from the app, donations match the Donation trigger and other
+//!     alerts such as merch are generic `streamlabs.<type>` events.
 //!
 //! ### Patreon
 //!
-//! Not yet implemented.
+//! 1. Create a webhook for the campaign on the Patreon developer portal and
+//!     forward it to `/webhook/patreon` on the localhost server.
+//! 2. Add its secret to auth.patreon.webhook_secret, pledges match the Pledge
+//!     trigger.
+//!
+//! ### Discord
+//!
+//! 1. Create an application with a bot on the Discord developer portal and
+//!     invite it with the `applications.commands` scope.
+//! 2. Add the bot token to auth.discord.bot_token, and the server id to
+//!     auth.discord.guild_id for the commands to show up straight away.
+//! 3. Connect from the app, each command with a SlashCommand trigger becomes a
+//!     slash command with its variables as options.
+//!
+//! ### Webhooks
+//!
+//! Any other service, e.g. Ko-fi, is a table in `webhooks` received at
+//! `/webhook/<source>` on the localhost server, see [`integration::webhooks`].
 //!
 //! -----
 //!
@@ -129,6 +158,7 @@ pub mod cli;
 pub mod command;
 pub mod game;
 pub mod integration;
+pub mod localhost;
 pub mod logging;
 pub mod servers;
 pub mod settings;
@@ -140,7 +170,9 @@ pub use std::sync::Arc;
 use anyhow::Context;
 use cli::handle_cli_matches;
 use command::settings::ScriptSettings;
-use integration::TwitchApiConnection;
+use integration::{
+    DiscordApiConnection, StreamLabsApiConnection, TwitchApiConnection, YouTubeApiConnection,
+};
 use logging::{LogLevel, Logger};
 pub use miette::Result;
 use serde_json::value;
@@ -252,9 +284,13 @@ pub async fn run() {
                 config.get_table("auth.twitch").unwrap(),
             )));
 
+            let youtube_integration = Arc::new(futures::lock::Mutex::new(
+                YouTubeApiConnection::new(&config),
+            ));
+
             let config_clone = config.clone();
             let twitch_int_clone = Arc::clone(&twitch_integration);
-            let _localhost_port: u16 =
+            let localhost_port: u16 =
                 match config.get_int("localhost_port").unwrap_or(20080).try_into() {
                     Ok(p) => p,
                     Err(_e) => {
@@ -264,6 +300,12 @@ pub async fn run() {
                         20080
                     }
                 };
+            localhost::start(localhost_port, Arc::clone(&twitch_int_clone));
+            integration::feedback::start(Arc::clone(&twitch_int_clone));
+            integration::twitch::chat::start(
+                Arc::clone(&twitch_int_clone),
+                integration::twitch::chat::RateLimiter::from_config(&config),
+            );
             match app.cli().matches() {
                 Ok(matches) => {
                     let mut devtools = false;
@@ -278,6 +320,7 @@ pub async fn run() {
                         matches,
                         app,
                         Arc::clone(&twitch_int_clone),
+                        Arc::clone(&youtube_integration),
                     ));
                     let window = app.get_webview_window("main").unwrap();
                     #[cfg(debug_assertions)] // only include this code on debug builds
@@ -306,6 +349,13 @@ pub async fn run() {
             app.manage(Arc::new(futures::lock::Mutex::new(default_server)));
             app.manage(Arc::clone(&COMMAND_LOGS));
             app.manage(twitch_int_clone);
+            app.manage(youtube_integration);
+            app.manage(Arc::new(futures::lock::Mutex::new(
+                StreamLabsApiConnection::new(),
+            )));
+            app.manage(Arc::new(futures::lock::Mutex::new(
+                DiscordApiConnection::new(),
+            )));
 
             tracing_subscriber::Registry::default()
                 .with(level_filter)
@@ -353,9 +403,41 @@ pub async fn run() {
             integration::connect_to_integration,
             integration::list_of_integrations,
             integration::status::integration_status,
+            integration::metrics::get_metrics,
             integration::twitch::get_channel_point_rewards,
+            integration::twitch::rewards::sync_command_reward,
+            integration::twitch::rewards::set_command_reward,
+            integration::twitch::rewards::create_channel_point_reward,
+            integration::twitch::rewards::set_channel_point_reward_paused,
+            integration::twitch::rewards::set_channel_point_reward_enabled,
+            integration::twitch::rewards::delete_channel_point_reward,
+            integration::twitch::polls::create_poll,
             integration::twitch::refresh_twitch_websocket,
+            integration::streamlabs::profile::import_streamlabs_profile,
             game::latest_game_server_status,
+            command::pause::pause_status,
+            command::pause::buffered_events,
+            command::pause::pause_runner,
+            command::pause::continue_runner,
+            command::pause::approve_buffered_event,
+            command::pause::discard_buffered_event,
+            command::approval::pending_approvals,
+            command::approval::update_approval_variables,
+            command::approval::approve_command,
+            command::approval::reject_command,
+            command::user_policy::blocked_users,
+            command::user_policy::block_user,
+            command::user_policy::unblock_user,
+            command::user_policy::block_command_log_user,
+            command::user_policy::allow_user,
+            command::user_policy::disallow_user,
+            command::quota::quota_usage,
+            command::quota::reset_quota_usage,
+            localhost::overlay::overlay_state,
+            localhost::overlay::set_overlay_meter,
+            localhost::overlay::add_to_overlay_meter,
+            localhost::overlay::start_overlay_countdown,
+            localhost::overlay::clear_overlay_countdown,
             command::command_logs::get_command_logs,
             command::command_logs::resend_command,
             command::command_logs::resend_event,
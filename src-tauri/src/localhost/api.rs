@@ -0,0 +1,192 @@
+//! Control API for stream decks and other tools, all responses are JSON.
+//!
+//! | **Method** | **Path**                      | **Description**                                   |
+//! |------------|-------------------------------|---------------------------------------------------|
+//! | GET        | `/api/pause`                  | [`PauseStatus`] of the runner.                    |
+//! | POST       | `/api/pause`                  | Pause the runner.                                 |
+//! | POST       | `/api/continue`               | Continue the runner.                              |
+//! | GET        | `/api/approvals`              | [`PendingApproval`]s, oldest first.               |
+//! | POST       | `/api/approvals/{id}/approve` | Approve, optionally with `{"variables": {..}}`.   |
+//! | POST       | `/api/approvals/{id}/reject`  | Reject, with `{"refund": true}` to refund points. |
+//! | GET        | `/api/metrics`                | [`MetricsSnapshot`] of the integrations.          |
+//!
+//! Every request needs the [`TOKEN_KEY`] from the config in an
+//! `Authorization: Bearer <token>` header. Requests from web pages other than
+//! the overlays are refused, as a browser always sends their `Origin`.
+use http::{header, Method, Request, Response, StatusCode};
+use indexmap::IndexMap;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::{json_response, text_response, TwitchState};
+use crate::{
+    command::{
+        approval::{self, PendingApproval, APPROVAL_QUEUE},
+        pause::{PauseStatus, PAUSE_STATE},
+    },
+    integration::{
+        metrics::{MetricsSnapshot, METRICS},
+        IntegrationEvent,
+    },
+    settings::Settings,
+};
+
+/// Config key of the token the API requires.
+pub const TOKEN_KEY: &str = "api_token";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ApprovalBody {
+    variables: Option<IndexMap<String, String>>,
+    refund: bool,
+}
+
+pub async fn route(request: Request<Vec<u8>>, twitch: TwitchState, port: u16) -> Response<Vec<u8>> {
+    let token = Settings::current_config().get_string(TOKEN_KEY).ok();
+    if let Some(response) = refusal(&request, port, token.as_deref()) {
+        return response;
+    }
+    let path = request.uri().path().trim_end_matches('/');
+    match (request.method(), path) {
+        (&Method::GET, "/api/pause") => {
+            let status: PauseStatus = PAUSE_STATE.lock().await.status();
+            json_response(StatusCode::OK, &status)
+        }
+        (&Method::POST, "/api/pause") => send_to_runner(twitch, IntegrationEvent::Pause).await,
+        (&Method::POST, "/api/continue") => {
+            send_to_runner(twitch, IntegrationEvent::Continue).await
+        }
+        (&Method::GET, "/api/approvals") => {
+            let pending: Vec<PendingApproval> = APPROVAL_QUEUE.lock().await.pending().to_vec();
+            json_response(StatusCode::OK, &pending)
+        }
+        (&Method::POST, p) if p.starts_with("/api/approvals/") => {
+            decide_approval(p, request.body(), twitch).await
+        }
+        (&Method::GET, "/api/metrics") => {
+            let metrics: MetricsSnapshot = METRICS.snapshot();
+            json_response(StatusCode::OK, &metrics)
+        }
+        _ => text_response(StatusCode::NOT_FOUND, "Not Found"),
+    }
+}
+
+/// Checks the `Origin` and `Authorization` headers, [`None`] when allowed.
+fn refusal(
+    request: &Request<Vec<u8>>,
+    port: u16,
+    token: Option<&str>,
+) -> Option<Response<Vec<u8>>> {
+    if let Some(origin) = request.headers().get(header::ORIGIN) {
+        let own_origins = [
+            format!("http://localhost:{port}"),
+            format!("http://127.0.0.1:{port}"),
+        ];
+        if !own_origins
+            .iter()
+            .any(|o| o.as_bytes() == origin.as_bytes())
+        {
+            return Some(text_response(
+                StatusCode::FORBIDDEN,
+                "Cross origin requests are not allowed",
+            ));
+        }
+    }
+    let Some(token) = token.filter(|t| !t.is_empty()) else {
+        return Some(text_response(
+            StatusCode::FORBIDDEN,
+            format!("Set {TOKEN_KEY} in the config to use the control API"),
+        ));
+    };
+    match request.headers().get(header::AUTHORIZATION) {
+        Some(value) if value.as_bytes() == format!("Bearer {token}").as_bytes() => None,
+        _ => Some(text_response(
+            StatusCode::UNAUTHORIZED,
+            "Missing or wrong api token",
+        )),
+    }
+}
+
+async fn decide_approval(path: &str, body: &[u8], twitch: TwitchState) -> Response<Vec<u8>> {
+    let mut parts = path.trim_start_matches("/api/approvals/").split('/');
+    let (Some(Ok(id)), Some(action), None) = (
+        parts.next().map(Uuid::parse_str),
+        parts.next(),
+        parts.next(),
+    ) else {
+        return text_response(StatusCode::NOT_FOUND, "Not Found");
+    };
+    let body: ApprovalBody = if body.is_empty() {
+        ApprovalBody::default()
+    } else {
+        match serde_json::from_slice(body) {
+            Ok(b) => b,
+            Err(e) => return text_response(StatusCode::BAD_REQUEST, e.to_string()),
+        }
+    };
+    let result = match action {
+        "approve" => approval::approve(id, body.variables).await,
+        "reject" => approval::reject(id, body.refund, &mut *twitch.lock().await).await,
+        _ => return text_response(StatusCode::NOT_FOUND, "Not Found"),
+    };
+    match result {
+        Ok(()) => json_response(StatusCode::OK, &serde_json::json!({"ok": true})),
+        Err(e) => json_response(
+            StatusCode::NOT_FOUND,
+            &serde_json::json!({"ok": false, "error": e.to_string()}),
+        ),
+    }
+}
+
+async fn send_to_runner(twitch: TwitchState, event: IntegrationEvent) -> Response<Vec<u8>> {
+    match twitch.lock().await.send_event_to_runner(event).await {
+        Ok(_) => json_response(StatusCode::ACCEPTED, &serde_json::json!({"ok": true})),
+        Err(e) => json_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            &serde_json::json!({"ok": false, "error": e.to_string()}),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(None, Some("Bearer abc"), Some("abc"), None)]
+    #[case(Some("http://localhost:20080"), Some("Bearer abc"), Some("abc"), None)]
+    #[case(Some("http://127.0.0.1:20080"), Some("Bearer abc"), Some("abc"), None)]
+    #[case(
+        Some("https://example.com"),
+        Some("Bearer abc"),
+        Some("abc"),
+        Some(StatusCode::FORBIDDEN)
+    )]
+    #[case(
+        Some("http://localhost:8080"),
+        Some("Bearer abc"),
+        Some("abc"),
+        Some(StatusCode::FORBIDDEN)
+    )]
+    #[case(None, None, Some("abc"), Some(StatusCode::UNAUTHORIZED))]
+    #[case(None, Some("Bearer abd"), Some("abc"), Some(StatusCode::UNAUTHORIZED))]
+    #[case(None, Some("Bearer "), Some(""), Some(StatusCode::FORBIDDEN))]
+    #[case(None, Some("Bearer abc"), None, Some(StatusCode::FORBIDDEN))]
+    fn authorize_request(
+        #[case] origin: Option<&str>,
+        #[case] authorization: Option<&str>,
+        #[case] token: Option<&str>,
+        #[case] refused: Option<StatusCode>,
+    ) {
+        let mut request = Request::builder().method(Method::POST).uri("/api/pause");
+        if let Some(origin) = origin {
+            request = request.header(header::ORIGIN, origin);
+        }
+        if let Some(authorization) = authorization {
+            request = request.header(header::AUTHORIZATION, authorization);
+        }
+        let request = request.body(Vec::new()).unwrap();
+        assert_eq!(refusal(&request, 20080, token).map(|r| r.status()), refused);
+    }
+}
@@ -0,0 +1,195 @@
+//! Small HTTP server listening on `localhost_port`.
+//!
+//! Serves the OBS browser source overlays and their live feed, see
+//! [`overlay`], and the control [`api`].
+
+use anyhow::{anyhow, bail, Context, Result};
+use http::{Method, Request, Response, StatusCode};
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufStream,
+};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, error, info, instrument};
+
+pub mod api;
+pub mod overlay;
+
+use crate::{
+    integration::{patreon, twitch::webhook, webhooks},
+    Arc, TwitchApiConnection,
+};
+
+pub type TwitchState = Arc<futures::lock::Mutex<TwitchApiConnection>>;
+
+/// Largest request body accepted by the server.
+pub const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Starts the server in the background, binding to `127.0.0.1:port`.
+pub fn start(port: u16, twitch: TwitchState) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = serve(port, twitch).await {
+            error!("Localhost server stopped: {e:?}");
+        }
+    });
+}
+
+#[instrument(skip(twitch))]
+async fn serve(port: u16, twitch: TwitchState) -> Result<()> {
+    let listener_address = format!("127.0.0.1:{port}");
+    let listener = TcpListener::bind(&listener_address)
+        .await
+        .with_context(|| format!("Binding localhost server to {listener_address}"))?;
+    info!("Overlays available at http://localhost:{port}/overlay");
+    loop {
+        let (stream, address) = listener.accept().await?;
+        debug!("Localhost connection from {address}");
+        let twitch = Arc::clone(&twitch);
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = handle_connection(stream, twitch, port).await {
+                debug!("Localhost connection error: {e:?}");
+            }
+        });
+    }
+}
+
+async fn handle_connection(stream: TcpStream, twitch: TwitchState, port: u16) -> Result<()> {
+    let mut stream = BufStream::new(stream);
+    let request = parse_request(&mut stream).await?;
+    debug!("{} {}", request.method(), request.uri());
+
+    if request.method() == Method::GET && request.uri().path() == overlay::FEED_PATH {
+        return overlay::feed(stream, request).await;
+    }
+    let response = route(request, twitch, port).await;
+    write_response(&mut stream, response).await
+}
+
+async fn route(request: Request<Vec<u8>>, twitch: TwitchState, port: u16) -> Response<Vec<u8>> {
+    let path = request.uri().path().trim_end_matches('/').to_string();
+    match (request.method(), path.as_str()) {
+        (&Method::GET, "") => redirect("/overlay"),
+        (&Method::GET, p) if p.starts_with("/overlay") => overlay::route(p),
+        (_, p) if p.starts_with("/api/") => api::route(request, twitch, port).await,
+        (&Method::POST, webhook::WEBHOOK_PATH) => webhook::route(request, twitch).await,
+        (&Method::POST, patreon::WEBHOOK_PATH) => patreon::route(request).await,
+        (&Method::POST, p) if p.starts_with(webhooks::PATH_PREFIX) => {
+            webhooks::route(request, twitch).await
+        }
+        _ => text_response(StatusCode::NOT_FOUND, "Not Found"),
+    }
+}
+
+/// Reads the request line, headers and body of a HTTP/1.1 request.
+pub async fn parse_request(stream: &mut (impl AsyncBufRead + Unpin)) -> Result<Request<Vec<u8>>> {
+    let mut line_buffer = String::new();
+    stream.read_line(&mut line_buffer).await?;
+    let mut parts = line_buffer.split_whitespace();
+    let method = parts.next().ok_or(anyhow!("missing method"))?;
+    let path = parts.next().ok_or(anyhow!("missing path"))?;
+
+    let mut builder = Request::builder().method(method).uri(path);
+    let mut content_length = 0;
+    loop {
+        line_buffer.clear();
+        if stream.read_line(&mut line_buffer).await? == 0 {
+            bail!("connection closed while reading headers");
+        }
+        let line = line_buffer.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            bail!("malformed header: {line}");
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse().context("content-length")?;
+        }
+        builder = builder.header(name, value);
+    }
+    if content_length > MAX_BODY_SIZE {
+        bail!("body too large: {content_length} bytes");
+    }
+    let mut body = vec![0; content_length];
+    stream.read_exact(&mut body).await?;
+    Ok(builder.body(body)?)
+}
+
+/// Writes the response and flushes the stream.
+pub async fn write_response<O: AsyncWrite + Unpin>(
+    stream: &mut O,
+    response: Response<Vec<u8>>,
+) -> Result<()> {
+    let mut head = format!("HTTP/1.1 {}\r\n", response.status());
+    for (k, v) in response.headers() {
+        head += &format!("{}: {}\r\n", k, v.to_str().unwrap_or(""));
+    }
+    head += &format!("content-length: {}\r\n", response.body().len());
+    head += "connection: close\r\n\r\n";
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(response.body()).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+pub fn text_response(status: StatusCode, body: impl Into<String>) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .header("content-type", "text/plain; charset=utf-8")
+        .body(body.into().into_bytes())
+        .unwrap()
+}
+
+pub fn html_response(body: &str) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "text/html; charset=utf-8")
+        .body(body.as_bytes().to_vec())
+        .unwrap()
+}
+
+pub fn json_response<T: serde::Serialize>(status: StatusCode, body: &T) -> Response<Vec<u8>> {
+    match serde_json::to_vec(body) {
+        Ok(body) => Response::builder()
+            .status(status)
+            .header("content-type", "application/json")
+            .body(body)
+            .unwrap(),
+        Err(e) => text_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+pub fn redirect(location: &str) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(StatusCode::FOUND)
+        .header("location", location)
+        .body(Vec::new())
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[tokio::test]
+    async fn parse_post_request() {
+        let raw = b"POST /webhook?x=1 HTTP/1.1\r\nHost: localhost\r\nContent-Length: 5\r\nX-Test: yes\r\n\r\nhello";
+        let mut stream = tokio::io::BufReader::new(&raw[..]);
+        let request = parse_request(&mut stream).await.unwrap();
+        assert_eq!(request.method(), Method::POST);
+        assert_eq!(request.uri().path(), "/webhook");
+        assert_eq!(request.uri().query(), Some("x=1"));
+        assert_eq!(request.headers()["x-test"], "yes");
+        assert_eq!(request.body(), b"hello");
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn parse_truncated_request() {
+        let raw = b"GET / HTTP/1.1\r\nHost: localhost\r\n";
+        let mut stream = tokio::io::BufReader::new(&raw[..]);
+        assert!(parse_request(&mut stream).await.is_err());
+    }
+}
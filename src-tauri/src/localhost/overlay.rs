@@ -0,0 +1,378 @@
+//! OBS browser source overlays.
+//!
+//! Widgets are served from `/overlay/*` and receive [`OverlayMessage`]s as
+//! JSON text frames over the websocket at [`FEED_PATH`]. The first frame is
+//! always a [`OverlayMessage::Snapshot`] so a widget can render immediately.
+use std::{
+    collections::VecDeque,
+    sync::{LazyLock, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Result;
+use futures::{SinkExt, StreamExt};
+use http::{Request, StatusCode};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncWriteExt, BufStream},
+    net::TcpStream,
+    sync::broadcast::{self, error::RecvError},
+};
+use tokio_tungstenite::{
+    tungstenite::{handshake::derive_accept_key, protocol::Role, Message},
+    WebSocketStream,
+};
+use tracing::debug;
+
+use super::{html_response, json_response, text_response, write_response};
+use crate::command::command_logs::CommandLog;
+
+pub const FEED_PATH: &str = "/overlay/ws";
+
+/// Number of triggers kept for the recent triggers widget.
+pub const RECENT_TRIGGERS: usize = 20;
+
+pub static OVERLAY: LazyLock<Overlay> = LazyLock::new(Overlay::new);
+
+/// A fired command as shown on the overlay.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OverlayTrigger {
+    /// Milliseconds since the unix epoch.
+    pub time: u64,
+    pub command: String,
+    pub server: String,
+    pub trigger: String,
+    pub username: String,
+    pub message: Option<String>,
+}
+
+impl From<&CommandLog> for OverlayTrigger {
+    fn from(log: &CommandLog) -> Self {
+        Self {
+            time: unix_millis(log.time()),
+            command: log.command().name.clone(),
+            server: log.trigger().server().name.clone(),
+            trigger: log.trigger().trigger.name().to_string(),
+            username: log.username().clone(),
+            message: log.message().map(String::from),
+        }
+    }
+}
+
+/// A progress bar, e.g. the boom factor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Meter {
+    pub label: String,
+    pub value: f64,
+    pub max: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Countdown {
+    pub label: String,
+    /// Milliseconds since the unix epoch.
+    pub ends_at: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OverlayState {
+    /// Oldest first.
+    pub recent: VecDeque<OverlayTrigger>,
+    pub meters: IndexMap<String, Meter>,
+    pub countdowns: IndexMap<String, Countdown>,
+    pub paused: bool,
+}
+
+/// Messages sent to the overlay widgets.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum OverlayMessage {
+    /// Full state, sent when a widget connects.
+    Snapshot(OverlayState),
+    /// A command was fired.
+    Trigger(OverlayTrigger),
+    Meter {
+        name: String,
+        meter: Meter,
+    },
+    /// `countdown` is [`None`] when cleared.
+    Countdown {
+        name: String,
+        countdown: Option<Countdown>,
+    },
+    Pause {
+        paused: bool,
+    },
+}
+
+/// Holds the overlay state and broadcasts every change to connected widgets.
+#[derive(Debug)]
+pub struct Overlay {
+    state: Mutex<OverlayState>,
+    tx: broadcast::Sender<OverlayMessage>,
+}
+
+impl Overlay {
+    pub fn new() -> Self {
+        let (tx, _rx) = broadcast::channel(100);
+        Self {
+            state: Mutex::new(OverlayState::default()),
+            tx,
+        }
+    }
+
+    /// Applies the message to the state and sends it to all widgets.
+    pub fn publish(&self, message: OverlayMessage) {
+        let mut state = self.state.lock().unwrap();
+        match &message {
+            OverlayMessage::Snapshot(snapshot) => *state = snapshot.clone(),
+            OverlayMessage::Trigger(trigger) => {
+                state.recent.push_back(trigger.clone());
+                while state.recent.len() > RECENT_TRIGGERS {
+                    state.recent.pop_front();
+                }
+            }
+            OverlayMessage::Meter { name, meter } => {
+                state.meters.insert(name.clone(), meter.clone());
+            }
+            OverlayMessage::Countdown {
+                name,
+                countdown: Some(countdown),
+            } => {
+                state.countdowns.insert(name.clone(), countdown.clone());
+            }
+            OverlayMessage::Countdown {
+                name,
+                countdown: None,
+            } => {
+                state.countdowns.shift_remove(name);
+            }
+            OverlayMessage::Pause { paused } => state.paused = *paused,
+        }
+        // Only fails when no widget is connected.
+        let _ = self.tx.send(message);
+    }
+
+    pub fn state(&self) -> OverlayState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Current state and a receiver for every following change.
+    pub fn subscribe(&self) -> (OverlayState, broadcast::Receiver<OverlayMessage>) {
+        let state = self.state.lock().unwrap();
+        (state.clone(), self.tx.subscribe())
+    }
+
+    pub fn record_trigger(&self, log: &CommandLog) {
+        self.publish(OverlayMessage::Trigger(log.into()));
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.publish(OverlayMessage::Pause { paused });
+    }
+
+    pub fn set_meter(&self, name: impl Into<String>, meter: Meter) {
+        self.publish(OverlayMessage::Meter {
+            name: name.into(),
+            meter,
+        });
+    }
+
+    /// Adds to the meter's value, clamped between 0 and its max. Returns the new
+    /// meter or [`None`] if there is no meter with that name.
+    pub fn add_to_meter(&self, name: &str, amount: f64) -> Option<Meter> {
+        // Held until sent so concurrent additions apply one after another.
+        let mut state = self.state.lock().unwrap();
+        let meter = state.meters.get_mut(name)?;
+        meter.value = (meter.value + amount).clamp(0.0, meter.max);
+        let meter = meter.clone();
+        let _ = self.tx.send(OverlayMessage::Meter {
+            name: name.to_string(),
+            meter: meter.clone(),
+        });
+        Some(meter)
+    }
+
+    /// Starts, or restarts, the countdown.
+    pub fn start_countdown(&self, name: impl Into<String>, label: String, duration: Duration) {
+        let countdown = Countdown {
+            label,
+            ends_at: unix_millis(SystemTime::now() + duration),
+        };
+        self.publish(OverlayMessage::Countdown {
+            name: name.into(),
+            countdown: Some(countdown),
+        });
+    }
+
+    pub fn clear_countdown(&self, name: impl Into<String>) {
+        self.publish(OverlayMessage::Countdown {
+            name: name.into(),
+            countdown: None,
+        });
+    }
+}
+
+impl Default for Overlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Static pages and the JSON state.
+pub fn route(path: &str) -> http::Response<Vec<u8>> {
+    match path {
+        "/overlay" => html_response(include_str!("widgets/index.html")),
+        "/overlay/recent" => html_response(include_str!("widgets/recent.html")),
+        "/overlay/progress" => html_response(include_str!("widgets/progress.html")),
+        "/overlay/countdown" => html_response(include_str!("widgets/countdown.html")),
+        "/overlay/overlay.js" => http::Response::builder()
+            .header("content-type", "text/javascript; charset=utf-8")
+            .body(include_bytes!("widgets/overlay.js").to_vec())
+            .unwrap(),
+        "/overlay/state" => json_response(StatusCode::OK, &OVERLAY.state()),
+        _ => text_response(StatusCode::NOT_FOUND, "Not Found"),
+    }
+}
+
+/// Upgrades the connection to a websocket and streams [`OverlayMessage`]s.
+pub async fn feed(mut stream: BufStream<TcpStream>, request: Request<Vec<u8>>) -> Result<()> {
+    let Some(key) = request.headers().get("sec-websocket-key") else {
+        let response = text_response(StatusCode::BAD_REQUEST, "Expected a websocket upgrade");
+        return write_response(&mut stream, response).await;
+    };
+    let handshake = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nupgrade: websocket\r\nconnection: Upgrade\r\nsec-websocket-accept: {}\r\n\r\n",
+        derive_accept_key(key.as_bytes())
+    );
+    stream.write_all(handshake.as_bytes()).await?;
+    stream.flush().await?;
+    let mut ws = WebSocketStream::from_raw_socket(stream.into_inner(), Role::Server, None).await;
+
+    let (snapshot, mut rx) = OVERLAY.subscribe();
+    send(&mut ws, &OverlayMessage::Snapshot(snapshot)).await?;
+    loop {
+        tokio::select! {
+            message = rx.recv() => match message {
+                Ok(message) => send(&mut ws, &message).await?,
+                Err(RecvError::Lagged(skipped)) => {
+                    debug!("Overlay feed lagged by {skipped} messages, resending snapshot.");
+                    send(&mut ws, &OverlayMessage::Snapshot(OVERLAY.state())).await?;
+                }
+                Err(RecvError::Closed) => break,
+            },
+            incoming = ws.next() => match incoming {
+                Some(Ok(Message::Close(_))) | None => break,
+                Some(Err(e)) => return Err(e.into()),
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    Ok(())
+}
+
+async fn send(ws: &mut WebSocketStream<TcpStream>, message: &OverlayMessage) -> Result<()> {
+    ws.send(Message::text(serde_json::to_string(message)?))
+        .await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn overlay_state() -> OverlayState {
+    OVERLAY.state()
+}
+
+#[tauri::command]
+pub fn set_overlay_meter(name: String, label: String, value: f64, max: f64) {
+    OVERLAY.set_meter(name, Meter { label, value, max });
+}
+
+#[tauri::command]
+pub fn add_to_overlay_meter(name: String, amount: f64) -> Result<Meter, String> {
+    OVERLAY
+        .add_to_meter(&name, amount)
+        .ok_or(format!("No overlay meter named {name}"))
+}
+
+#[tauri::command]
+pub fn start_overlay_countdown(name: String, label: String, seconds: u64) {
+    OVERLAY.start_countdown(name, label, Duration::from_secs(seconds));
+}
+
+#[tauri::command]
+pub fn clear_overlay_countdown(name: String) {
+    OVERLAY.clear_countdown(name);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn meter_is_clamped() {
+        let overlay = Overlay::new();
+        assert!(overlay.add_to_meter("boom", 1.0).is_none());
+        overlay.set_meter(
+            "boom",
+            Meter {
+                label: "Boom".into(),
+                value: 8.0,
+                max: 10.0,
+            },
+        );
+        assert_eq!(overlay.add_to_meter("boom", 5.0).unwrap().value, 10.0);
+        assert_eq!(overlay.add_to_meter("boom", -20.0).unwrap().value, 0.0);
+    }
+
+    #[rstest]
+    fn concurrent_additions_are_kept() {
+        let overlay = Overlay::new();
+        overlay.set_meter(
+            "boom",
+            Meter {
+                label: "Boom".into(),
+                value: 0.0,
+                max: 1000.0,
+            },
+        );
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        overlay.add_to_meter("boom", 1.0);
+                    }
+                });
+            }
+        });
+        assert_eq!(overlay.state().meters["boom"].value, 400.0);
+    }
+
+    #[rstest]
+    fn subscribers_receive_changes() {
+        let overlay = Overlay::new();
+        let (snapshot, mut rx) = overlay.subscribe();
+        assert!(!snapshot.paused);
+        overlay.set_paused(true);
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            OverlayMessage::Pause { paused: true }
+        );
+        assert!(overlay.state().paused);
+        overlay.clear_countdown("boom");
+        assert!(overlay.state().countdowns.is_empty());
+    }
+
+    #[rstest]
+    fn protocol_is_tagged() {
+        let json = serde_json::to_value(OverlayMessage::Pause { paused: true }).unwrap();
+        assert_eq!(json, serde_json::json!({"type": "Pause", "paused": true}));
+    }
+}
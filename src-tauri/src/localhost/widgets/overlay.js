@@ -0,0 +1,17 @@
+// Connects to the RCON2.0 overlay feed and reconnects when the app restarts.
+//
+// `onMessage` is called with every parsed message, starting with a
+// `Snapshot` of the current overlay state.
+function rconOverlay(onMessage) {
+  const url = `ws://${location.host}/overlay/ws`;
+  const connect = () => {
+    const ws = new WebSocket(url);
+    ws.onmessage = (event) => onMessage(JSON.parse(event.data));
+    ws.onclose = () => setTimeout(connect, 2000);
+  };
+  connect();
+}
+
+function rconParam(name, fallback) {
+  return new URLSearchParams(location.search).get(name) ?? fallback;
+}
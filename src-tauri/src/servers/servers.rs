@@ -15,6 +15,7 @@ use tracing::{debug, error, info, instrument, trace};
 
 use crate::{
     command::command_logs::{CommandLog, COMMAND_LOGS},
+    integration::feedback::{self, Feedback},
     AsyncMutex,
 };
 use crate::{
@@ -72,6 +73,8 @@ pub struct GameServerConnected {
     pub server: GameServer,
     pub connection: Connection<TcpStream>,
     pub channel: Channel<ServerStatus>,
+    /// Whether the last check reached the server, its rewards are paused while not.
+    pub responding: bool,
 }
 
 impl std::fmt::Debug for GameServerConnected {
@@ -111,6 +114,7 @@ impl GameServerConnected {
                     server: server.clone(),
                     channel,
                     connection,
+                    responding: true,
                 };
                 info!("Connected to server: {}", &server.name);
 
@@ -129,6 +133,10 @@ impl GameServerConnected {
                         .await
                         .insert(server.clone(), gameserverconnected);
                 }
+                feedback::send(Feedback::ServerConnection {
+                    server: server.clone(),
+                    connected: true,
+                });
                 Ok(server)
             }
             Err(e) => {
@@ -178,6 +186,10 @@ impl GameServerConnected {
                 let _ = channel.send(ServerStatus::Disconnected {
                     server: Some(s.clone()),
                 });
+                feedback::send(Feedback::ServerConnection {
+                    server: s.clone(),
+                    connected: false,
+                });
 
                 Ok(s)
             }
@@ -628,7 +640,11 @@ pub async fn send_command_to_server(
     mut command: Command,
     command_logs: State<'_, Arc<AsyncMutex<CommandLogs>>>,
 ) -> Result<String, String> {
-    let log = CommandLog::from_server(&command, &server);
+    let mut log = CommandLog::from_server(&command, &server);
+    if let Some((opened, pick)) = command.open().map_err(|e| e.to_string())? {
+        command = opened.clone();
+        log = log.with_pick(opened, pick);
+    }
     COMMAND_LOGS.lock().await.add_log(log);
     trace!("send_command_to_server");
     let mut connections = CONNECTIONS.lock().await;
@@ -654,13 +670,21 @@ pub async fn send_command_to_server(
 /// TODO: Check by sending a print command
 pub async fn check_connection(server: GameServer) -> ServerStatus {
     trace!("check_connection");
-    let connections = CONNECTIONS.lock().await;
+    let mut connections = CONNECTIONS.lock().await;
     trace!("CONNECTIONS Locked");
-    let conn = connections.get(&server);
+    let conn = connections.get_mut(&server);
     let status = match conn {
         Some(c) => {
             let status = c.handshake().await;
             let _ = c.channel.send(status.clone());
+            let responding = matches!(status, ServerStatus::Connected { .. });
+            if responding != c.responding {
+                c.responding = responding;
+                feedback::send(Feedback::ServerConnection {
+                    server: server.clone(),
+                    connected: responding,
+                });
+            }
             status
         }
         None => ServerStatus::Disconnected {
@@ -314,10 +314,23 @@ impl Default for Settings {
                 ),
                 ("auth.youtube.username", ""),
                 ("auth.youtube.api_token", ""),
+                ("auth.youtube.client_id", ""),
+                ("auth.youtube.client_secret", ""),
+                (
+                    "auth.youtube.redirect_url",
+                    "http://localhost:27935/youtube/register",
+                ),
+                ("auth.youtube.live_chat_id", ""),
+                ("auth.streamlabs.socket_token", ""),
+                ("auth.patreon.webhook_secret", ""),
+                ("auth.discord.bot_token", ""),
+                ("auth.discord.guild_id", ""),
                 ("servers.default", ""),
                 ("log_folder", default_log_path.to_str().unwrap()),
                 ("script_folder", default_script_path.to_str().unwrap()),
                 ("max_log_level", "Info"),
+                ("runner.pause_policy", "Replay"),
+                ("auth.twitch.transport", "Websocket"),
             ],
             game::settings::GameSettings::default_settings_str(),
         ]
@@ -332,7 +345,12 @@ impl Default for Settings {
             ("debug", false),
             ("show_logs", true),
             ("auth.twitch.auto_connect", true),
+            ("auth.twitch.conduit", false),
             ("auth.youtube.auto_connect", true),
+            ("auth.streamlabs.auto_connect", true),
+            ("auth.streamlabs.platform_events", false),
+            ("auth.patreon.auto_connect", true),
+            ("auth.discord.auto_connect", true),
         ];
 
         builder = Settings::default_loop(builder, default_settings_bool);
@@ -352,7 +370,11 @@ impl Default for Settings {
         ];
         builder = Settings::default_loop(builder, default_settings_list_str);
 
-        let default_settings_int: Vec<DefaultValue<u16>> = vec![("localhost_port", 20080)];
+        let default_settings_int: Vec<DefaultValue<u16>> = vec![
+            ("localhost_port", 20080),
+            ("runner.replay_spacing_ms", 0),
+            ("auth.twitch.chat_rate_limit", 20),
+        ];
         builder = Settings::default_loop(builder, default_settings_int);
 
         let settings = Self {
@@ -0,0 +1,184 @@
+use config::Config;
+use futures::{SinkExt, StreamExt};
+use rcon2_lib::{
+    command::{Command, GameServerTrigger, Prefix, RconCommand, RconCommandLua, Trigger, Variable},
+    integration::{DiscordApiConnection, IntegrationEvent},
+    localhost::{parse_request, text_response, write_response},
+    servers::GameServer,
+};
+use serde_json::{json, Value};
+
+use http::StatusCode;
+use rstest::rstest;
+use tokio::{
+    io::BufStream,
+    net::TcpListener,
+    sync::mpsc::{channel, Receiver},
+};
+use tokio_tungstenite::tungstenite::Message;
+
+const TOKEN: &str = "bot-token";
+const MODERATOR: &str = "1111";
+
+/// The method, path and body of each request to the fake REST api.
+async fn rest() -> (String, Receiver<(String, String, Value)>) {
+    let (request_tx, request_rx) = channel(10);
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = format!("http://{}/api/v10", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufStream::new(stream);
+            let request = parse_request(&mut stream).await.unwrap();
+            assert_eq!(request.headers()["Authorization"], format!("Bot {TOKEN}"));
+            let path = request.uri().path().trim_start_matches("/api/v10");
+            let body = serde_json::from_slice(request.body()).unwrap_or_default();
+            request_tx
+                .send((request.method().to_string(), path.to_string(), body))
+                .await
+                .unwrap();
+            let response = text_response(StatusCode::OK, "{}");
+            write_response(&mut stream, response).await.unwrap();
+        }
+    });
+    (address, request_rx)
+}
+
+fn dispatch(name: &str, sequence: u64, data: Value) -> Message {
+    Message::text(json!({ "op": 0, "t": name, "s": sequence, "d": data }).to_string())
+}
+
+fn interaction(id: &str, roles: &[&str]) -> Value {
+    json!({
+        "id": id,
+        "application_id": "1",
+        "type": 2,
+        "token": format!("token-{id}"),
+        "guild_id": "99",
+        "data": {
+            "name": "spawn",
+            "options": [{ "name": "count", "type": 4, "value": 5 }]
+        },
+        "member": {
+            "nick": "Cool User",
+            "roles": roles,
+            "user": { "id": "42", "username": "cool_user" }
+        }
+    })
+}
+
+/// Identifies the bot, then sends a command from a moderator and one from a
+/// member without the role.
+async fn gateway() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = format!(
+        "ws://{}/?v=10&encoding=json",
+        listener.local_addr().unwrap()
+    );
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+        let hello = json!({ "op": 10, "d": { "heartbeat_interval": 45000 } });
+        socket.send(Message::text(hello.to_string())).await.unwrap();
+        let identify: Value =
+            serde_json::from_str(socket.next().await.unwrap().unwrap().to_text().unwrap()).unwrap();
+        assert_eq!(identify["op"], 2);
+        assert_eq!(identify["d"]["token"], TOKEN);
+        let ready =
+            json!({ "v": 10, "user": { "username": "rcon2" }, "application": { "id": "1" } });
+        for message in [
+            dispatch("READY", 1, ready),
+            dispatch("INTERACTION_CREATE", 2, interaction("3", &[MODERATOR])),
+            dispatch("INTERACTION_CREATE", 3, interaction("4", &["2222"])),
+        ] {
+            socket.send(message).await.unwrap();
+        }
+        // Keep the connection open until the test ends.
+        while let Some(Ok(_)) = socket.next().await {}
+    });
+    address
+}
+
+fn command() -> Command {
+    let rcon_lua = RconCommand {
+        prefix: Prefix::SC,
+        lua_command: RconCommandLua::Inline("game.print(COUNT)".to_string()),
+        variables: Variable::from_config("COUNT:int=1").unwrap(),
+    };
+    let mut server_trigger = GameServerTrigger::new(
+        GameServer::default(),
+        Trigger::SlashCommand {
+            name: "spawn".to_string(),
+            roles: vec![MODERATOR.to_string()],
+        },
+    );
+    server_trigger.enable();
+    Command::from_config("Spawn", rcon_lua, vec![server_trigger])
+}
+
+#[rstest]
+#[tokio::test]
+async fn slash_command_round_trip() {
+    let (api_url, mut requests) = rest().await;
+    std::env::set_var("DISCORD_API_URL", api_url);
+    std::env::set_var("DISCORD_GATEWAY_URL", gateway().await);
+    let config = Config::builder()
+        .set_override("auth.discord.bot_token", TOKEN)
+        .unwrap()
+        .set_override("auth.discord.guild_id", "99")
+        .unwrap()
+        .build()
+        .unwrap();
+    let (event_tx, mut event_rx) = channel(10);
+    let mut discord = DiscordApiConnection::new();
+    discord
+        .run(config, vec![command()], event_tx)
+        .await
+        .unwrap();
+
+    let (method, path, body) = requests.recv().await.unwrap();
+    assert_eq!(
+        (method.as_str(), path.as_str()),
+        ("PUT", "/applications/1/guilds/99/commands")
+    );
+    assert_eq!(body[0]["name"], "spawn");
+    assert_eq!(body[0]["options"][0]["name"], "count");
+    assert_eq!(body[0]["options"][0]["type"], 4);
+
+    // The moderator's command is deferred and passed on.
+    let (method, path, body) = requests.recv().await.unwrap();
+    assert_eq!(
+        (method.as_str(), path.as_str(), body["type"].as_u64()),
+        ("POST", "/interactions/3/token-3/callback", Some(5))
+    );
+    assert_eq!(event_rx.recv().await.unwrap(), IntegrationEvent::Connected);
+    let event = event_rx.recv().await.unwrap();
+    assert_eq!(event.username(), "Cool User");
+    assert_eq!(event.values()["COUNT"], "5");
+    assert!(command().server_triggers[0].trigger.is_match(&event));
+
+    // The other member only sees why not.
+    let (method, path, body) = requests.recv().await.unwrap();
+    assert_eq!(
+        (method.as_str(), path.as_str(), body["type"].as_u64()),
+        ("POST", "/interactions/4/token-4/callback", Some(4))
+    );
+    assert_eq!(body["data"]["flags"], 64);
+    assert!(event_rx.try_recv().is_err());
+
+    // No server is connected, so the result is the error.
+    let values = event.values();
+    assert!(command()
+        .send(&GameServer::default(), &event, &values)
+        .await
+        .is_err());
+    let (method, path, body) = requests.recv().await.unwrap();
+    assert_eq!(
+        (method.as_str(), path.as_str()),
+        ("POST", "/webhooks/1/token-3")
+    );
+    assert!(body["content"]
+        .as_str()
+        .unwrap()
+        .starts_with("Spawn failed: "));
+}
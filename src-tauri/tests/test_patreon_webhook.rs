@@ -0,0 +1,114 @@
+use std::path::PathBuf;
+
+use rcon2_lib::{
+    integration::{
+        patreon::{self, PatreonWebhook, WEBHOOK_PATH},
+        IntegrationEvent, PledgeAction,
+    },
+    localhost::{parse_request, write_response},
+};
+
+use rstest::rstest;
+use tokio::{
+    io::BufStream,
+    net::TcpListener,
+    sync::mpsc::{channel, Receiver},
+};
+
+const SECRET: &str = "patreon-webhook-secret";
+
+fn fixture(name: &str) -> Vec<u8> {
+    std::fs::read(PathBuf::from("./tests/fixtures/patreon").join(name)).unwrap()
+}
+
+/// Serves the webhook route like the localhost server, returning its address.
+async fn listen() -> (String, Receiver<IntegrationEvent>) {
+    let (event_tx, event_rx) = channel(10);
+    patreon::set_webhook(Some(PatreonWebhook {
+        secret: SECRET.to_string(),
+        event_tx,
+    }));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = format!("http://{}{WEBHOOK_PATH}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufStream::new(stream);
+            let request = parse_request(&mut stream).await.unwrap();
+            let response = patreon::route(request).await;
+            write_response(&mut stream, response).await.unwrap();
+        }
+    });
+    (address, event_rx)
+}
+
+async fn post(address: &str, event_type: &str, body: Vec<u8>, signature: &str) -> u16 {
+    reqwest::Client::new()
+        .post(address)
+        .header("X-Patreon-Event", event_type)
+        .header("X-Patreon-Signature", signature)
+        .header("Content-Type", "application/json")
+        .body(body)
+        .send()
+        .await
+        .unwrap()
+        .status()
+        .as_u16()
+}
+
+#[rstest]
+#[tokio::test]
+async fn recorded_pledges() {
+    let (address, mut event_rx) = listen().await;
+    for (event_type, file, expected) in [
+        (
+            "members:pledge:create",
+            "members_pledge_create.json",
+            IntegrationEvent::Pledge {
+                action: PledgeAction::Create,
+                tier: "Engineer".to_string(),
+                amount_cents: 500,
+                user: "Cool User".to_string(),
+            },
+        ),
+        (
+            "members:pledge:update",
+            "members_pledge_update.json",
+            IntegrationEvent::Pledge {
+                action: PledgeAction::Update,
+                tier: "Manager".to_string(),
+                amount_cents: 1000,
+                user: "Cool User".to_string(),
+            },
+        ),
+        (
+            "members:pledge:delete",
+            "members_pledge_delete.json",
+            IntegrationEvent::Pledge {
+                action: PledgeAction::Delete,
+                tier: String::new(),
+                amount_cents: 0,
+                user: "Cool User".to_string(),
+            },
+        ),
+    ] {
+        let body = fixture(file);
+        let signature = patreon::signature(SECRET.as_bytes(), &body);
+        assert_eq!(post(&address, event_type, body, &signature).await, 200);
+        assert_eq!(event_rx.recv().await.unwrap(), expected);
+    }
+
+    // Forged and other webhooks don't reach the runner.
+    let body = fixture("members_pledge_create.json");
+    let forged = patreon::signature(b"wrong-secret", &body);
+    assert_eq!(
+        post(&address, "members:pledge:create", body.clone(), &forged).await,
+        403
+    );
+    let signature = patreon::signature(SECRET.as_bytes(), &body);
+    assert_eq!(
+        post(&address, "members:create", body, &signature).await,
+        200
+    );
+    assert!(event_rx.try_recv().is_err());
+}
@@ -76,7 +76,14 @@ export type RconLuaCommand =
       commandType: "File";
       command: { relative_path: string; command?: string };
     }
-  | { commandType: "Inline"; command: string };
+  | { commandType: "Inline"; command: string }
+  | {
+      commandType: "MysteryBox";
+      command: {
+        picks: { command: string; weight: number }[];
+        exclude_last: number;
+      };
+    };
 
 export enum LuaCommandType {
   File = "File",
@@ -105,6 +112,18 @@ export enum TriggerType {
   Subscription = "Subscription",
   GiftSub = "Gift Sub",
   Bits = "Bits",
+  Follow = "Follow",
+  Raid = "Raid",
+  HypeTrain = "HypeTrain",
+  PollResult = "PollResult",
+  JsonPath = "JsonPath",
+  SuperChat = "SuperChat",
+  Membership = "Membership",
+  Donation = "Donation",
+  Value = "Value",
+  Pledge = "Pledge",
+  Webhook = "Webhook",
+  SlashCommand = "SlashCommand",
 }
 
 export type Trigger =
@@ -122,6 +141,71 @@ export type Trigger =
       trigger: TriggerType.Bits;
       data: { bits: number; comaparison_operator: ComparisonOperator };
     }
+  | { trigger: TriggerType.Follow }
+  | {
+      trigger: TriggerType.Raid;
+      data: { viewers: number; comparison_operator: ComparisonOperator };
+    }
+  | {
+      trigger: TriggerType.PollResult;
+      data: { title: string; choice: string };
+    }
+  | {
+      trigger: TriggerType.JsonPath;
+      data: {
+        event_type: string;
+        path: string;
+        comparison_operator: ComparisonOperator;
+        value: string;
+      };
+    }
+  | {
+      trigger: TriggerType.SuperChat;
+      data: { usd_cents: number; comparison_operator: ComparisonOperator };
+    }
+  | { trigger: TriggerType.Membership; data: { level: string } }
+  | {
+      trigger: TriggerType.Donation;
+      data: {
+        amount: number;
+        currency: string;
+        comparison_operator: ComparisonOperator;
+      };
+    }
+  | {
+      trigger: TriggerType.Webhook;
+      data: {
+        source: string;
+        amount: number;
+        comparison_operator: ComparisonOperator;
+      };
+    }
+  | {
+      trigger: TriggerType.SlashCommand;
+      data: { name: string; roles: string[] };
+    }
+  | {
+      trigger: TriggerType.Pledge;
+      data: {
+        tier: string;
+        amount: number;
+        comparison_operator: ComparisonOperator;
+        action?: PledgeAction;
+      };
+    }
+  | {
+      trigger: TriggerType.Value;
+      data: { amount: number; comparison_operator: ComparisonOperator };
+    }
+  | {
+      trigger: TriggerType.HypeTrain;
+      data: {
+        state?: HypeTrainState;
+        level: number;
+        comparison_operator: ComparisonOperator;
+        level_increased: boolean;
+      };
+    }
   | {
       trigger: TriggerType.GiftSub;
       data: {
@@ -132,10 +216,28 @@ export type Trigger =
       };
     };
 
+export type Platform =
+  | "Twitch"
+  | "YouTube"
+  | "Streamlabs"
+  | "Patreon"
+  | "Discord"
+  | "Webhook";
+export type ViewerRole = "Broadcaster" | "Moderator" | "Vip" | "Subscriber";
+export type Chatter = {
+  user_id: string;
+  message_id: string;
+  roles: ViewerRole[];
+  badges: { set_id: string; id: string; info: string }[];
+  sub_months: number;
+  platform: Platform;
+};
 export type GameServerTrigger = {
   server: Server;
   trigger: Trigger;
   enabled: boolean;
+  requires_approval?: boolean;
+  viewer_roles?: ViewerRole[];
 };
 export type IntegrationEvent =
   | { type: "Connected" }
@@ -145,7 +247,7 @@ export type IntegrationEvent =
   | { type: "Pause" }
   | { type: "Continue" }
   | { type: "Update" }
-  | { type: "Chat"; msg: string; author: string }
+  | { type: "Chat"; msg: string; author: string; chatter: Chatter }
   | { type: "Subscription"; tier: string; user_name: string }
   | {
       type: "ChannelPoint";
@@ -156,12 +258,115 @@ export type IntegrationEvent =
       user_name: string;
       message: string;
     }
-  | { type: "GiftSub"; tier: string; user_name?: string; count: number };
+  | { type: "GiftSub"; tier: string; user_name?: string; count: number }
+  | { type: "Bits"; user_name: string; bits: number }
+  | { type: "Follow"; user_name: string }
+  | { type: "Raid"; from: string; viewers: number }
+  | { type: "PollEnd"; title: string; winning_choice: string; votes: number }
+  | { type: "Generic"; event_type: string; payload: unknown }
+  | {
+      type: "SuperChat";
+      user_name: string;
+      amount_micros: number;
+      currency: string;
+      usd_cents?: number;
+      message: string;
+      sticker?: string;
+    }
+  | {
+      type: "Membership";
+      user_name: string;
+      level: string;
+      months: number;
+      gifts: number;
+    }
+  | {
+      type: "Donation";
+      user_name: string;
+      amount: number;
+      currency: string;
+      usd_cents?: number;
+      message: string;
+    }
+  | {
+      type: "Webhook";
+      source: string;
+      amount: number;
+      currency: string;
+      usd_cents?: number;
+      user: string;
+      message: string;
+      payload: unknown;
+    }
+  | {
+      type: "SlashCommand";
+      name: string;
+      user_name: string;
+      user_id: string;
+      roles: string[];
+      options: [string, string][];
+      application_id: string;
+      interaction_id: string;
+      token: string;
+    }
+  | {
+      type: "Pledge";
+      action: PledgeAction;
+      tier: string;
+      amount_cents: number;
+      user: string;
+    }
+  | {
+      type: "HypeTrain";
+      state: HypeTrainState;
+      id: string;
+      level: number;
+      progress: number;
+      goal: number;
+      total: number;
+      expires_at: string;
+      level_increased: boolean;
+    };
+
+export type HypeTrainState = "Begin" | "Progress" | "End";
+export type PledgeAction = "Create" | "Update" | "Delete";
 
 export type Command = {
   name: string;
   rcon_lua: RconCommand;
   server_triggers: GameServerTrigger[];
+  reward?: Reward;
+  reply: Reply;
+  allowed_users: string[];
+  quota?: Quota;
+};
+export type Quota = {
+  max_triggers?: number;
+  max_value_cents?: number;
+  window_minutes: number;
+  per_stream: boolean;
+  over_quota: "Drop" | "Approval";
+};
+export type Viewer = {
+  user_id: string;
+  user_name: string;
+};
+export type ImportReport = {
+  profile: string;
+  commands: Command[];
+  unmapped: string[];
+};
+export type Reply = {
+  success?: string;
+  error?: string;
+};
+export type Reward = {
+  id?: string;
+  title: string;
+  cost: number;
+  prompt?: string;
+  is_user_input_required: boolean;
+  global_cooldown?: number;
 };
 export type CommandLog = {
   time: Date;
@@ -169,6 +374,26 @@ export type CommandLog = {
   trigger: GameServerTrigger;
   message?: string;
   username?: string;
+  status: CommandStatus;
+  variables: Record<string, string>;
+  /// Command picked by a mystery box.
+  pick?: string;
+};
+export type CommandStatus =
+  | "Sent"
+  | "AwaitingApproval"
+  | "Approved"
+  | { Rejected: { refunded: boolean } }
+  | { Blocked: { reason: string } }
+  | { OverQuota: { reason: string } };
+export type PendingApproval = {
+  id: string;
+  received: Date;
+  command: Command;
+  trigger: GameServerTrigger;
+  event: IntegrationEvent;
+  variables: Record<string, string>;
+  reported: boolean;
 };
 
 /// Make sure left side is equal to right.
@@ -177,6 +402,7 @@ export enum Api {
   YouTube = "YouTube",
   Patreon = "Patreon",
   StreamLabs = "StreamLabs",
+  Discord = "Discord",
 }
 export type ApiString = keyof typeof Api;
 
@@ -216,7 +442,14 @@ export type IntegrationStatus =
 export type IntegrationError =
   | { error: "Token"; data: TokenError }
   | { error: "NotImplemented"; data: Api }
-  | { error: "Unknown" };
+  | { error: "Unknown" }
+  | { error: "WsError" }
+  | { error: "Subscriptions"; data: string[] };
+
+/** Counters returned by `get_metrics` and `/api/metrics`. */
+export type MetricsSnapshot = {
+  duplicates_dropped: number;
+};
 
 /** nanos_since_epoch are nanoseconds since the second.
  *